@@ -0,0 +1,360 @@
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    expand::SourceMap,
+    plateau::{Feature, Plateau},
+    position::Position,
+    rover::{Mission, Rover},
+};
+
+/// The tag marking a cell of a world file a rover is meant to reach
+pub const GOAL: &str = "goal";
+
+/// An issue found by statically analysing a mission, without executing it
+#[derive(Debug, PartialEq)]
+pub enum Finding {
+    /// The rover's instructions bring it back to where it started
+    ReturnsToStart { rover: usize },
+    /// The rover leaves the plateau whatever happens, at the given instruction
+    ExitsPlateau { rover: usize, instruction: usize },
+    /// The rover starts on the same cell as an earlier rover
+    DuplicateStart {
+        rover: usize,
+        other: usize,
+        position: Position,
+    },
+    /// No rover passes over a cell tagged as a goal, given on the line of the world file
+    UnreachableGoal { position: Position, line: usize },
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::ReturnsToStart { rover } => write!(
+                f,
+                "Rover Analysis 🔎 - Rover {} returns to its starting position",
+                rover
+            ),
+            Finding::ExitsPlateau { rover, instruction } => write!(
+                f,
+                "Rover Analysis 🔎 - Rover {} is guaranteed to exit the plateau: Instruction {}",
+                rover,
+                instruction + 1
+            ),
+            Finding::DuplicateStart {
+                rover,
+                other,
+                position,
+            } => write!(
+                f,
+                "Rover Analysis 🔎 - Rover {} starts at ({}, {}), the same position as rover {}",
+                rover, position.x, position.y, other
+            ),
+            Finding::UnreachableGoal { position, .. } => write!(
+                f,
+                "Rover Analysis 🔎 - No rover reaches the goal at ({}, {})",
+                position.x, position.y
+            ),
+        }
+    }
+}
+
+impl Finding {
+    /// Point the finding at the line it was found on, see [`Located`]
+    pub fn locate(self, sources: &SourceMap, world: Option<&Path>) -> Located {
+        // The ID of the rover starts from one
+        let source = match &self {
+            Finding::ReturnsToStart { rover } => sources
+                .rovers
+                .get(rover - 1)
+                .and_then(|instructions| instructions.first()),
+            Finding::ExitsPlateau { rover, instruction } => sources
+                .rovers
+                .get(rover - 1)
+                .and_then(|instructions| instructions.get(*instruction)),
+            Finding::DuplicateStart { rover, .. } => sources.starts.get(rover - 1),
+            Finding::UnreachableGoal { line, .. } => {
+                let line = Some(*line);
+                return Located {
+                    finding: self,
+                    file: world.map(Path::to_path_buf),
+                    line,
+                };
+            }
+        };
+
+        Located {
+            file: source.and_then(|source| sources.named_file(source)),
+            line: source.map(|source| source.line),
+            finding: self,
+        }
+    }
+}
+
+/// A finding, with where it was written
+#[derive(Debug, PartialEq)]
+pub struct Located {
+    pub finding: Finding,
+    /// The file it was written in, unless it is the mission file itself
+    pub file: Option<PathBuf>,
+    /// The index of the line it was written on
+    pub line: Option<usize>,
+}
+
+impl Display for Located {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        write!(f, "{}", self.finding)?;
+        if let Some(line) = self.line {
+            write!(f, ", At Line: {}", line + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Statically analyse a parsed mission, along with the world file it names
+pub fn analyze(mission: &Mission, plateau: Option<&Plateau>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut starts: Vec<(usize, Position)> = Vec::new();
+    let mut visited = Vec::new();
+
+    for (index, (pose, instructions, _)) in mission.rovers.iter().enumerate() {
+        // The ID of the rover should start from one
+        let id = index + 1;
+
+        if let Some((other, _)) = starts.iter().find(|(_, start)| *start == pose.position) {
+            findings.push(Finding::DuplicateStart {
+                rover: id,
+                other: *other,
+                position: pose.position,
+            });
+        }
+        starts.push((id, pose.position));
+
+        let mut rover = Rover::new(id, *pose);
+        visited.push(pose.position);
+        let exit = instructions.iter().position(|instruction| {
+            rover.step(instruction);
+            let exited = rover.has_crossed_boundery(Some(mission.bounderies));
+            if !exited {
+                visited.push(rover.pose.position);
+            }
+            exited
+        });
+
+        match exit {
+            Some(instruction) => findings.push(Finding::ExitsPlateau {
+                rover: id,
+                instruction,
+            }),
+            None if !instructions.is_empty() && rover.pose.position == pose.position => {
+                findings.push(Finding::ReturnsToStart { rover: id })
+            }
+            None => (),
+        }
+    }
+
+    let goals = plateau.into_iter().flat_map(|plateau| &plateau.features);
+    for (line, feature) in goals {
+        if let Feature::Tag(position, tag) = feature {
+            if tag == GOAL && !visited.contains(position) {
+                findings.push(Finding::UnreachableGoal {
+                    position: *position,
+                    line: *line,
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod analyze_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod analyze {
+        use super::{analyze, Finding};
+        use crate::{
+            enums::{Direction, Instruction},
+            plateau::Plateau,
+            position::{Pose, Position},
+            rover::{Attributes, Mission},
+        };
+
+        #[test]
+        fn no_findings() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![(
+                    Pose::new(Position::new(1, 1), Direction::North),
+                    vec![Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            assert!(analyze(&mission, None).is_empty());
+        }
+
+        #[test]
+        fn returns_to_start() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![(
+                    Pose::new(Position::new(1, 1), Direction::North),
+                    vec![
+                        Instruction::Move,
+                        Instruction::Right,
+                        Instruction::Right,
+                        Instruction::Move,
+                    ],
+                    Attributes::default(),
+                )],
+            };
+            assert_eq!(
+                analyze(&mission, None),
+                vec![Finding::ReturnsToStart { rover: 1 }]
+            );
+        }
+
+        #[test]
+        fn exits_plateau() {
+            let mission = Mission {
+                bounderies: Position::new(2, 2),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::North),
+                    vec![Instruction::Left, Instruction::Move, Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            assert_eq!(
+                analyze(&mission, None),
+                vec![Finding::ExitsPlateau {
+                    rover: 1,
+                    instruction: 1
+                }]
+            );
+        }
+
+        #[test]
+        fn duplicate_start() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(1, 1), Direction::North),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(1, 1), Direction::East),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            assert_eq!(
+                analyze(&mission, None),
+                vec![Finding::DuplicateStart {
+                    rover: 2,
+                    other: 1,
+                    position: Position::new(1, 1)
+                }]
+            );
+        }
+
+        #[test]
+        fn unreachable_goal() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![(
+                    Pose::new(Position::new(1, 1), Direction::North),
+                    vec![Instruction::Move, Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            let plateau = Plateau::parse("5 5\ntag 1,3=goal\ntag 4,4=goal\ntag 0,0=base").unwrap();
+            assert_eq!(
+                analyze(&mission, Some(&plateau)),
+                vec![Finding::UnreachableGoal {
+                    position: Position::new(4, 4),
+                    line: 2
+                }]
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod locate {
+        use std::path::{Path, PathBuf};
+
+        use super::Finding;
+        use crate::{
+            expand::{Provenance, SourceMap},
+            position::Position,
+        };
+
+        fn sources() -> SourceMap {
+            let written = |file: &str, line| Provenance {
+                file: Some(PathBuf::from(file)),
+                line,
+                expansion: None,
+            };
+            SourceMap {
+                rovers: vec![vec![written("rover.txt", 4), written("launch.txt", 0)]],
+                starts: vec![written("rover.txt", 3)],
+                implied: Some(PathBuf::from("rover.txt")),
+                variables: Vec::new(),
+                included: Vec::new(),
+            }
+        }
+
+        #[test]
+        fn mission_file() {
+            let finding = Finding::DuplicateStart {
+                rover: 1,
+                other: 1,
+                position: Position::new(1, 1),
+            };
+            assert!(finding
+                .locate(&sources(), None)
+                .to_string()
+                .starts_with("Rover Analysis 🔎 - Rover 1 starts at (1, 1)"));
+
+            let located = Finding::ReturnsToStart { rover: 1 }.locate(&sources(), None);
+            assert_eq!((located.file, located.line), (None, Some(4)));
+        }
+
+        #[test]
+        fn included_file() {
+            let finding = Finding::ExitsPlateau {
+                rover: 1,
+                instruction: 1,
+            };
+            assert_eq!(
+                finding.locate(&sources(), None).to_string(),
+                "launch.txt: Rover Analysis 🔎 - Rover 1 is guaranteed to exit the plateau: \
+                 Instruction 2, At Line: 1"
+            );
+        }
+
+        #[test]
+        fn world_file() {
+            let finding = Finding::UnreachableGoal {
+                position: Position::new(4, 4),
+                line: 2,
+            };
+            assert_eq!(
+                finding
+                    .locate(&sources(), Some(Path::new("mars.world")))
+                    .to_string(),
+                "mars.world: Rover Analysis 🔎 - No rover reaches the goal at (4, 4), At Line: 3"
+            );
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    diagnostics::Diagnostics, enums::RoverErr, rover::RoverControlSatellite,
+    simulation::Simulation, trace::Trace,
+};
+
+/// How long parsing and executing a workload took over a number of iterations
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub iterations: usize,
+    pub rovers: usize,
+    /// The instructions in the workload, counted once rather than per iteration
+    pub instructions: usize,
+    pub parse: Duration,
+    pub execute: Duration,
+}
+
+impl BenchResult {
+    /// Instructions parsed per second
+    pub fn parse_throughput(&self) -> f64 {
+        throughput(self.instructions * self.iterations, self.parse)
+    }
+
+    /// Instructions executed per second
+    pub fn execute_throughput(&self) -> f64 {
+        throughput(self.instructions * self.iterations, self.execute)
+    }
+}
+
+impl Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Workload - Iterations: {}, Rovers: {}, Instructions: {}",
+            self.iterations, self.rovers, self.instructions
+        )?;
+        writeln!(
+            f,
+            "Parse - Total: {:.3}ms, Per Iteration: {:.3}ms, Instructions/s: {:.0}",
+            millis(self.parse),
+            millis(self.parse) / self.iterations.max(1) as f64,
+            self.parse_throughput()
+        )?;
+        write!(
+            f,
+            "Execute - Total: {:.3}ms, Per Iteration: {:.3}ms, Instructions/s: {:.0}",
+            millis(self.execute),
+            millis(self.execute) / self.iterations.max(1) as f64,
+            self.execute_throughput()
+        )
+    }
+}
+
+fn throughput(instructions: usize, elapsed: Duration) -> f64 {
+    match elapsed.is_zero() {
+        true => 0.0,
+        false => instructions as f64 / elapsed.as_secs_f64(),
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Parse and execute a workload `iterations` times with the simulation's settings, timing each
+/// stage separately
+///
+/// Every iteration starts from a fresh world, so lost rover scents do not carry between them.
+pub fn bench(
+    simulation: &Simulation,
+    message: &str,
+    iterations: usize,
+) -> Result<BenchResult, RoverErr> {
+    let mut result = BenchResult {
+        iterations,
+        rovers: 0,
+        instructions: 0,
+        parse: Duration::ZERO,
+        execute: Duration::ZERO,
+    };
+
+    for _ in 0..iterations {
+        let mut diagnostics = Diagnostics::default();
+
+        let start = Instant::now();
+        let mission = RoverControlSatellite::parse_incoming_message(
+            message.to_string(),
+            simulation.locale,
+            &mut diagnostics,
+        )?;
+        result.parse += start.elapsed();
+
+        result.rovers = mission.rovers.len();
+        result.instructions = mission
+            .rovers
+            .iter()
+            .map(|(_, instructions, _)| instructions.len())
+            .sum();
+
+        let mut world = simulation.world();
+        let start = Instant::now();
+        RoverControlSatellite::execute_mission(
+            &mission,
+            &mut world,
+            &mut simulation.budget(),
+            &mut diagnostics,
+            &mut Trace::default(),
+        )?;
+        result.execute += start.elapsed();
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod bench_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod bench {
+        use super::bench;
+        use crate::{enums::RoverErr, simulation::Simulation};
+
+        #[test]
+        fn valid_input() {
+            let result = bench(
+                &Simulation::default(),
+                "5 5\n1 2 N\nLMLMLMLMM\n3 3 E\nMMRMMRMRRM",
+                3,
+            )
+            .unwrap();
+            assert_eq!(
+                (result.iterations, result.rovers, result.instructions),
+                (3, 2, 19)
+            );
+        }
+
+        #[test]
+        fn failing_workload() {
+            let result = bench(&Simulation::default(), "5 5\n1 2 N\nMMMMMM", 3);
+            assert!(matches!(result, Err(RoverErr::Boundery(_, _))));
+        }
+    }
+}
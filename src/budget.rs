@@ -0,0 +1,118 @@
+use std::{
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+/// A limit on how much work a single mission may do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Instructions,
+    Time,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Limit::Instructions => "instruction",
+                Limit::Time => "time",
+            }
+        )
+    }
+}
+
+/// How many spends pass between readings of the clock, reading it is slow next to a step
+const CLOCK_INTERVAL: usize = 256;
+
+/// How many instructions a mission may execute, and until when, shared by all of its rovers
+///
+/// The default budget is unlimited.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Budget {
+    instructions: Option<usize>,
+    deadline: Option<Instant>,
+    /// How many spends have passed since the clock was last read
+    unchecked: usize,
+}
+
+impl Budget {
+    /// Start a budget, the time limit counts from now
+    pub fn new(instructions: Option<usize>, time: Option<Duration>) -> Self {
+        Budget {
+            instructions,
+            deadline: time.map(|time| Instant::now() + time),
+            unchecked: 0,
+        }
+    }
+
+    /// How many more instructions may be executed
+    pub fn remaining_instructions(&self) -> usize {
+        self.instructions.unwrap_or(usize::MAX)
+    }
+
+    /// Spend some instructions, or find which limit has been reached
+    ///
+    /// The time limit is checked on the first spend, and then every [`CLOCK_INTERVAL`] spends.
+    pub fn spend(&mut self, count: usize) -> Result<(), Limit> {
+        if let Some(deadline) = self.deadline {
+            if self.unchecked == 0 && Instant::now() >= deadline {
+                return Err(Limit::Time);
+            }
+            self.unchecked = (self.unchecked + 1) % CLOCK_INTERVAL;
+        }
+
+        match &mut self.instructions {
+            Some(remaining) if *remaining < count => Err(Limit::Instructions),
+            Some(remaining) => {
+                *remaining -= count;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod budget_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod spend {
+        use std::time::Duration;
+
+        use super::{Budget, Limit, CLOCK_INTERVAL};
+
+        #[test]
+        fn instructions() {
+            let mut budget = Budget::new(Some(3), None);
+            assert_eq!(budget.spend(2), Ok(()));
+            assert_eq!(budget.spend(2), Err(Limit::Instructions));
+            assert_eq!(budget.spend(1), Ok(()));
+            assert_eq!(budget.remaining_instructions(), 0);
+        }
+
+        #[test]
+        fn time() {
+            let mut budget = Budget::new(None, Some(Duration::ZERO));
+            assert_eq!(budget.spend(1), Err(Limit::Time));
+        }
+
+        #[test]
+        fn time_checked_on_interval() {
+            let mut budget = Budget::new(None, Some(Duration::ZERO));
+            budget.unchecked = 1;
+            for _ in 1..CLOCK_INTERVAL {
+                assert_eq!(budget.spend(1), Ok(()));
+            }
+            assert_eq!(budget.spend(1), Err(Limit::Time));
+        }
+
+        #[test]
+        fn unlimited() {
+            let mut budget = Budget::default();
+            assert_eq!(budget.spend(usize::MAX), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, Arg, ArgMatches, Command, ValueEnum};
+
+use crate::{
+    diagnostics::{Level, Policy, WarningCode},
+    enums::RoverErr,
+};
+
+/// Where the value of a setting came from, later sources take precedence
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    Default,
+    /// A `key = value` line of the config file
+    ConfigFile(PathBuf),
+    /// A `MARS_ROVER_*` environment variable
+    Environment(String),
+    CommandLine,
+}
+
+/// The effective value of a setting that can be given through the environment
+#[derive(Debug, PartialEq)]
+pub struct Setting {
+    pub name: String,
+    pub value: String,
+    pub source: Source,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::ConfigFile(path) => write!(f, "config file: {}", path.display()),
+            Source::Environment(var) => write!(f, "environment: {}", var),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+impl Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {} ({})", self.name, self.value, self.source)
+    }
+}
+
+/// A config file of `key = value` settings
+#[derive(Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    text: String,
+}
+
+impl ConfigFile {
+    pub fn open(path: &Path) -> Result<ConfigFile, RoverErr> {
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            text: fs::read_to_string(path).map_err(RoverErr::OpeningConfig)?,
+        })
+    }
+
+    /// The diagnostics policy the file sets, see `read_policy`
+    pub fn policy(&self) -> Result<Policy, RoverErr> {
+        read_policy(&self.text)
+    }
+
+    /// The value the file gives a setting, the values of a key set on several lines are joined
+    fn value(&self, name: &str) -> Option<String> {
+        let values = self
+            .text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim())
+            .collect::<Vec<_>>();
+
+        (!values.is_empty()).then(|| values.join(","))
+    }
+}
+
+/// List every setting that can be overridden by an environment variable, with its merged value
+///
+/// The variables are looked up through `env`, which takes precedence over the config file. A
+/// value given on the command line still wins.
+pub fn settings(
+    command: &Command,
+    matches: &ArgMatches,
+    env: &dyn Fn(&str) -> Option<String>,
+    config: Option<&ConfigFile>,
+) -> Vec<Setting> {
+    command
+        .get_arguments()
+        .filter_map(|arg| Some((arg, arg.get_env()?.to_string_lossy().into_owned())))
+        .map(|(arg, var)| {
+            let setting = setting(arg, matches);
+            let from_config =
+                config.and_then(|config| Some((config.value(&setting.name)?, config.path.clone())));
+            match (&setting.source, env(&var), from_config) {
+                (Source::CommandLine, _, _) => setting,
+                (_, Some(value), _) => Setting {
+                    value,
+                    source: Source::Environment(var),
+                    ..setting
+                },
+                (_, None, Some((value, path))) => Setting {
+                    value,
+                    source: Source::ConfigFile(path),
+                    ..setting
+                },
+                (_, None, None) => Setting {
+                    source: Source::Default,
+                    ..setting
+                },
+            }
+        })
+        .collect()
+}
+
+/// List every setting that was given a value, however it was given
+pub fn effective(command: &Command, matches: &ArgMatches) -> Vec<Setting> {
+    command
+        .get_arguments()
+        .filter(|arg| matches.get_raw(arg.get_id().as_str()).is_some())
+        .map(|arg| setting(arg, matches))
+        .collect()
+}
+
+fn setting(arg: &Arg, matches: &ArgMatches) -> Setting {
+    let name = arg.get_id().as_str();
+
+    let value = matches
+        .get_raw(name)
+        .map(|values| {
+            values
+                .map(|value| value.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+    let source = match (matches.value_source(name), arg.get_env()) {
+        (Some(ValueSource::EnvVariable), Some(var)) => {
+            Source::Environment(var.to_string_lossy().into_owned())
+        }
+        (Some(ValueSource::CommandLine), _) => Source::CommandLine,
+        _ => Source::Default,
+    };
+
+    Setting {
+        // Show the name the way it is written as a flag
+        name: name.replace('_', "-"),
+        value,
+        source,
+    }
+}
+
+/// Read the diagnostics policy from a config file, one `key = value` setting per line
+///
+/// `deny-warnings` is `true` or `false`, and `allow`, `warn` and `deny` list warning codes
+/// separated by commas. As with the flags, `deny` takes precedence over `warn` and `warn` over
+/// `allow`. Blank lines and lines starting with `#` are skipped.
+pub fn read_policy(text: &str) -> Result<Policy, RoverErr> {
+    let mut policy = Policy::default();
+    let mut levels = Vec::new();
+
+    for (index, line) in text.lines().enumerate().map(|(i, line)| (i, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or(RoverErr::ConfigParse(index))?;
+        let level = match (key.trim(), value.trim()) {
+            ("deny-warnings", "true") => {
+                policy.default = Level::Deny;
+                continue;
+            }
+            ("deny-warnings", "false") => {
+                policy.default = Level::Warn;
+                continue;
+            }
+            ("allow", _) => Level::Allow,
+            ("warn", _) => Level::Warn,
+            ("deny", _) => Level::Deny,
+            _ => return Err(RoverErr::ConfigParse(index)),
+        };
+        let codes = value
+            .split(',')
+            .map(|code| WarningCode::from_str(code.trim(), true))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| RoverErr::ConfigParse(index))?;
+        levels.push((level, codes));
+    }
+
+    for level in [Level::Allow, Level::Warn, Level::Deny] {
+        for (_, codes) in levels.iter().filter(|(other, _)| *other == level) {
+            for code in codes {
+                policy.set(*code, level);
+            }
+        }
+    }
+
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod config_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod settings {
+        use std::{env, fs};
+
+        use clap::{Arg, ArgAction, Command};
+
+        use super::{effective, settings, ConfigFile, Source};
+
+        #[test]
+        fn merged_sources() {
+            let env = |var: &str| match var {
+                "MARS_ROVER_TEST_SORT" => Some("x".to_string()),
+                "MARS_ROVER_TEST_MARGIN" => Some("2".to_string()),
+                _ => None,
+            };
+            let command = Command::new("rover-cli")
+                .arg(Arg::new("sort").long("sort").env("MARS_ROVER_TEST_SORT"))
+                .arg(
+                    Arg::new("safety_margin")
+                        .long("safety-margin")
+                        .env("MARS_ROVER_TEST_MARGIN"),
+                )
+                .arg(
+                    Arg::new("unbounded")
+                        .long("unbounded")
+                        .action(ArgAction::SetTrue)
+                        .env("MARS_ROVER_TEST_UNBOUNDED"),
+                )
+                .arg(Arg::new("output").long("output"));
+            let matches = command
+                .clone()
+                .try_get_matches_from(["rover-cli", "--safety-margin", "1"])
+                .unwrap();
+
+            let result = settings(&command, &matches, &env, None)
+                .into_iter()
+                .map(|setting| (setting.name, setting.value, setting.source))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                result,
+                vec![
+                    (
+                        "sort".to_string(),
+                        "x".to_string(),
+                        Source::Environment("MARS_ROVER_TEST_SORT".to_string())
+                    ),
+                    (
+                        "safety-margin".to_string(),
+                        "1".to_string(),
+                        Source::CommandLine
+                    ),
+                    (
+                        "unbounded".to_string(),
+                        "false".to_string(),
+                        Source::Default
+                    ),
+                ]
+            );
+        }
+
+        #[test]
+        fn config_file() {
+            let config_path =
+                env::temp_dir().join(format!("rover-settings-{}.conf", std::process::id()));
+            fs::write(
+                &config_path,
+                "deny-warnings = true\ndeny = halted\ndeny = degraded\nallow = no-op-turns",
+            )
+            .unwrap();
+            let config = ConfigFile::open(&config_path).unwrap();
+            let env = |var: &str| match var {
+                "MARS_ROVER_TEST_ALLOW" => Some("halted".to_string()),
+                _ => None,
+            };
+            let command = Command::new("rover-cli")
+                .arg(
+                    Arg::new("deny_warnings")
+                        .long("deny-warnings")
+                        .action(ArgAction::SetTrue)
+                        .env("MARS_ROVER_TEST_DENY_WARNINGS"),
+                )
+                .arg(Arg::new("allow").long("allow").env("MARS_ROVER_TEST_ALLOW"))
+                .arg(Arg::new("deny").long("deny").env("MARS_ROVER_TEST_DENY"))
+                .arg(Arg::new("warn").long("warn").env("MARS_ROVER_TEST_WARN"));
+            let matches = command
+                .clone()
+                .try_get_matches_from(["rover-cli", "--warn", "halted"])
+                .unwrap();
+
+            let result = settings(&command, &matches, &env, Some(&config))
+                .into_iter()
+                .map(|setting| (setting.name, setting.value, setting.source))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                result,
+                vec![
+                    (
+                        "deny-warnings".to_string(),
+                        "true".to_string(),
+                        Source::ConfigFile(config_path.clone())
+                    ),
+                    (
+                        "allow".to_string(),
+                        "halted".to_string(),
+                        Source::Environment("MARS_ROVER_TEST_ALLOW".to_string())
+                    ),
+                    (
+                        "deny".to_string(),
+                        "halted,degraded".to_string(),
+                        Source::ConfigFile(config_path.clone())
+                    ),
+                    (
+                        "warn".to_string(),
+                        "halted".to_string(),
+                        Source::CommandLine
+                    ),
+                ]
+            );
+
+            fs::remove_file(config_path).unwrap();
+        }
+
+        #[test]
+        fn effective_settings() {
+            let command = Command::new("rover-cli")
+                .arg(Arg::new("output").long("output"))
+                .arg(Arg::new("trace").long("trace"))
+                .arg(
+                    Arg::new("seed")
+                        .long("seed")
+                        .default_value("0")
+                        .env("MARS_ROVER_TEST_SEED"),
+                );
+            let matches = command
+                .clone()
+                .try_get_matches_from(["rover-cli", "--output", "out.txt"])
+                .unwrap();
+
+            let result = effective(&command, &matches)
+                .into_iter()
+                .map(|setting| setting.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                result,
+                vec!["output = out.txt (command line)", "seed = 0 (default)"]
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod read_policy {
+        use super::read_policy;
+        use crate::{
+            diagnostics::{Level, WarningCode},
+            enums::RoverErr,
+        };
+
+        #[test]
+        fn valid_input() {
+            let policy = read_policy(
+                "# Fail on anything near the edge\n\
+                 deny-warnings = true\n\
+                 \n\
+                 deny = near-boundery, ended-on-boundery\n\
+                 allow = no-op-turns,near-boundery",
+            )
+            .unwrap();
+            assert_eq!(policy.default, Level::Deny);
+            assert_eq!(policy.level(WarningCode::NearBoundery), Level::Deny);
+            assert_eq!(policy.level(WarningCode::EndedOnBoundery), Level::Deny);
+            assert_eq!(policy.level(WarningCode::NoOpTurns), Level::Allow);
+            assert_eq!(policy.level(WarningCode::Halted), Level::Deny);
+        }
+
+        #[test]
+        fn invalid_input() {
+            for (text, line) in [
+                ("allow = no-op-turns\ndeny = edges", 1),
+                ("deny-warnings = yes", 0),
+                ("\nsort = x", 1),
+                ("deny near-boundery", 0),
+            ] {
+                assert!(
+                    matches!(read_policy(text), Err(RoverErr::ConfigParse(index)) if index == line)
+                );
+            }
+        }
+    }
+}
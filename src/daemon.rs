@@ -0,0 +1,295 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    enums::RoverErr,
+    expand::{Defines, Expansion},
+    input::{self, MissionText},
+    rover::RoverControlSatellite,
+    simulation::Simulation,
+};
+
+/// The subdirectory of the spool that processed instructions files are moved to
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// The subdirectory of the spool that instructions files which could not be processed are moved to
+pub const FAILED_DIR: &str = "failed";
+
+/// Watches a spool directory and executes every instructions file that appears in it
+pub struct SpoolDaemon {
+    pub spool: PathBuf,
+    pub simulation: Simulation,
+}
+
+impl SpoolDaemon {
+    /// Poll the spool forever, or only once if `interval` is `None`
+    ///
+    /// A file that can not be processed is logged and moved to the failed directory, so one bad
+    /// file does not stop the daemon. If it can not be moved either, it is skipped from then on.
+    pub fn run(&self, interval: Option<Duration>) -> Result<(), RoverErr> {
+        fs::create_dir_all(self.spool.join(ARCHIVE_DIR)).map_err(RoverErr::Opening)?;
+        fs::create_dir_all(self.spool.join(FAILED_DIR)).map_err(RoverErr::Opening)?;
+        let mut stuck = HashSet::new();
+
+        loop {
+            for path in self.pending()? {
+                if stuck.contains(&path) {
+                    continue;
+                }
+                if let Err(err) = self.process(&path) {
+                    eprintln!("{}: {err}", path.display());
+                    let failed = self
+                        .spool
+                        .join(FAILED_DIR)
+                        .join(path.file_name().unwrap_or_default());
+                    if let Err(e) = fs::rename(&path, failed) {
+                        eprintln!("{}: {}", path.display(), RoverErr::Archiving(e));
+                        stuck.insert(path);
+                    }
+                }
+            }
+
+            match interval {
+                Some(interval) => thread::sleep(interval),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// The instructions files waiting in the spool, in name order
+    pub fn pending(&self) -> Result<Vec<PathBuf>, RoverErr> {
+        let mut paths = fs::read_dir(&self.spool)
+            .map_err(RoverErr::Reading)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && is_mission_file(path))
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    /// Execute an instructions file, write its `.result`, `.budget` or `.error` and archive it
+    ///
+    /// A mission that runs out of budget has its error followed by the rovers as they were when
+    /// it stopped.
+    pub fn process(&self, path: &Path) -> Result<(), RoverErr> {
+        // A file that cannot be read is reported like any other failed mission
+        let (result, finished) = match self.open(path) {
+            Ok((expansion, simulation)) => {
+                let outcome = simulation.run(&expansion.text);
+                for warning in outcome.warnings {
+                    eprintln!("{}: {}", path.display(), expansion.locate_warning(warning));
+                }
+                for event in &outcome.world.events {
+                    eprintln!("{}: {event}", path.display());
+                }
+                (
+                    outcome.result.map_err(|err| expansion.locate(err)),
+                    outcome.world.rovers,
+                )
+            }
+            Err(err) => (Err(err), Vec::new()),
+        };
+
+        let (extension, contents) = match result {
+            Ok(rovers) => ("result", RoverControlSatellite::format_report(&rovers)),
+            Err(RoverErr::BudgetExceeded(rover, instruction, limit)) => {
+                let mut partial = finished;
+                partial.push(rover.clone());
+                partial.sort_by_key(|rover| rover.id);
+                let err = RoverErr::BudgetExceeded(rover, instruction, limit);
+                (
+                    "budget",
+                    format!("{err}\n{}", RoverControlSatellite::format_report(&partial)),
+                )
+            }
+            Err(err) => ("error", err.to_string()),
+        };
+        fs::write(with_added_extension(path, extension), contents).map_err(RoverErr::Saving)?;
+
+        let archived = self
+            .spool
+            .join(ARCHIVE_DIR)
+            .join(path.file_name().unwrap_or_default());
+        fs::rename(path, archived).map_err(RoverErr::Archiving)
+    }
+}
+
+impl SpoolDaemon {
+    /// Read an instructions file and expand its macros, along with the simulation to run it with
+    /// once the world file it names is added
+    fn open(&self, path: &Path) -> Result<(Expansion, Simulation), RoverErr> {
+        let texts = vec![MissionText {
+            path: Some(path.to_path_buf()),
+            text: fs::read_to_string(path).map_err(RoverErr::Reading)?,
+            origins: None,
+        }];
+        let (texts, plateaus) = input::prepare(texts, None, &Defines::new())?;
+        let MissionText {
+            path,
+            text,
+            origins,
+        } = &texts[0];
+        let expansion = input::expand(text, path.as_deref(), origins.as_deref(), &Defines::new())?;
+        Ok((expansion, self.simulation.with_plateaus(&plateaus)))
+    }
+}
+
+/// Whether a file in the spool is an instructions file waiting to be processed
+///
+/// Hidden files are skipped so that writers can create `.name` and rename it once complete.
+pub fn is_mission_file(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.'));
+    let output = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("result" | "budget" | "error")
+    );
+
+    !hidden && !output
+}
+
+/// Append an extension, so `foo.txt` becomes `foo.txt.result`
+fn with_added_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    path.into()
+}
+
+#[cfg(test)]
+mod daemon_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod is_mission_file {
+        use std::path::Path;
+
+        use super::is_mission_file;
+
+        #[test]
+        fn valid_input() {
+            assert!(is_mission_file(Path::new("spool/foo.txt")));
+            assert!(is_mission_file(Path::new("spool/foo")));
+        }
+
+        #[test]
+        fn output_files() {
+            assert!(!is_mission_file(Path::new("spool/foo.txt.result")));
+            assert!(!is_mission_file(Path::new("spool/foo.txt.budget")));
+            assert!(!is_mission_file(Path::new("spool/foo.txt.error")));
+        }
+
+        #[test]
+        fn hidden_file() {
+            assert!(!is_mission_file(Path::new("spool/.foo.txt")));
+        }
+    }
+
+    #[cfg(test)]
+    mod run {
+        use std::{env, fs};
+
+        use super::{SpoolDaemon, ARCHIVE_DIR, FAILED_DIR};
+        use crate::simulation::Simulation;
+
+        #[test]
+        fn processes_and_archives() {
+            let spool = env::temp_dir().join(format!("rover-spool-{}", std::process::id()));
+            fs::create_dir_all(&spool).unwrap();
+            fs::write(spool.join("ok.txt"), "5 5\n1 2 N\nLMLMLMLMM").unwrap();
+            fs::write(spool.join("bad.txt"), "5 5\n1 2 N").unwrap();
+
+            let daemon = SpoolDaemon {
+                spool: spool.clone(),
+                simulation: Simulation::default(),
+            };
+            assert!(daemon.run(None).is_ok());
+
+            assert_eq!(
+                fs::read_to_string(spool.join("ok.txt.result")).unwrap(),
+                "1 3 N"
+            );
+            assert!(spool.join("bad.txt.error").is_file());
+            assert!(spool.join(ARCHIVE_DIR).join("ok.txt").is_file());
+            assert!(!spool.join("ok.txt").exists());
+
+            fs::remove_dir_all(spool).unwrap();
+        }
+
+        #[test]
+        fn keeps_going_after_a_failure() {
+            let spool = env::temp_dir().join(format!("rover-failed-{}", std::process::id()));
+            fs::create_dir_all(&spool).unwrap();
+            fs::write(spool.join("a.txt"), "5 5\n1 2 N\nM").unwrap();
+            fs::write(spool.join("b.txt"), "5 5\n1 2 N\nM").unwrap();
+            // The result of the first file can not be written over a directory
+            fs::create_dir_all(spool.join("a.txt.result")).unwrap();
+
+            let daemon = SpoolDaemon {
+                spool: spool.clone(),
+                simulation: Simulation::default(),
+            };
+            assert!(daemon.run(None).is_ok());
+
+            assert!(spool.join(FAILED_DIR).join("a.txt").is_file());
+            assert_eq!(
+                fs::read_to_string(spool.join("b.txt.result")).unwrap(),
+                "1 3 N"
+            );
+
+            fs::remove_dir_all(spool).unwrap();
+        }
+
+        #[test]
+        fn expands_includes() {
+            let spool = env::temp_dir().join(format!("rover-include-{}", std::process::id()));
+            fs::create_dir_all(spool.join("parts")).unwrap();
+            fs::write(spool.join("parts").join("rover.txt"), "1 2 N\nLMLMLMLMM").unwrap();
+            fs::write(spool.join("mission.txt"), "5 5\n!include parts/rover.txt").unwrap();
+
+            let daemon = SpoolDaemon {
+                spool: spool.clone(),
+                simulation: Simulation::default(),
+            };
+            assert!(daemon.run(None).is_ok());
+
+            assert_eq!(
+                fs::read_to_string(spool.join("mission.txt.result")).unwrap(),
+                "1 3 N"
+            );
+
+            fs::remove_dir_all(spool).unwrap();
+        }
+
+        #[test]
+        fn budget_exceeded() {
+            let spool = env::temp_dir().join(format!("rover-budget-{}", std::process::id()));
+            fs::create_dir_all(&spool).unwrap();
+            fs::write(spool.join("long.txt"), "5 5\n1 2 N\nM\n3 3 E\nMMRMMRMRRM").unwrap();
+
+            let daemon = SpoolDaemon {
+                spool: spool.clone(),
+                simulation: Simulation {
+                    instruction_budget: Some(3),
+                    ..Simulation::default()
+                },
+            };
+            assert!(daemon.run(None).is_ok());
+
+            let result = fs::read_to_string(spool.join("long.txt.budget")).unwrap();
+            let (err, report) = result.split_once('\n').unwrap();
+            assert!(err.contains("instruction budget ran out with rover 2"));
+            assert_eq!(report, "1 3 N\n5 3 E");
+
+            fs::remove_dir_all(spool).unwrap();
+        }
+    }
+}
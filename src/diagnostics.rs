@@ -0,0 +1,459 @@
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    enums::{Instruction, Recovery},
+    json,
+    position::Position,
+};
+
+/// The stable code of each kind of warning, used to choose how seriously it is taken
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum WarningCode {
+    NoOpTurns,
+    EndedOnBoundery,
+    NearBoundery,
+    Halted,
+    Degraded,
+    ServiceOffBase,
+    Intervention,
+    UnusedDefine,
+}
+
+impl Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How seriously a kind of warning is taken, like a lint level
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Level {
+    /// The warning is not reported
+    Allow,
+    #[default]
+    Warn,
+    /// The warning is reported and fails the mission once it has executed
+    Deny,
+}
+
+/// The level of each kind of warning, kinds without a level of their own take the default
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Policy {
+    pub default: Level,
+    levels: Vec<(WarningCode, Level)>,
+}
+
+impl Policy {
+    pub fn new(default: Level) -> Self {
+        Policy {
+            default,
+            levels: Vec::new(),
+        }
+    }
+
+    /// Set the level of a kind of warning, replacing any it had
+    pub fn set(&mut self, code: WarningCode, level: Level) {
+        self.levels.retain(|(other, _)| *other != code);
+        self.levels.push((code, level));
+    }
+
+    pub fn level(&self, code: WarningCode) -> Level {
+        self.levels
+            .iter()
+            .find(|(other, _)| *other == code)
+            .map_or(self.default, |(_, level)| *level)
+    }
+}
+
+/// A non-fatal issue found whilst parsing or executing the instructions file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// A pair of adjacent turns that cancel each other out (e.g. `LR`)
+    NoOpTurns { instruction: usize, line: usize },
+    /// A rover finished its instructions exactly on the edge of the plateau
+    EndedOnBoundery { rover: usize, position: Position },
+    /// A rover moved within the safety margin of the edge of the plateau
+    NearBoundery {
+        rover: usize,
+        position: Position,
+        instruction: usize,
+    },
+    /// A rover was halted before the end of its instructions
+    Halted {
+        rover: usize,
+        instruction: usize,
+        ignored: usize,
+    },
+    /// A rover moved further than its maintenance threshold without being serviced
+    Degraded {
+        rover: usize,
+        instruction: usize,
+        maintenance: usize,
+    },
+    /// A rover was told to service itself away from a base cell
+    ServiceOffBase {
+        rover: usize,
+        position: Position,
+        instruction: usize,
+    },
+    /// A rover in safe mode refused a move that would have been fatal
+    Intervention {
+        rover: usize,
+        position: Position,
+        instruction: usize,
+        recovery: Recovery,
+    },
+    /// A variable given with `--define` that the mission never uses
+    UnusedDefine { name: String },
+    /// A warning raised whilst executing, with the index of the line its instruction was
+    /// written on
+    OnLine { warning: Box<Warning>, line: usize },
+    /// A warning raised in a file other than the one the mission was read from
+    InFile {
+        path: PathBuf,
+        warning: Box<Warning>,
+    },
+}
+
+impl Warning {
+    pub fn code(&self) -> WarningCode {
+        match self {
+            Warning::OnLine { warning, .. } | Warning::InFile { warning, .. } => warning.code(),
+            Warning::NoOpTurns { .. } => WarningCode::NoOpTurns,
+            Warning::EndedOnBoundery { .. } => WarningCode::EndedOnBoundery,
+            Warning::NearBoundery { .. } => WarningCode::NearBoundery,
+            Warning::Halted { .. } => WarningCode::Halted,
+            Warning::Degraded { .. } => WarningCode::Degraded,
+            Warning::ServiceOffBase { .. } => WarningCode::ServiceOffBase,
+            Warning::Intervention { .. } => WarningCode::Intervention,
+            Warning::UnusedDefine { .. } => WarningCode::UnusedDefine,
+        }
+    }
+
+    /// Serialise the warning as a JSON object of its code and message
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"code": "{}", "message": "{}"}}"#,
+            self.code(),
+            json::escape(&self.to_string())
+        )
+    }
+
+    /// The ID of the rover that raised the warning whilst executing, and the index of its
+    /// instruction if the warning was raised by one
+    pub fn raised_by(&self) -> Option<(usize, Option<usize>)> {
+        match self {
+            Warning::NoOpTurns { .. }
+            | Warning::UnusedDefine { .. }
+            | Warning::InFile { .. }
+            | Warning::OnLine { .. } => None,
+            Warning::EndedOnBoundery { rover, .. } => Some((*rover, None)),
+            Warning::NearBoundery {
+                rover, instruction, ..
+            }
+            | Warning::Halted {
+                rover, instruction, ..
+            }
+            | Warning::Degraded {
+                rover, instruction, ..
+            }
+            | Warning::ServiceOffBase {
+                rover, instruction, ..
+            }
+            | Warning::Intervention {
+                rover, instruction, ..
+            } => Some((*rover, Some(*instruction))),
+        }
+    }
+
+    /// Replace the index of the line the warning was raised on
+    pub fn with_line_index(self, line_index: usize) -> Warning {
+        match self {
+            Warning::NoOpTurns { instruction, .. } => Warning::NoOpTurns {
+                instruction,
+                line: line_index,
+            },
+            Warning::OnLine { warning, .. } => Warning::OnLine {
+                warning,
+                line: line_index,
+            },
+            Warning::InFile { path, warning } => Warning::InFile {
+                path,
+                warning: Box::new(warning.with_line_index(line_index)),
+            },
+            warning => Warning::OnLine {
+                warning: Box::new(warning),
+                line: line_index,
+            },
+        }
+    }
+
+    /// Describe the warning, pointing at the line it was raised on, by default the rover's line
+    /// of a single instructions file
+    fn fmt_on_line(&self, f: &mut fmt::Formatter, line_index: Option<usize>) -> fmt::Result {
+        match self {
+            Warning::NoOpTurns { instruction, line } => write!(
+                f,
+                "Rover Warning ⚠️ - Turns cancel each other out: Instruction {}, At Line: {}",
+                instruction + 1,
+                line_index.unwrap_or(*line) + 1
+            ),
+            Warning::EndedOnBoundery { rover, position } => write!(
+                f,
+                "Rover Warning ⚠️ - Rover {} ended exactly on the plateau's boundery at position ({}, {}), At Line: {}",
+                rover,
+                position.x,
+                position.y,
+                line_index.unwrap_or(rover * 2) + 1
+            ),
+            Warning::NearBoundery {
+                rover,
+                position,
+                instruction,
+            } => write!(
+                f,
+                "Rover Warning ⚠️ - Rover {} came within the safety margin of the plateau's boundery at position ({}, {}): Tick {}, Instruction {}, At Line: {}",
+                rover,
+                position.x,
+                position.y,
+                // Tick zero is the starting position, so the first instruction ends on tick one
+                instruction + 1,
+                instruction + 1,
+                line_index.unwrap_or(rover * 2) + 1
+            ),
+            Warning::Halted {
+                rover,
+                instruction,
+                ignored,
+            } => write!(
+                f,
+                "Rover Warning ⚠️ - Rover {} was halted, {} remaining instruction(s) were ignored: Instruction {}, At Line: {}",
+                rover,
+                ignored,
+                instruction + 1,
+                line_index.unwrap_or(rover * 2) + 1
+            ),
+            Warning::Degraded {
+                rover,
+                instruction,
+                maintenance,
+            } => write!(
+                f,
+                "Rover Warning ⚠️ - Rover {} moved more than {} cell(s) without being serviced and is degraded: Instruction {}, At Line: {}",
+                rover,
+                maintenance,
+                instruction + 1,
+                line_index.unwrap_or(rover * 2) + 1
+            ),
+            Warning::ServiceOffBase {
+                rover,
+                position,
+                instruction,
+            } => write!(
+                f,
+                "Rover Warning ⚠️ - Rover {} can not be serviced at position ({}, {}), it is not a base: Instruction {}, At Line: {}",
+                rover,
+                position.x,
+                position.y,
+                instruction + 1,
+                line_index.unwrap_or(rover * 2) + 1
+            ),
+            Warning::Intervention {
+                rover,
+                position,
+                instruction,
+                recovery,
+            } => write!(
+                f,
+                "Rover Warning ⚠️ - Rover {} refused a fatal move from position ({}, {}) and {}: Instruction {}, At Line: {}",
+                rover,
+                position.x,
+                position.y,
+                match recovery {
+                    Recovery::Stop => "stopped",
+                    Recovery::TurnRight => "turned right to retry it",
+                },
+                instruction + 1,
+                line_index.unwrap_or(rover * 2) + 1
+            ),
+            Warning::UnusedDefine { name } => write!(
+                f,
+                "Rover Warning ⚠️ - {} was defined with --define but never used",
+                name
+            ),
+            warning => write!(f, "{warning}"),
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::OnLine { warning, line } => warning.fmt_on_line(f, Some(*line)),
+            Warning::InFile { path, warning } => write!(f, "{}: {}", path.display(), warning),
+            warning => warning.fmt_on_line(f, None),
+        }
+    }
+}
+
+/// Collects the warnings raised whilst a message is parsed and executed
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics {
+    warnings: Vec<Warning>,
+    policy: Policy,
+}
+
+impl Diagnostics {
+    /// Take each kind of warning as seriously as the policy says
+    pub fn with_policy(self, policy: Policy) -> Self {
+        Diagnostics { policy, ..self }
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Record a warning, unless the policy allows its kind
+    pub fn warn(&mut self, warning: Warning) {
+        if self.policy.level(warning.code()) != Level::Allow {
+            self.warnings.push(warning)
+        }
+    }
+
+    /// Add the warnings collected by another set of diagnostics, after this one's and as this
+    /// one's policy says
+    pub fn merge(&mut self, other: Diagnostics) {
+        for warning in other.warnings {
+            self.warn(warning);
+        }
+    }
+
+    /// Rewrite the warnings from index `from` onwards, e.g. to point them at where they were
+    /// written
+    pub fn relocate(&mut self, from: usize, locate: impl Fn(Warning) -> Warning) {
+        let relocated = self
+            .warnings
+            .split_off(from.min(self.warnings.len()))
+            .into_iter()
+            .map(locate)
+            .collect::<Vec<Warning>>();
+        self.warnings.extend(relocated);
+    }
+
+    pub fn into_warnings(self) -> Vec<Warning> {
+        self.warnings
+    }
+
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// How many of the warnings the policy denies
+    pub fn denied(&self) -> usize {
+        self.warnings
+            .iter()
+            .filter(|warning| self.policy.level(warning.code()) == Level::Deny)
+            .count()
+    }
+}
+
+/// Find the indexes of adjacent turns that cancel each other out
+pub fn no_op_turns(instructions: &[Instruction]) -> Vec<usize> {
+    let mut indexes = Vec::new();
+    let mut i = 0;
+
+    while i + 1 < instructions.len() {
+        match (instructions[i], instructions[i + 1]) {
+            (Instruction::Left, Instruction::Right) | (Instruction::Right, Instruction::Left) => {
+                indexes.push(i);
+                // Skip the second turn so `LRL` is only reported once
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+
+    indexes
+}
+
+#[cfg(test)]
+mod diagnostics_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod policy {
+        use super::{Diagnostics, Level, Policy, Warning, WarningCode};
+        use crate::position::Position;
+
+        #[test]
+        fn levels() {
+            let mut policy = Policy::new(Level::Deny);
+            policy.set(WarningCode::NoOpTurns, Level::Allow);
+            policy.set(WarningCode::EndedOnBoundery, Level::Warn);
+
+            let mut diagnostics = Diagnostics::default().with_policy(policy);
+            diagnostics.warn(Warning::NoOpTurns {
+                instruction: 0,
+                line: 2,
+            });
+            diagnostics.warn(Warning::EndedOnBoundery {
+                rover: 1,
+                position: Position::new(0, 3),
+            });
+            diagnostics.warn(Warning::NearBoundery {
+                rover: 1,
+                position: Position::new(0, 3),
+                instruction: 2,
+            });
+            assert_eq!(diagnostics.len(), 2);
+            assert_eq!(diagnostics.denied(), 1);
+        }
+
+        #[test]
+        fn code_names() {
+            assert_eq!(
+                WarningCode::EndedOnBoundery.to_string(),
+                "ended-on-boundery"
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod no_op_turns {
+        use super::no_op_turns;
+        use crate::enums::Instruction;
+
+        #[test]
+        fn cancelling_pairs() {
+            let result = no_op_turns(&[
+                Instruction::Left,
+                Instruction::Right,
+                Instruction::Left,
+                Instruction::Right,
+            ]);
+            assert_eq!(result, vec![0, 2]);
+        }
+
+        #[test]
+        fn no_cancelling_pairs() {
+            let result = no_op_turns(&[Instruction::Left, Instruction::Move, Instruction::Right]);
+            assert!(result.is_empty());
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{
+    enums::{InputFormat, RoverErr},
+    input,
+    json::{self, Json, Lines},
+    rover::Attributes,
+    terrain,
+};
+
+/// How many characters from the start of a mission are looked at to work out its format
+const SNIFF_LENGTH: usize = 1024;
+
+/// Work out which format a mission is written in from its contents
+///
+/// Only the first [`SNIFF_LENGTH`] characters of the mission are looked at, so this is cheap
+/// even for very large missions.
+pub fn detect(text: &str) -> InputFormat {
+    let head = text
+        .char_indices()
+        .nth(SNIFF_LENGTH)
+        .map_or(text, |(end, _)| &text[..end]);
+    let start = head.trim_start();
+    let first_line = start.lines().next().unwrap_or_default();
+
+    if head.contains(|c: char| c.is_control() && !c.is_whitespace()) {
+        detect_binary(text.as_bytes())
+    } else if start.starts_with('{') {
+        InputFormat::Json
+    } else if first_line.starts_with("---")
+        || first_line
+            .split_once(':')
+            .is_some_and(|(key, _)| is_yaml_key(key))
+    {
+        InputFormat::Yaml
+    } else if first_line
+        .split_once(|c: char| c.is_whitespace() || c == '=')
+        .is_some_and(|(label, _)| is_yaml_key(label))
+        && input::world_directive(first_line).is_none()
+    {
+        InputFormat::Labeled
+    } else {
+        InputFormat::Text
+    }
+}
+
+/// Work out which format a mission that is not text is written in
+///
+/// Only the first [`SNIFF_LENGTH`] bytes are looked at, like [`detect`].
+pub fn detect_binary(bytes: &[u8]) -> InputFormat {
+    let head = &bytes[..bytes.len().min(SNIFF_LENGTH)];
+    match is_protobuf(head, head.len() < bytes.len()) {
+        true => InputFormat::Protobuf,
+        false => InputFormat::Binary,
+    }
+}
+
+/// Whether the bytes are a run of protobuf fields, each a key with a field number and a known
+/// wire type followed by a value of that type
+///
+/// `truncated` says the bytes are only the start of the mission, so its last field may run past
+/// them.
+fn is_protobuf(mut bytes: &[u8], truncated: bool) -> bool {
+    if bytes.is_empty() {
+        return false;
+    }
+
+    while !bytes.is_empty() {
+        let Some((key, rest)) = varint(bytes) else {
+            return truncated;
+        };
+        if key >> 3 == 0 {
+            return false;
+        }
+        let length = match key & 0x7 {
+            // A varint
+            0 => match varint(rest) {
+                Some((_, after)) => rest.len() - after.len(),
+                None => return truncated,
+            },
+            // Eight bytes
+            1 => 8,
+            // A length followed by that many bytes
+            2 => match varint(rest) {
+                Some((length, after)) => rest.len() - after.len() + length as usize,
+                None => return truncated,
+            },
+            // Four bytes
+            5 => 4,
+            _ => return false,
+        };
+        match rest.get(length..) {
+            Some(after) => bytes = after,
+            None => return truncated,
+        }
+    }
+
+    true
+}
+
+/// Read a protobuf varint, seven bits a byte with the high bit set on all but the last
+fn varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let end = bytes.iter().position(|byte| byte & 0x80 == 0)?;
+    if end >= 10 {
+        return None;
+    }
+    let value = bytes[..=end]
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 7) | u64::from(byte & 0x7f));
+    Some((value, &bytes[end + 1..]))
+}
+
+/// A YAML mapping key, e.g. `plateau`, which the classic format never starts a line with
+fn is_yaml_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && key.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Rewrite a mission in the classic text format, so it can be parsed like any other
+///
+/// Missions that were converted also give the line each classic line was written on, so errors
+/// can be reported against the mission as written.
+pub fn to_classic(
+    text: String,
+    format: InputFormat,
+) -> Result<(String, Option<Vec<usize>>), RoverErr> {
+    match format {
+        InputFormat::Text => Ok((text, None)),
+        InputFormat::Json => json_to_classic(&text),
+        InputFormat::Labeled | InputFormat::Yaml | InputFormat::Protobuf | InputFormat::Binary => {
+            Err(RoverErr::UnsupportedFormat(format))
+        }
+    }
+}
+
+/// Translate a JSON mission, e.g.
+/// `{"plateau": [5, 5], "rovers": [{"position": [1, 2], "facing": "N", "instructions": "LM"}]}`
+///
+/// The plateau may be left out of missions that only add further rovers, and each rover may also
+/// have an `"id"`, a `"name"`, a `"priority"` and a `"maintenance"` threshold.
+fn json_to_classic(text: &str) -> Result<(String, Option<Vec<usize>>), RoverErr> {
+    let (json, written) = json::parse_with_lines(text).map_err(RoverErr::JsonParse)?;
+    let line_of = |lines: Option<&Lines>| lines.map_or(written.line, |lines| lines.line);
+    let mut lines = Vec::new();
+    let mut origins = Vec::new();
+
+    if let Some(plateau) = json.get("plateau") {
+        let (x, y) = pair(plateau).ok_or(RoverErr::JsonMission("plateau"))?;
+        lines.push(format!("{x} {y}"));
+        origins.push(line_of(written.member(&json, "plateau")));
+    }
+
+    let rovers = json
+        .get("rovers")
+        .and_then(Json::as_array)
+        .ok_or(RoverErr::JsonMission("rovers"))?;
+    let rovers_written = written.member(&json, "rovers");
+    for (index, rover) in rovers.iter().enumerate() {
+        let rover_written = rovers_written.and_then(|lines| lines.parts.get(index));
+        let (x, y) = rover
+            .get("position")
+            .and_then(pair)
+            .ok_or(RoverErr::JsonMission("rover position"))?;
+        let facing = rover
+            .get("facing")
+            .and_then(Json::as_str)
+            .ok_or(RoverErr::JsonMission("rover facing"))?;
+        let instructions = rover
+            .get("instructions")
+            .and_then(Json::as_str)
+            .filter(|instructions| is_instruction_text(instructions))
+            .ok_or(RoverErr::JsonMission("rover instructions"))?;
+
+        let attributes = Attributes {
+            id: optional_count(rover, "id")?,
+            name: optional_name(rover)?,
+            priority: optional_count(rover, "priority")?.unwrap_or_default(),
+            maintenance: optional_count(rover, "maintenance")?,
+        };
+        lines.push(format!("{x} {y} {facing}{attributes}"));
+        lines.push(instructions.to_string());
+        origins.push(line_of(rover_written));
+        origins.push(line_of(
+            rover_written.and_then(|lines| lines.member(rover, "instructions")),
+        ));
+    }
+
+    Ok((lines.join("\n"), Some(origins)))
+}
+
+/// Whether a rover's instructions are only letters and spaces, along with any `${VAR}`
+/// variables, so they can't spill over onto further lines of the classic text
+fn is_instruction_text(instructions: &str) -> bool {
+    let letters = |text: &str| text.chars().all(|c| c.is_alphabetic() || c == ' ');
+    let mut rest = instructions;
+
+    while let Some((before, after)) = rest.split_once("${") {
+        let Some((name, after)) = after.split_once('}') else {
+            return false;
+        };
+        if !letters(before) || name.contains(|c: char| c.is_control()) {
+            return false;
+        }
+        rest = after;
+    }
+    letters(rest)
+}
+
+/// A whole number a rover may be given, e.g. its priority
+fn optional_count(rover: &Json, key: &'static str) -> Result<Option<usize>, RoverErr> {
+    rover
+        .get(key)
+        .map(|value| {
+            value
+                .as_isize()
+                .and_then(|value| usize::try_from(value).ok())
+                .ok_or(RoverErr::JsonMission(key))
+        })
+        .transpose()
+}
+
+/// The name a rover may be given, which is written as an attribute so is limited to letters,
+/// digits, `-` and `_`
+fn optional_name(rover: &Json) -> Result<Option<String>, RoverErr> {
+    rover
+        .get("name")
+        .map(|value| {
+            value
+                .as_str()
+                .filter(|name| terrain::is_valid_tag(name))
+                .map(String::from)
+                .ok_or(RoverErr::JsonMission("rover name"))
+        })
+        .transpose()
+}
+
+fn pair(json: &Json) -> Option<(isize, isize)> {
+    match json.as_array()? {
+        [x, y] => Some((x.as_isize()?, y.as_isize()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod dialect_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod detect {
+        use super::{detect, detect_binary};
+        use crate::enums::InputFormat;
+
+        #[test]
+        fn classic_text() {
+            assert_eq!(detect("5 5\n1 2 N\nLMLMLMLMM"), InputFormat::Text);
+            assert_eq!(detect("!include rovers.txt\n"), InputFormat::Text);
+            assert_eq!(detect("WORLD mars.txt\n1 2 N\nM"), InputFormat::Text);
+        }
+
+        #[test]
+        fn labeled() {
+            assert_eq!(
+                detect("plateau 5 5\nrover 1 2 N\ninstructions LMLMLMLMM"),
+                InputFormat::Labeled
+            );
+            assert_eq!(detect("plateau=5 5"), InputFormat::Labeled);
+        }
+
+        #[test]
+        fn json() {
+            assert_eq!(detect("\n  {\"plateau\": [5, 5]}"), InputFormat::Json);
+        }
+
+        #[test]
+        fn yaml() {
+            assert_eq!(detect("---\nplateau: [5, 5]"), InputFormat::Yaml);
+            assert_eq!(detect("plateau: [5, 5]"), InputFormat::Yaml);
+        }
+
+        #[test]
+        fn protobuf() {
+            // A plateau string as field 1, then a rover message holding a varint as field 2
+            assert_eq!(
+                detect("\n\u{3}5 5\u{12}\u{2}\u{8}\u{1}"),
+                InputFormat::Protobuf
+            );
+            assert_eq!(
+                detect_binary(&[0x0a, 0x03, b'5', b' ', 0xff]),
+                InputFormat::Protobuf
+            );
+        }
+
+        #[test]
+        fn binary() {
+            assert_eq!(detect("\u{8}\0\u{12}\u{3}"), InputFormat::Binary);
+            assert_eq!(detect_binary(&[0xff, 0xfe, 0x00]), InputFormat::Binary);
+        }
+
+        #[test]
+        fn only_start() {
+            let text = format!("5 5\n1 2 N\n{}\0", "M".repeat(2000));
+            assert_eq!(detect(&text), InputFormat::Text);
+        }
+    }
+
+    #[cfg(test)]
+    mod to_classic {
+        use super::to_classic;
+        use crate::enums::{InputFormat, RoverErr};
+
+        #[test]
+        fn json() {
+            let result = to_classic(
+                r#"{"plateau": [5, 5], "rovers": [
+                    {"position": [1, 2], "facing": "N", "instructions": "LMLMLMLMM"},
+                    {"position": [3, 3], "facing": "E", "instructions": "MMRMMRMRRM", "priority": 2, "maintenance": 9}
+                ]}"#
+                .to_string(),
+                InputFormat::Json,
+            );
+            assert_eq!(
+                result.unwrap(),
+                (
+                    "5 5\n1 2 N\nLMLMLMLMM\n3 3 E priority=2 maintenance=9\nMMRMMRMRRM".to_string(),
+                    Some(vec![0, 1, 1, 2, 2])
+                )
+            );
+        }
+
+        #[test]
+        fn json_smuggled_rover() {
+            for instructions in [r"M\n3 3 E\nM", "M${TURN}\\n", "M${TURN"] {
+                let result = to_classic(
+                    format!(
+                        r#"{{"plateau": [5, 5], "rovers": [{{"position": [1, 2], "facing": "N", "instructions": "{instructions}"}}]}}"#
+                    ),
+                    InputFormat::Json,
+                );
+                assert!(matches!(
+                    result,
+                    Err(RoverErr::JsonMission("rover instructions"))
+                ));
+            }
+        }
+
+        #[test]
+        fn json_missing_instructions() {
+            let result = to_classic(
+                r#"{"rovers": [{"position": [1, 2], "facing": "N"}]}"#.to_string(),
+                InputFormat::Json,
+            );
+            assert!(matches!(
+                result,
+                Err(RoverErr::JsonMission("rover instructions"))
+            ));
+        }
+
+        #[test]
+        fn invalid_json() {
+            let result = to_classic(
+                "{\"plateau\": [5, 5],\n \"rovers\": [\n  {\"position\": [1, 2]\n]}".to_string(),
+                InputFormat::Json,
+            );
+            assert!(matches!(result, Err(RoverErr::JsonParse(3))));
+        }
+
+        #[test]
+        fn unsupported() {
+            let result = to_classic("plateau: [5, 5]".to_string(), InputFormat::Yaml);
+            assert!(matches!(
+                result,
+                Err(RoverErr::UnsupportedFormat(InputFormat::Yaml))
+            ));
+        }
+    }
+}
@@ -1,20 +1,72 @@
 use std::{
     fmt::{self, Display},
     io,
+    path::PathBuf,
 };
 
+use clap::ValueEnum;
 use enum_iterator::Sequence;
 use nom::IResult;
 
-use crate::rover::Rover;
+use crate::{budget::Limit, input::MISSION_ENV, position::Position, rover::Rover};
 
 #[derive(Debug)]
 pub enum RoverErr {
     Opening(io::Error),
     Reading(io::Error),
     Saving(io::Error),
+    Archiving(io::Error),
+    OpeningTrace(io::Error),
+    OpeningWorld(io::Error),
+    OpeningPlateau(io::Error),
+    OpeningKey(io::Error),
+    OpeningConfig(io::Error),
     Parse(ParsingErr, usize),
+    TraceParse(usize),
+    /// The part of the world state that could not be read
+    WorldParse(&'static str),
+    /// The part of a signature file that could not be read
+    SignatureParse(&'static str),
+    /// Why the signature does not match the output
+    BadSignature(&'static str),
+    /// Which half of a key pair could not be read
+    InvalidKey(&'static str),
+    /// The index of the line of a world file that could not be read
+    PlateauParse(usize),
+    /// The index of the line of a config file that could not be read
+    ConfigParse(usize),
+    UnsupportedFormat(InputFormat),
+    /// The part of a JSON mission that could not be read
+    JsonMission(&'static str),
+    /// The index of the line a JSON mission could not be parsed past
+    JsonParse(usize),
+    UnknownRover(usize, usize),
+    LostRover(usize, usize),
+    ConflictingBounderies(Position, Position, usize),
+    /// Only some rovers were given an `id=`, or the IDs do not number them from one
+    RoverIds,
+    InFile(PathBuf, Box<RoverErr>),
+    Including(PathBuf, io::Error, usize),
+    IncludeCycle(PathBuf, usize),
+    UndefinedVariable(String, usize),
+    /// A `${` without a closing `}`, and the index of its line
+    UnclosedVariable(usize),
     Boundery(Rover, usize),
+    Crater(Rover, usize),
+    Hazard(Rover, usize),
+    /// The rover as it was before the instruction that could not be afforded
+    BudgetExceeded(Rover, usize, Limit),
+    /// An error raised whilst executing, with the index of the line its instruction was
+    /// written on
+    OnLine(Box<RoverErr>, usize),
+    SharedFinalPosition(usize, usize, Position),
+    MissingInput,
+    DeniedWarnings(usize),
+    Analysis(usize),
+    InvalidPlateau(usize),
+    /// The plateau's bounderies and the most cells that can be written out
+    OversizedPlateau(Position, usize),
+    UnknownFormat(String, Vec<String>),
 }
 
 impl RoverErr {
@@ -22,10 +74,93 @@ impl RoverErr {
     pub fn from_parse_result<T>(input: IResult<&str, T>, line_index: usize) -> Result<T, RoverErr> {
         match input {
             // returns ok if there are no characters left in the string
-            Ok((s, t)) if s.is_empty() => Ok(t),
+            Ok(("", t)) => Ok(t),
             _ => Err(RoverErr::Parse(ParsingErr::UnexpectedToken, line_index)), // TODO: improve error by displaying the position of the unexpected token
         }
     }
+
+    /// The index of the line of the instructions file the error occurred on, if known
+    pub fn line_index(&self) -> Option<usize> {
+        match self {
+            RoverErr::Parse(_, index)
+            | RoverErr::ConflictingBounderies(_, _, index)
+            | RoverErr::Including(_, _, index)
+            | RoverErr::IncludeCycle(_, index)
+            | RoverErr::UndefinedVariable(_, index)
+            | RoverErr::UnclosedVariable(index)
+            | RoverErr::UnknownRover(_, index)
+            | RoverErr::LostRover(_, index)
+            | RoverErr::OnLine(_, index) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Replace the index of the line the error occurred on
+    pub fn with_line_index(self, line_index: usize) -> RoverErr {
+        match self {
+            RoverErr::Parse(e, _) => RoverErr::Parse(e, line_index),
+            RoverErr::ConflictingBounderies(expected, found, _) => {
+                RoverErr::ConflictingBounderies(expected, found, line_index)
+            }
+            RoverErr::Including(path, e, _) => RoverErr::Including(path, e, line_index),
+            RoverErr::IncludeCycle(path, _) => RoverErr::IncludeCycle(path, line_index),
+            RoverErr::UndefinedVariable(name, _) => RoverErr::UndefinedVariable(name, line_index),
+            RoverErr::UnclosedVariable(_) => RoverErr::UnclosedVariable(line_index),
+            RoverErr::UnknownRover(id, _) => RoverErr::UnknownRover(id, line_index),
+            RoverErr::LostRover(id, _) => RoverErr::LostRover(id, line_index),
+            RoverErr::OnLine(err, _) => RoverErr::OnLine(err, line_index),
+            err @ (RoverErr::Boundery(..)
+            | RoverErr::Crater(..)
+            | RoverErr::Hazard(..)
+            | RoverErr::BudgetExceeded(..)) => RoverErr::OnLine(Box::new(err), line_index),
+            err => err,
+        }
+    }
+
+    /// Describe an error raised whilst executing, pointing at the line its instruction was
+    /// written on, by default the rover's line of a single instructions file
+    fn fmt_execution(&self, f: &mut fmt::Formatter, line_index: Option<usize>) -> fmt::Result {
+        match self {
+            RoverErr::Boundery(rover, instruction) => write!(
+                f,
+                "Rover Error 🤖 - Rover {} crossed the plateau's boundery at position ({}, {}): Instruction {}, At Line: {}.\n\nPlease send help! 😞",
+                rover.id,
+                rover.pose.position.x,
+                rover.pose.position.y,
+                instruction + 1,
+                line_index.unwrap_or(rover.id * 2) + 1
+            ),
+            RoverErr::Crater(rover, instruction) => write!(
+                f,
+                "Rover Error 🤖 - Rover {} fell into a crater at position ({}, {}): Instruction {}, At Line: {}.\n\nPlease send help! 😞",
+                rover.id,
+                rover.pose.position.x,
+                rover.pose.position.y,
+                instruction + 1,
+                line_index.unwrap_or(rover.id * 2) + 1
+            ),
+            RoverErr::Hazard(rover, instruction) => write!(
+                f,
+                "Rover Error 🤖 - Rover {} was struck by a hazard at position ({}, {}): Instruction {}, At Line: {}.\n\nPlease send help! 😞",
+                rover.id,
+                rover.pose.position.x,
+                rover.pose.position.y,
+                instruction + 1,
+                line_index.unwrap_or(rover.id * 2) + 1
+            ),
+            RoverErr::BudgetExceeded(rover, instruction, limit) => write!(
+                f,
+                "Rover Error 🤖 - The mission's {} budget ran out with rover {} at position ({}, {}): Instruction {}, At Line: {}",
+                limit,
+                rover.id,
+                rover.pose.position.x,
+                rover.pose.position.y,
+                instruction + 1,
+                line_index.unwrap_or(rover.id * 2) + 1
+            ),
+            err => write!(f, "{err}"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -56,6 +191,12 @@ impl Display for RoverErr {
             RoverErr::Opening(e) => ("opening the instructions file", e),
             RoverErr::Reading(e) => ("reading in the instructions file", e),
             RoverErr::Saving(e) => ("saving the output file", e),
+            RoverErr::Archiving(e) => ("archiving the instructions file", e),
+            RoverErr::OpeningTrace(e) => ("opening the trace file", e),
+            RoverErr::OpeningWorld(e) => ("opening the world state file", e),
+            RoverErr::OpeningPlateau(e) => ("opening the world file", e),
+            RoverErr::OpeningKey(e) => ("opening the signing key", e),
+            RoverErr::OpeningConfig(e) => ("opening the config file", e),
             RoverErr::Parse(e, index) => {
                 return write!(
                 f,
@@ -64,15 +205,196 @@ impl Display for RoverErr {
                 index + 1
             )
             }
-            RoverErr::Boundery(rover, instruction) => {
+            RoverErr::TraceParse(index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Issue whilst parsing trace file: Unexpected token encountered, At line: {}",
+                    index + 1
+                )
+            }
+            RoverErr::UnsupportedFormat(format) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - The mission is written as {}, which cannot be read yet: Convert it to text or JSON",
+                    format
+                )
+            }
+            RoverErr::JsonParse(index) => {
                 return write!(
                     f,
-                    "Rover Error 🤖 - Rover {} crossed the plateau's boundery at position ({}, {}): Instruction {}, At Line: {}.\n\nPlease send help! 😞",
-                    rover.id,
-                    rover.x,
-                    rover.y,
-                    instruction + 1,
-                    (rover.id * 2) + 1
+                    "Rover Error 🤖 - Issue whilst parsing JSON mission: Invalid JSON, At line: {}",
+                    index + 1
+                )
+            }
+            RoverErr::JsonMission(part) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Unable to read the JSON mission: Invalid {}",
+                    part
+                )
+            }
+            RoverErr::WorldParse(part) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Issue whilst parsing world state file: {}",
+                    part
+                )
+            }
+            RoverErr::SignatureParse(part) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Issue whilst parsing signature file: Invalid {}",
+                    part
+                )
+            }
+            RoverErr::BadSignature(reason) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - The signature does not match: {}",
+                    reason
+                )
+            }
+            RoverErr::InvalidKey(half) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - The key is not an Ed25519 {} key in PEM format",
+                    half
+                )
+            }
+            RoverErr::PlateauParse(index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Issue whilst parsing world file: Unexpected token encountered, At line: {}",
+                    index + 1
+                )
+            }
+            RoverErr::ConfigParse(index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Issue whilst parsing config file: Unknown or invalid setting, At line: {}",
+                    index + 1
+                )
+            }
+            RoverErr::UnknownRover(id, index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - There is no rover {} in the world, At line: {}",
+                    id,
+                    index + 1
+                )
+            }
+            RoverErr::LostRover(id, index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Rover {} was lost and can not be given instructions, At line: {}",
+                    id,
+                    index + 1
+                )
+            }
+            RoverErr::ConflictingBounderies(expected, found, index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Plateau bounderies ({}, {}) conflict with the bounderies ({}, {}) of the first instructions file, At line: {}",
+                    found.x,
+                    found.y,
+                    expected.x,
+                    expected.y,
+                    index + 1
+                )
+            }
+            RoverErr::RoverIds => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - The rovers' IDs must number them from one, each once: Give every rover an id= or none"
+                )
+            }
+            RoverErr::InFile(path, e) => return write!(f, "{}: {}", path.display(), e),
+            RoverErr::Including(path, e, index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Issue whilst including {}: {}, At line: {}",
+                    path.display(),
+                    e,
+                    index + 1
+                )
+            }
+            RoverErr::IncludeCycle(path, index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - {} includes itself, At line: {}",
+                    path.display(),
+                    index + 1
+                )
+            }
+            RoverErr::UndefinedVariable(name, index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Variable ${{{}}} is not defined: Pass --define {}=<value> or set it in the environment, At line: {}",
+                    name,
+                    name,
+                    index + 1
+                )
+            }
+            RoverErr::UnclosedVariable(index) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - A variable is missing its closing `}}`: Write it as ${{NAME}}, At line: {}",
+                    index + 1
+                )
+            }
+            RoverErr::Boundery(..)
+            | RoverErr::Crater(..)
+            | RoverErr::Hazard(..)
+            | RoverErr::BudgetExceeded(..) => return self.fmt_execution(f, None),
+            RoverErr::OnLine(err, index) => return err.fmt_execution(f, Some(*index)),
+            RoverErr::SharedFinalPosition(first, second, position) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Rovers {} and {} both finished at position ({}, {}), there is only room for one!",
+                    first, second, position.x, position.y
+                )
+            }
+            RoverErr::MissingInput => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - No instructions given: Pass an instructions file, use --inline or set {}",
+                    MISSION_ENV
+                )
+            }
+            RoverErr::DeniedWarnings(count) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - {} warning(s) were promoted to errors by --deny, --deny-warnings or the config file",
+                    count
+                )
+            }
+            RoverErr::Analysis(count) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Analysis found {} issue(s) with the instructions file",
+                    count
+                )
+            }
+            RoverErr::InvalidPlateau(count) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Validation found {} issue(s) with the world file",
+                    count
+                )
+            }
+            RoverErr::OversizedPlateau(bounderies, cells) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - The plateau ({}, {}) is too large to write out: It may have at most {} cells",
+                    bounderies.x, bounderies.y, cells
+                )
+            }
+            RoverErr::UnknownFormat(name, known) => {
+                return write!(
+                    f,
+                    "Rover Error 🤖 - Unknown output format `{}`: Expected one of {}",
+                    name,
+                    known.join(", ")
                 )
             }
         };
@@ -81,26 +403,223 @@ impl Display for RoverErr {
     }
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum Instruction {
     #[default]
     Move,
     Left,
     Right,
+    /// End the rover's mission, skipping any remaining instructions
+    Halt,
+    /// Service the rover, which only has an effect on a base cell
+    Service,
 }
 
 impl From<&str> for Instruction {
+    /// Look up an instruction in the English alphabet, anything else is a move
     fn from(c: &str) -> Self {
-        match c {
-            "M" | "m" => Instruction::Move,
-            "L" | "l" => Instruction::Left,
-            "R" | "r" => Instruction::Right,
-            _ => Instruction::default(),
+        c.chars()
+            .next()
+            .and_then(|letter| InstructionLocale::English.instruction(letter))
+            .unwrap_or_default()
+    }
+}
+
+/// The alphabet used to write instruction streams.
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum InstructionLocale {
+    /// `M`ove, `L`eft, `R`ight
+    #[default]
+    #[value(name = "en", alias = "english")]
+    English,
+    /// `A`vancer, `G`auche, `D`roite
+    #[value(name = "fr", alias = "french")]
+    French,
+    /// `A`vanzar, `I`zquierda, `D`erecha
+    #[value(name = "es", alias = "spanish")]
+    Spanish,
+    /// `V`orwärts, `L`inks, `R`echts
+    #[value(name = "de", alias = "german")]
+    German,
+}
+
+impl InstructionLocale {
+    /// The alias table mapping each letter of the locale's alphabet to an instruction
+    ///
+    /// `X` halts and `W` services the rover in every locale.
+    pub fn aliases(&self) -> &'static [(char, Instruction)] {
+        match self {
+            InstructionLocale::English => &[
+                ('M', Instruction::Move),
+                ('L', Instruction::Left),
+                ('R', Instruction::Right),
+                ('X', Instruction::Halt),
+                ('W', Instruction::Service),
+            ],
+            InstructionLocale::French => &[
+                ('A', Instruction::Move),
+                ('G', Instruction::Left),
+                ('D', Instruction::Right),
+                ('X', Instruction::Halt),
+                ('W', Instruction::Service),
+            ],
+            InstructionLocale::Spanish => &[
+                ('A', Instruction::Move),
+                ('I', Instruction::Left),
+                ('D', Instruction::Right),
+                ('X', Instruction::Halt),
+                ('W', Instruction::Service),
+            ],
+            InstructionLocale::German => &[
+                ('V', Instruction::Move),
+                ('L', Instruction::Left),
+                ('R', Instruction::Right),
+                ('X', Instruction::Halt),
+                ('W', Instruction::Service),
+            ],
+        }
+    }
+
+    /// Look up the instruction for a letter (case insensitive)
+    pub fn instruction(&self, letter: char) -> Option<Instruction> {
+        self.aliases()
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(&letter))
+            .map(|(_, instruction)| *instruction)
+    }
+
+    /// The letter an instruction is written as in the locale's alphabet
+    pub fn letter(&self, instruction: Instruction) -> char {
+        self.aliases()
+            .iter()
+            .find(|(_, alias)| *alias == instruction)
+            .map(|(letter, _)| *letter)
+            .unwrap_or('X')
+    }
+}
+
+/// The order the rovers are output in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SortKey {
+    /// The order the rovers appear in the instructions file
+    #[default]
+    Id,
+    /// Final x co-ordinate, then y co-ordinate
+    X,
+    /// Final y co-ordinate, then x co-ordinate
+    Y,
+    /// The name given with `name=`, rovers without one come last
+    Name,
+}
+
+impl SortKey {
+    /// Sort the rovers in place, ties are broken by the rover's ID
+    pub fn sort(&self, rovers: &mut [Rover]) {
+        match self {
+            SortKey::Id => rovers.sort_by_key(|rover| rover.id),
+            SortKey::X => rovers.sort_by_key(|rover| (rover.pose.position, rover.id)),
+            SortKey::Y => {
+                rovers.sort_by_key(|rover| (rover.pose.position.y, rover.pose.position.x, rover.id))
+            }
+            SortKey::Name => rovers.sort_by(|a, b| {
+                (a.name.is_none(), &a.name, a.id).cmp(&(b.name.is_none(), &b.name, b.id))
+            }),
+        }
+    }
+}
+
+/// How far a rover may override its instructions to keep itself safe
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Autonomy {
+    /// Every instruction is executed as given
+    #[default]
+    Off,
+    /// A move that would take the rover off the plateau or into a crater is refused, and the
+    /// recovery maneuver is executed instead
+    Safe,
+}
+
+/// What a rover in safe mode does instead of a move that would be fatal
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Recovery {
+    /// Stop where it is, ignoring the rest of its instructions
+    #[default]
+    Stop,
+    /// Turn right and retry the move once, stopping if that would be fatal too
+    TurnRight,
+}
+
+/// The built in sources of random numbers
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Generator {
+    /// SplitMix64, which earlier versions always used
+    #[default]
+    Splitmix64,
+    /// xorshift64*, which is a little faster
+    #[value(name = "xorshift64star")]
+    Xorshift64Star,
+}
+
+/// The formats a mission can be written in
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum InputFormat {
+    /// The classic format, a line for the plateau then two lines per rover
+    Text,
+    /// The classic format with each line given a label, e.g. `plateau 5 5`
+    Labeled,
+    Json,
+    Yaml,
+    Protobuf,
+    Binary,
+}
+
+impl Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                InputFormat::Text => "text",
+                InputFormat::Labeled => "the labeled dialect",
+                InputFormat::Json => "JSON",
+                InputFormat::Yaml => "YAML",
+                InputFormat::Protobuf => "protobuf",
+                InputFormat::Binary => "binary",
+            }
+        )
+    }
+}
+
+/// The layout of the structured outputs (traces, timelines and heatmaps).
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SchemaVersion {
+    /// The original layouts, which do not record their schema version
+    #[value(name = "1")]
+    V1,
+    /// Every output records its schema version
+    #[default]
+    #[value(name = "2")]
+    V2,
+}
+
+impl SchemaVersion {
+    pub fn number(&self) -> usize {
+        match self {
+            SchemaVersion::V1 => 1,
+            SchemaVersion::V2 => 2,
+        }
+    }
+
+    pub fn from_number(number: usize) -> Option<Self> {
+        match number {
+            1 => Some(SchemaVersion::V1),
+            2 => Some(SchemaVersion::V2),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Default, Sequence, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Sequence, PartialEq, Eq, Hash)]
 pub enum Direction {
     #[default]
     North,
@@ -136,4 +655,53 @@ impl Display for Direction {
     }
 }
 
-pub type Coordinate = (isize, isize);
+#[cfg(test)]
+mod enums_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod sort_key {
+        use super::{Direction, Rover, SortKey};
+        use crate::position::{Pose, Position};
+
+        #[test]
+        fn sort_by_x() {
+            let mut rovers = vec![
+                Rover::new(1, Pose::new(Position::new(3, 0), Direction::North)),
+                Rover::new(2, Pose::new(Position::new(1, 5), Direction::North)),
+                Rover::new(3, Pose::new(Position::new(1, 2), Direction::North)),
+            ];
+            SortKey::X.sort(&mut rovers);
+            assert_eq!(
+                rovers.iter().map(|rover| rover.id).collect::<Vec<usize>>(),
+                vec![3, 2, 1]
+            );
+        }
+
+        #[test]
+        fn sort_by_name() {
+            let mut rovers = vec![
+                Rover::new(1, Pose::new(Position::new(0, 0), Direction::North)),
+                Rover::new(2, Pose::new(Position::new(0, 0), Direction::North)),
+                Rover::new(3, Pose::new(Position::new(0, 0), Direction::North)),
+            ];
+            rovers[1].name = Some("spirit".to_string());
+            rovers[2].name = Some("opportunity".to_string());
+            SortKey::Name.sort(&mut rovers);
+            assert_eq!(
+                rovers.iter().map(|rover| rover.id).collect::<Vec<usize>>(),
+                vec![3, 2, 1]
+            );
+        }
+
+        #[test]
+        fn sort_by_id() {
+            let mut rovers = vec![
+                Rover::new(2, Pose::new(Position::new(0, 0), Direction::North)),
+                Rover::new(1, Pose::new(Position::new(0, 0), Direction::North)),
+            ];
+            SortKey::Id.sort(&mut rovers);
+            assert_eq!(rovers[0].id, 1);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt::{self, Display};
+
+use clap::Args;
+
+use crate::{enums::Instruction, rover::Mission};
+
+/// The fuel and time each kind of instruction costs
+#[derive(Debug, Clone, Copy, PartialEq, Args)]
+pub struct CostModel {
+    /// The fuel used by moving one cell.
+    #[arg(long, default_value_t = CostModel::default().move_fuel)]
+    pub move_fuel: f64,
+
+    /// The fuel used by turning left or right.
+    #[arg(long, default_value_t = CostModel::default().turn_fuel)]
+    pub turn_fuel: f64,
+
+    /// The seconds taken to move one cell.
+    #[arg(long, default_value_t = CostModel::default().move_seconds)]
+    pub move_seconds: f64,
+
+    /// The seconds taken to turn left or right.
+    #[arg(long, default_value_t = CostModel::default().turn_seconds)]
+    pub turn_seconds: f64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            move_fuel: 1.0,
+            turn_fuel: 0.5,
+            move_seconds: 2.0,
+            turn_seconds: 1.0,
+        }
+    }
+}
+
+impl CostModel {
+    /// The fuel used by moving and turning a number of times
+    pub fn fuel(&self, distance: usize, turns: usize) -> f64 {
+        distance as f64 * self.move_fuel + turns as f64 * self.turn_fuel
+    }
+}
+
+/// The expected cost of a rover's instructions
+#[derive(Debug, PartialEq)]
+pub struct Estimate {
+    pub rover: usize,
+    pub instructions: usize,
+    /// The number of cells the rover moves
+    pub distance: usize,
+    pub fuel: f64,
+    pub seconds: f64,
+    pub redundancy: Redundancy,
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Rover {} - Instructions: {}, Distance: {}, Fuel: {:.1}, Duration: {:.1}s",
+            self.rover, self.instructions, self.distance, self.fuel, self.seconds
+        )?;
+
+        if self.redundancy.savings() > 0 {
+            write!(f, ", {}", self.redundancy)?;
+        }
+        Ok(())
+    }
+}
+
+/// Instructions that could be left out without changing where a rover ends up
+#[derive(Debug, Default, PartialEq)]
+pub struct Redundancy {
+    /// Left and right turns that undo each other, e.g. `LR`
+    pub cancelling_turns: usize,
+    /// Moves straight back to the previous cell, e.g. `MRRM`, which only needs the turns
+    pub backtracks: usize,
+}
+
+impl Redundancy {
+    /// How many instructions leaving out the redundant ones would save
+    pub fn savings(&self) -> usize {
+        (self.cancelling_turns + self.backtracks) * 2
+    }
+}
+
+impl Display for Redundancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Redundant: {} (cancelling turns: {}, backtracks: {})",
+            self.savings(),
+            self.cancelling_turns,
+            self.backtracks
+        )
+    }
+}
+
+/// Find the redundant instructions in a stream, returning the stream without them
+///
+/// Removing one redundant run can expose another, e.g. `LMRRML` reduces to `LRRL` and then to
+/// nothing, so the stream is reduced as it is read.
+pub fn reduce(instructions: &[Instruction]) -> (Vec<Instruction>, Redundancy) {
+    let mut reduced: Vec<Instruction> = Vec::new();
+    let mut redundancy = Redundancy::default();
+
+    for instruction in instructions {
+        reduced.push(*instruction);
+
+        loop {
+            match reduced.as_slice() {
+                [.., Instruction::Left, Instruction::Right]
+                | [.., Instruction::Right, Instruction::Left] => {
+                    reduced.truncate(reduced.len() - 2);
+                    redundancy.cancelling_turns += 1;
+                }
+                [.., Instruction::Move, first, second, Instruction::Move]
+                    if first == second
+                        && matches!(first, Instruction::Left | Instruction::Right) =>
+                {
+                    let turn = *first;
+                    reduced.truncate(reduced.len() - 4);
+                    reduced.extend([turn, turn]);
+                    redundancy.backtracks += 1;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    (reduced, redundancy)
+}
+
+/// Estimate the cost of every rover's instructions, without executing them
+pub fn estimate(mission: &Mission, model: &CostModel) -> Vec<Estimate> {
+    mission
+        .rovers
+        .iter()
+        .enumerate()
+        .map(|(index, (_, instructions, _))| {
+            // Nothing after a halt is executed, and halting itself is free
+            let instructions = match instructions
+                .iter()
+                .position(|instruction| *instruction == Instruction::Halt)
+            {
+                Some(halt) => &instructions[..=halt],
+                None => &instructions[..],
+            };
+            let count = |kind: Instruction| {
+                instructions
+                    .iter()
+                    .filter(|instruction| **instruction == kind)
+                    .count()
+            };
+            let distance = count(Instruction::Move);
+            let turns = count(Instruction::Left) + count(Instruction::Right);
+            let (_, redundancy) = reduce(instructions);
+
+            Estimate {
+                // The ID of the rover should start from one
+                rover: index + 1,
+                instructions: instructions.len(),
+                distance,
+                fuel: model.fuel(distance, turns),
+                seconds: distance as f64 * model.move_seconds + turns as f64 * model.turn_seconds,
+                redundancy,
+            }
+        })
+        .collect()
+}
+
+/// Summarise the estimates, the rovers are executed one after another so durations add up
+pub fn total(estimates: &[Estimate]) -> String {
+    let total = format!(
+        "Mission - Instructions: {}, Distance: {}, Fuel: {:.1}, Duration: {:.1}s",
+        estimates.iter().map(|e| e.instructions).sum::<usize>(),
+        estimates.iter().map(|e| e.distance).sum::<usize>(),
+        estimates.iter().map(|e| e.fuel).sum::<f64>(),
+        estimates.iter().map(|e| e.seconds).sum::<f64>(),
+    );
+    let redundancy = Redundancy {
+        cancelling_turns: estimates
+            .iter()
+            .map(|e| e.redundancy.cancelling_turns)
+            .sum(),
+        backtracks: estimates.iter().map(|e| e.redundancy.backtracks).sum(),
+    };
+
+    match redundancy.savings() {
+        0 => total,
+        _ => format!("{total}, {redundancy}"),
+    }
+}
+
+#[cfg(test)]
+mod estimate_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod estimate {
+        use super::{estimate, total, CostModel};
+        use crate::{
+            enums::{Direction, Instruction},
+            position::{Pose, Position},
+            rover::{Attributes, Mission},
+        };
+
+        #[test]
+        fn valid_input() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::North),
+                    vec![Instruction::Move, Instruction::Left, Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            let estimates = estimate(&mission, &CostModel::default());
+            assert_eq!(
+                estimates[0].to_string(),
+                "Rover 1 - Instructions: 3, Distance: 2, Fuel: 2.5, Duration: 5.0s"
+            );
+            assert_eq!(
+                total(&estimates),
+                "Mission - Instructions: 3, Distance: 2, Fuel: 2.5, Duration: 5.0s"
+            );
+        }
+
+        #[test]
+        fn redundant_instructions() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::North),
+                    vec![Instruction::Left, Instruction::Right, Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            let estimates = estimate(&mission, &CostModel::default());
+            assert_eq!(
+                estimates[0].to_string(),
+                "Rover 1 - Instructions: 3, Distance: 1, Fuel: 2.0, Duration: 4.0s, Redundant: 2 (cancelling turns: 1, backtracks: 0)"
+            );
+            assert_eq!(
+                total(&estimates),
+                "Mission - Instructions: 3, Distance: 1, Fuel: 2.0, Duration: 4.0s, Redundant: 2 (cancelling turns: 1, backtracks: 0)"
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod reduce {
+        use super::{reduce, Redundancy};
+        use crate::enums::Instruction;
+
+        fn stream(input: &str) -> Vec<Instruction> {
+            input
+                .chars()
+                .map(|c| Instruction::from(c.to_string().as_str()))
+                .collect()
+        }
+
+        #[test]
+        fn nothing_redundant() {
+            let (reduced, redundancy) = reduce(&stream("LMLMLMLMM"));
+            assert_eq!(reduced, stream("LMLMLMLMM"));
+            assert_eq!(redundancy, Redundancy::default());
+        }
+
+        #[test]
+        fn backtrack() {
+            let (reduced, redundancy) = reduce(&stream("MMRRMM"));
+            assert_eq!(reduced, stream("RR"));
+            assert_eq!(
+                redundancy,
+                Redundancy {
+                    cancelling_turns: 0,
+                    backtracks: 2
+                }
+            );
+        }
+
+        #[test]
+        fn exposed_by_reduction() {
+            let (reduced, redundancy) = reduce(&stream("LMRRMLM"));
+            assert_eq!(reduced, stream("M"));
+            assert_eq!(
+                redundancy,
+                Redundancy {
+                    cancelling_turns: 2,
+                    backtracks: 1
+                }
+            );
+            assert_eq!(redundancy.savings(), 6);
+        }
+    }
+}
@@ -0,0 +1,671 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Display},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{diagnostics::Warning, enums::RoverErr};
+
+/// Values for `${VAR}` given on the command line, these take precedence over the environment
+pub type Defines = HashMap<String, String>;
+
+/// Where a `${VAR}` was substituted in a line, and the name of the variable
+pub type MacroSpan = (Range<usize>, String);
+
+/// Where a line of an expanded text came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    /// The included file the line came from, `None` for lines of the text itself
+    pub include: Option<PathBuf>,
+    pub line: usize,
+    /// Where each `${VAR}` on the line was substituted, as a range of the expanded line
+    pub macros: Vec<MacroSpan>,
+}
+
+/// Where a primitive instruction was written, before its macros and includes were expanded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// The file the instruction was written in, `None` if it was not read from a file
+    pub file: Option<PathBuf>,
+    /// The index of the line it was written on
+    pub line: usize,
+    /// The `${VAR}` the instruction came from, and its index among the instructions the
+    /// variable expanded to
+    pub expansion: Option<(String, usize)>,
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        write!(f, "{}", self.line + 1)
+    }
+}
+
+/// An instructions text with its `!include` directives and `${VAR}` variables expanded
+#[derive(Debug, PartialEq)]
+pub struct Expansion {
+    pub text: String,
+    /// The path of the text itself, included files are named by their lines' origins
+    path: Option<PathBuf>,
+    origins: Vec<Origin>,
+    /// The files included, in the order they were read
+    included: Vec<PathBuf>,
+}
+
+impl Expansion {
+    /// Expand a text, includes are resolved relative to the text's own path
+    pub fn new(text: &str, path: Option<&Path>, defines: &Defines) -> Result<Self, RoverErr> {
+        let mut expansion = Expansion {
+            text: String::new(),
+            path: path.map(Path::to_path_buf),
+            origins: Vec::new(),
+            included: Vec::new(),
+        };
+        // The text itself is on the stack, so a file that includes it back is caught
+        let mut stack = path
+            .map(|path| fs::canonicalize(path).unwrap_or(path.to_path_buf()))
+            .into_iter()
+            .collect();
+        expansion.expand(text, path, defines, &mut stack)?;
+
+        Ok(expansion)
+    }
+
+    fn expand(
+        &mut self,
+        text: &str,
+        path: Option<&Path>,
+        defines: &Defines,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(), RoverErr> {
+        // Only errors from included files need to name the file they came from
+        let include = path
+            .filter(|path| Some(*path) != self.path.as_deref())
+            .map(Path::to_path_buf);
+        let located = |err: RoverErr| match &include {
+            Some(include) => RoverErr::InFile(include.clone(), Box::new(err)),
+            None => err,
+        };
+
+        for (index, line) in text.lines().enumerate() {
+            let (line, macros) = substitute_with_spans(line, defines)
+                .map_err(|err| located(err.with_line_index(index)))?;
+
+            match include_directive(&line) {
+                Some(include_path) => {
+                    let include_path = path
+                        .and_then(Path::parent)
+                        .unwrap_or(Path::new(""))
+                        .join(include_path);
+                    let canonical = fs::canonicalize(&include_path).unwrap_or(include_path.clone());
+                    if stack.contains(&canonical) {
+                        return Err(located(RoverErr::IncludeCycle(include_path, index)));
+                    }
+
+                    let contents = fs::read_to_string(&include_path).map_err(|e| {
+                        located(RoverErr::Including(include_path.clone(), e, index))
+                    })?;
+
+                    if !self.included.contains(&include_path) {
+                        self.included.push(include_path.clone());
+                    }
+                    stack.push(canonical);
+                    self.expand(&contents, Some(&include_path), defines, stack)?;
+                    stack.pop();
+                }
+                // A value may span several lines, each of which came from this line
+                None => {
+                    let mut start = 0;
+                    for piece in line.split('\n') {
+                        let end = start + piece.len();
+                        if !self.origins.is_empty() {
+                            self.text.push('\n');
+                        }
+                        self.text.push_str(piece);
+                        self.origins.push(Origin {
+                            include: include.clone(),
+                            line: index,
+                            macros: macros
+                                .iter()
+                                .filter(|(span, _)| span.start < end && start < span.end)
+                                .map(|(span, name)| {
+                                    let span =
+                                        span.start.max(start) - start..span.end.min(end) - start;
+                                    (span, name.clone())
+                                })
+                                .collect(),
+                        });
+                        start = end + 1;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move the lines of the text itself, e.g. to where they were written before the text was
+    /// converted to the classic format
+    pub fn relocated(mut self, line: impl Fn(usize) -> usize) -> Self {
+        for origin in self
+            .origins
+            .iter_mut()
+            .filter(|origin| origin.include.is_none())
+        {
+            origin.line = line(origin.line);
+        }
+        self
+    }
+
+    /// The files included whilst expanding the text, each once and in the order they were read
+    pub fn included(&self) -> &[PathBuf] {
+        &self.included
+    }
+
+    /// The names of the variables substituted into the text, each time one was used
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.origins
+            .iter()
+            .flat_map(|origin| origin.macros.iter().map(|(_, name)| name.as_str()))
+    }
+
+    /// Where each instruction on a line of the expanded text was written
+    ///
+    /// Every character of an instruction line that is not whitespace is an instruction.
+    pub fn provenance(&self, index: usize) -> Vec<Provenance> {
+        let (Some(origin), Some(line)) = (self.origins.get(index), self.text.lines().nth(index))
+        else {
+            return Vec::new();
+        };
+        let file = origin.include.clone().or_else(|| self.path.clone());
+
+        line.char_indices()
+            .filter(|(_, c)| !c.is_whitespace())
+            .map(|(offset, _)| Provenance {
+                file: file.clone(),
+                line: origin.line,
+                expansion: origin
+                    .macros
+                    .iter()
+                    .find(|(span, _)| span.contains(&offset))
+                    .map(|(span, name)| {
+                        let before = line[span.start..offset]
+                            .chars()
+                            .filter(|c| !c.is_whitespace())
+                            .count();
+                        (name.clone(), before)
+                    }),
+            })
+            .collect()
+    }
+
+    /// Map the line of an error raised whilst parsing the expanded text back to where it was written
+    pub fn locate(&self, err: RoverErr) -> RoverErr {
+        let Some(index) = err.line_index() else {
+            return err;
+        };
+
+        match self.origins.get(index) {
+            Some(Origin {
+                include: Some(include),
+                line,
+                ..
+            }) => RoverErr::InFile(include.clone(), Box::new(err.with_line_index(*line))),
+            Some(Origin {
+                include: None,
+                line,
+                ..
+            }) => err.with_line_index(*line),
+            None => err,
+        }
+    }
+
+    /// Map the line of a warning raised whilst parsing the expanded text back to where it was
+    /// written
+    pub fn locate_warning(&self, warning: Warning) -> Warning {
+        let Warning::NoOpTurns { line: index, .. } = warning else {
+            return warning;
+        };
+
+        match self.origins.get(index) {
+            Some(Origin {
+                include: Some(include),
+                line,
+                ..
+            }) => Warning::InFile {
+                path: include.clone(),
+                warning: Box::new(warning.with_line_index(*line)),
+            },
+            Some(Origin {
+                include: None,
+                line,
+                ..
+            }) => warning.with_line_index(*line),
+            None => warning,
+        }
+    }
+}
+
+/// Warn about each variable given with `--define` that is not among the variables used, in name
+/// order
+pub fn unused_defines(defines: &Defines, variables: &[String]) -> Vec<Warning> {
+    let mut unused = defines
+        .keys()
+        .filter(|name| !variables.contains(name))
+        .collect::<Vec<&String>>();
+    unused.sort();
+    unused
+        .into_iter()
+        .map(|name| Warning::UnusedDefine { name: name.clone() })
+        .collect()
+}
+
+/// The path an `!include <path>` line names, if the line is one
+fn include_directive(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("!include")
+        .filter(|path| path.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+/// Where the instructions of every rover of a mission were written, so what happens whilst it
+/// executes can be pointed at the lines as written
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SourceMap {
+    /// Indexed by rover and then instruction
+    pub rovers: Vec<Vec<Provenance>>,
+    /// Where each rover's starting position was written, indexed by rover
+    pub starts: Vec<Provenance>,
+    /// The only file the mission was read from, which does not need naming
+    pub implied: Option<PathBuf>,
+    /// The names of the variables substituted whilst expanding the mission
+    pub variables: Vec<String>,
+    /// The files included whilst expanding the mission, in the order they were read
+    pub included: Vec<PathBuf>,
+}
+
+impl SourceMap {
+    /// Where a rover's instruction was written, or its last instruction if none is given
+    fn source(&self, rover: usize, instruction: Option<usize>) -> Option<&Provenance> {
+        // The ID of the rover starts from one
+        let instructions = self.rovers.get(rover.checked_sub(1)?)?;
+        match instruction {
+            Some(instruction) => instructions.get(instruction),
+            None => instructions.last(),
+        }
+    }
+
+    /// The file a source was written in, unless it is the implied file
+    pub fn named_file(&self, source: &Provenance) -> Option<PathBuf> {
+        source
+            .file
+            .clone()
+            .filter(|file| Some(file) != self.implied.as_ref())
+    }
+
+    /// Point an error raised whilst executing at the line its instruction was written on
+    pub fn locate(&self, err: RoverErr) -> RoverErr {
+        let source = match &err {
+            RoverErr::Boundery(rover, instruction)
+            | RoverErr::Crater(rover, instruction)
+            | RoverErr::Hazard(rover, instruction)
+            | RoverErr::BudgetExceeded(rover, instruction, _) => {
+                self.source(rover.id, Some(*instruction))
+            }
+            _ => None,
+        };
+        let Some(source) = source else {
+            return err;
+        };
+
+        let err = err.with_line_index(source.line);
+        match self.named_file(source) {
+            Some(file) => RoverErr::InFile(file, Box::new(err)),
+            None => err,
+        }
+    }
+
+    /// Point a warning raised whilst executing at the line its instruction was written on
+    pub fn locate_warning(&self, warning: Warning) -> Warning {
+        let Some(source) = warning
+            .raised_by()
+            .and_then(|(rover, instruction)| self.source(rover, instruction))
+        else {
+            return warning;
+        };
+
+        let warning = warning.with_line_index(source.line);
+        match self.named_file(source) {
+            Some(path) => Warning::InFile {
+                path,
+                warning: Box::new(warning),
+            },
+            None => warning,
+        }
+    }
+}
+
+/// Replace every `${VAR}` in a line, failing on the first variable that is undefined or not
+/// closed
+///
+/// The error is raised on line zero, the caller knows which line it was.
+pub fn substitute(line: &str, defines: &Defines) -> Result<String, RoverErr> {
+    substitute_with_spans(line, defines).map(|(output, _)| output)
+}
+
+/// Replace every `${VAR}` in a line, along with where each value was written in the output
+pub fn substitute_with_spans(
+    line: &str,
+    defines: &Defines,
+) -> Result<(String, Vec<MacroSpan>), RoverErr> {
+    let mut output = String::new();
+    let mut spans = Vec::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(RoverErr::UnclosedVariable(0));
+        };
+
+        let name = &rest[start + 2..start + end];
+        let value = defines
+            .get(name)
+            .cloned()
+            .or_else(|| env::var(name).ok())
+            .ok_or_else(|| RoverErr::UndefinedVariable(name.to_string(), 0))?;
+        spans.push((output.len()..output.len() + value.len(), name.to_string()));
+        output.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok((output, spans))
+}
+
+/// Parse a `KEY=VALUE` pair given to `--define`
+pub fn parse_define(input: &str) -> Result<(String, String), String> {
+    input
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected `KEY=VALUE`, found `{input}`"))
+}
+
+#[cfg(test)]
+mod expand_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod substitute {
+        use super::{substitute, Defines};
+        use crate::enums::RoverErr;
+
+        #[test]
+        fn valid_input() {
+            let defines = Defines::from([("SIZE".to_string(), "5".to_string())]);
+            let result = substitute("${SIZE} ${SIZE}", &defines);
+            assert_eq!(result.unwrap(), "5 5");
+        }
+
+        #[test]
+        fn undefined_variable() {
+            let result = substitute("${ROVER_CLI_UNDEFINED_VARIABLE}", &Defines::new());
+            assert!(matches!(
+                result,
+                Err(RoverErr::UndefinedVariable(name, 0)) if name == "ROVER_CLI_UNDEFINED_VARIABLE"
+            ));
+        }
+
+        #[test]
+        fn unclosed_variable() {
+            let defines = Defines::from([("SIZE".to_string(), "5".to_string())]);
+            let result = substitute("${SIZE} ${SIZE", &defines);
+            assert!(matches!(result, Err(RoverErr::UnclosedVariable(0))));
+        }
+    }
+
+    #[cfg(test)]
+    mod expansion {
+        use std::{env, fs};
+
+        use super::{Defines, Expansion, Provenance};
+        use crate::{
+            diagnostics::Warning,
+            enums::{ParsingErr, RoverErr},
+        };
+
+        #[test]
+        fn includes_file() {
+            let dir = env::temp_dir().join(format!("rover-include-{}", std::process::id()));
+            fs::create_dir_all(dir.join("common")).unwrap();
+            fs::write(dir.join("common/launch.txt"), "1 2 N\nLMLMLMLMM").unwrap();
+            let path = dir.join("mission.txt");
+
+            let expansion = Expansion::new(
+                "5 5\n!include common/launch.txt\n3 3 E\nM",
+                Some(&path),
+                &Defines::new(),
+            )
+            .unwrap();
+            assert_eq!(expansion.text, "5 5\n1 2 N\nLMLMLMLMM\n3 3 E\nM");
+            assert_eq!(expansion.included(), [dir.join("common/launch.txt")]);
+
+            // The third line of the expanded text is the second line of the included file
+            let err = expansion.locate(RoverErr::Parse(ParsingErr::UnexpectedToken, 2));
+            assert!(matches!(
+                err,
+                RoverErr::InFile(include, error)
+                    if include == dir.join("common/launch.txt")
+                        && matches!(*error, RoverErr::Parse(_, 1))
+            ));
+            let err = expansion.locate(RoverErr::Parse(ParsingErr::UnexpectedToken, 3));
+            assert!(matches!(err, RoverErr::Parse(_, 2)));
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn provenance() {
+            let dir = env::temp_dir().join(format!("rover-provenance-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("launch.txt"), "1 2 N\nL${TURN}M").unwrap();
+            let path = dir.join("mission.txt");
+            let defines = Defines::from([("TURN".to_string(), "R R".to_string())]);
+
+            let expansion = Expansion::new(
+                "5 5\n!include launch.txt\n3 3 E\nM ${TURN}",
+                Some(&path),
+                &defines,
+            )
+            .unwrap();
+            assert_eq!(
+                expansion.provenance(2),
+                vec![
+                    Provenance {
+                        file: Some(dir.join("launch.txt")),
+                        line: 1,
+                        expansion: None,
+                    },
+                    Provenance {
+                        file: Some(dir.join("launch.txt")),
+                        line: 1,
+                        expansion: Some(("TURN".to_string(), 0)),
+                    },
+                    Provenance {
+                        file: Some(dir.join("launch.txt")),
+                        line: 1,
+                        expansion: Some(("TURN".to_string(), 1)),
+                    },
+                    Provenance {
+                        file: Some(dir.join("launch.txt")),
+                        line: 1,
+                        expansion: None,
+                    },
+                ]
+            );
+            let written = expansion.provenance(4);
+            assert_eq!(written[0].file, Some(path.clone()));
+            assert_eq!(written[0].line, 3);
+            assert_eq!(written[2].expansion, Some(("TURN".to_string(), 1)));
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn includes_itself() {
+            let dir = env::temp_dir().join(format!("rover-cycle-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("a.txt"), "5 5\n!include b.txt").unwrap();
+            fs::write(dir.join("b.txt"), "!include a.txt").unwrap();
+            let path = dir.join("a.txt");
+
+            let text = fs::read_to_string(&path).unwrap();
+            let result = Expansion::new(&text, Some(&path), &Defines::new());
+            assert!(matches!(
+                result,
+                Err(RoverErr::InFile(include, error))
+                    if include == dir.join("b.txt")
+                        && matches!(*error, RoverErr::IncludeCycle(_, 0))
+            ));
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn multiline_value() {
+            let defines = Defines::from([("ROVER".to_string(), "1 2 N\nLM".to_string())]);
+            let expansion = Expansion::new("5 5\n${ROVER}\n3 3 E\nLR", None, &defines).unwrap();
+            assert_eq!(expansion.text, "5 5\n1 2 N\nLM\n3 3 E\nLR");
+
+            // Lines after the value still point at where they were written
+            let warning = expansion.locate_warning(Warning::NoOpTurns {
+                instruction: 0,
+                line: 4,
+            });
+            assert_eq!(
+                warning,
+                Warning::NoOpTurns {
+                    instruction: 0,
+                    line: 3
+                }
+            );
+            assert_eq!(
+                expansion.provenance(2)[1].expansion,
+                Some(("ROVER".to_string(), 1))
+            );
+        }
+
+        #[test]
+        fn missing_include() {
+            let result = Expansion::new("!include missing.txt", None, &Defines::new());
+            assert!(matches!(result, Err(RoverErr::Including(_, _, 0))));
+        }
+
+        #[test]
+        fn directive_needs_a_space() {
+            let expansion = Expansion::new("5 5\n!includemore.txt", None, &Defines::new()).unwrap();
+            assert_eq!(expansion.text, "5 5\n!includemore.txt");
+        }
+    }
+
+    #[cfg(test)]
+    mod unused_defines {
+        use super::{unused_defines, Defines, Expansion};
+        use crate::diagnostics::Warning;
+
+        #[test]
+        fn valid_input() {
+            let defines = Defines::from([
+                ("TURN".to_string(), "L".to_string()),
+                ("SIZE".to_string(), "5".to_string()),
+                ("MOVE".to_string(), "M".to_string()),
+                ("EXTRA".to_string(), "R".to_string()),
+            ]);
+            let expansion =
+                Expansion::new("${SIZE} ${SIZE}\n1 2 N\n${TURN}M", None, &defines).unwrap();
+            let variables = expansion.variables().map(String::from).collect::<Vec<_>>();
+
+            assert_eq!(
+                unused_defines(&defines, &variables),
+                vec![
+                    Warning::UnusedDefine {
+                        name: "EXTRA".to_string()
+                    },
+                    Warning::UnusedDefine {
+                        name: "MOVE".to_string()
+                    },
+                ]
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod source_map {
+        use std::path::PathBuf;
+
+        use super::{Provenance, SourceMap};
+        use crate::{
+            diagnostics::Warning,
+            enums::{Direction, RoverErr},
+            position::{Pose, Position},
+            rover::Rover,
+        };
+
+        fn sources() -> SourceMap {
+            let written = |file: &str, line| Provenance {
+                file: Some(PathBuf::from(file)),
+                line,
+                expansion: None,
+            };
+            SourceMap {
+                rovers: vec![
+                    vec![written("plateau.txt", 2)],
+                    vec![written("rover.txt", 1), written("rover.txt", 1)],
+                ],
+                starts: Vec::new(),
+                implied: None,
+                variables: Vec::new(),
+                included: Vec::new(),
+            }
+        }
+
+        #[test]
+        fn locates_error() {
+            let rover = Rover::new(2, Pose::new(Position::new(4, 6), Direction::North));
+            let err = sources().locate(RoverErr::Boundery(rover, 1));
+            assert!(matches!(
+                &err,
+                RoverErr::InFile(path, error)
+                    if path == &PathBuf::from("rover.txt")
+                        && matches!(**error, RoverErr::OnLine(_, 1))
+            ));
+            assert!(err.to_string().contains("Instruction 2, At Line: 2."));
+        }
+
+        #[test]
+        fn locates_warning() {
+            let warning = sources().locate_warning(Warning::EndedOnBoundery {
+                rover: 2,
+                position: Position::new(5, 3),
+            });
+            assert!(warning.to_string().starts_with("rover.txt: "));
+            assert!(warning.to_string().ends_with("At Line: 2"));
+        }
+
+        #[test]
+        fn implied_file() {
+            let sources = SourceMap {
+                implied: Some(PathBuf::from("plateau.txt")),
+                ..sources()
+            };
+            let rover = Rover::new(1, Pose::new(Position::new(1, 6), Direction::North));
+            let err = sources.locate(RoverErr::Boundery(rover, 0));
+            assert!(matches!(err, RoverErr::OnLine(_, 2)));
+        }
+    }
+}
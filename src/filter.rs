@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::multispace0,
+    combinator::{all_consuming, map, value},
+    multi::separated_list1,
+    sequence::{delimited, tuple},
+    IResult,
+};
+
+use crate::{
+    enums::Direction,
+    parse::{direction, signed_decimal},
+    rover::Rover,
+};
+
+/// A numeric property of a rover that can be compared against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Id,
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    LessOrEqual,
+    Less,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn compare(&self, left: isize, right: isize) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Less => left < right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+/// A single test such as `x>=3` or `facing==N`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Numeric(Field, Comparison, isize),
+    /// Whether the rover should (or should not) be facing the direction
+    Facing(bool, Direction),
+}
+
+impl Condition {
+    pub fn matches(&self, rover: &Rover) -> bool {
+        match self {
+            Condition::Numeric(field, comparison, value) => {
+                let actual = match field {
+                    Field::Id => rover.id as isize,
+                    Field::X => rover.pose.position.x,
+                    Field::Y => rover.pose.position.y,
+                };
+                comparison.compare(actual, *value)
+            }
+            Condition::Facing(equal, direction) => (rover.pose.facing == *direction) == *equal,
+        }
+    }
+}
+
+/// Selects which rovers are output, e.g. `x>=3 && facing==N || id==1`
+///
+/// `&&` binds more tightly than `||`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoverFilter {
+    alternatives: Vec<Vec<Condition>>,
+}
+
+impl RoverFilter {
+    pub fn matches(&self, rover: &Rover) -> bool {
+        self.alternatives
+            .iter()
+            .any(|conditions| conditions.iter().all(|condition| condition.matches(rover)))
+    }
+}
+
+impl FromStr for RoverFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        all_consuming(delimited(multispace0, filter, multispace0))(s)
+            .map(|(_, filter)| filter)
+            .map_err(|_| format!("invalid filter expression `{s}`"))
+    }
+}
+
+/// Parse an operator surrounded by optional whitespace
+fn operator<'a>(op: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(multispace0, tag(op), multispace0)
+}
+
+/// Parse a comparison, longer operators are tried first so `<=` is not read as `<`
+fn comparison(input: &str) -> IResult<&str, Comparison> {
+    alt((
+        value(Comparison::Equal, operator("==")),
+        value(Comparison::NotEqual, operator("!=")),
+        value(Comparison::LessOrEqual, operator("<=")),
+        value(Comparison::GreaterOrEqual, operator(">=")),
+        value(Comparison::Less, operator("<")),
+        value(Comparison::Greater, operator(">")),
+    ))(input)
+}
+
+/// Parse a condition on the rover's ID or position
+fn numeric_condition(input: &str) -> IResult<&str, Condition> {
+    map(
+        tuple((
+            alt((
+                value(Field::Id, tag_no_case("id")),
+                value(Field::X, tag_no_case("x")),
+                value(Field::Y, tag_no_case("y")),
+            )),
+            comparison,
+            signed_decimal,
+        )),
+        |(field, comparison, value)| Condition::Numeric(field, comparison, value),
+    )(input)
+}
+
+/// Parse a condition on the direction the rover is facing
+fn facing_condition(input: &str) -> IResult<&str, Condition> {
+    map(
+        tuple((
+            tag_no_case("facing"),
+            alt((value(true, operator("==")), value(false, operator("!=")))),
+            direction,
+        )),
+        |(_, equal, direction)| Condition::Facing(equal, direction),
+    )(input)
+}
+
+/// Parse a full filter expression
+fn filter(input: &str) -> IResult<&str, RoverFilter> {
+    map(
+        separated_list1(
+            operator("||"),
+            separated_list1(operator("&&"), alt((facing_condition, numeric_condition))),
+        ),
+        |alternatives| RoverFilter { alternatives },
+    )(input)
+}
+
+#[cfg(test)]
+mod filter_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod rover_filter {
+        use super::RoverFilter;
+        use crate::{
+            enums::Direction,
+            position::{Pose, Position},
+            rover::Rover,
+        };
+
+        fn rover(id: usize, x: isize, y: isize, facing: Direction) -> Rover {
+            Rover::new(id, Pose::new(Position::new(x, y), facing))
+        }
+
+        #[test]
+        fn valid_input() {
+            let filter: RoverFilter = "x>=3 && facing==N".parse().unwrap();
+            assert!(filter.matches(&rover(1, 3, 0, Direction::North)));
+            assert!(!filter.matches(&rover(1, 2, 0, Direction::North)));
+            assert!(!filter.matches(&rover(1, 3, 0, Direction::East)));
+        }
+
+        #[test]
+        fn alternatives() {
+            let filter: RoverFilter = "id == 1 || y < -1".parse().unwrap();
+            assert!(filter.matches(&rover(1, 0, 0, Direction::North)));
+            assert!(filter.matches(&rover(2, 0, -2, Direction::North)));
+            assert!(!filter.matches(&rover(2, 0, 0, Direction::North)));
+        }
+
+        #[test]
+        fn invalid_comparison_for_facing() {
+            let result = "facing>N".parse::<RoverFilter>();
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn trailing_operator() {
+            let result = "x==1 &&".parse::<RoverFilter>();
+            assert!(result.is_err());
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{
+    diagnostics::Warning,
+    enums::{RoverErr, SchemaVersion},
+    position::Position,
+    render,
+    rover::{Rover, RoverControlSatellite},
+};
+
+/// A way of writing out the final positions of the rovers
+///
+/// Implement this and register it with `Formatters` to add a `--format` of your own. The
+/// warnings raised by the run are also printed on their own, so formats may leave them out.
+/// Formats with a layout that has changed over time write the one `version` asks for.
+pub trait OutputFormatter {
+    fn format(
+        &self,
+        rovers: &[Rover],
+        bounderies: Position,
+        warnings: &[Warning],
+        version: SchemaVersion,
+    ) -> Result<String, RoverErr>;
+}
+
+/// One line per rover, e.g. `1 3 N`
+pub struct Text;
+
+impl OutputFormatter for Text {
+    fn format(
+        &self,
+        rovers: &[Rover],
+        _: Position,
+        _: &[Warning],
+        _: SchemaVersion,
+    ) -> Result<String, RoverErr> {
+        Ok(RoverControlSatellite::format_report(rovers))
+    }
+}
+
+/// A JSON list of the rovers
+///
+/// From version 2 the list is the `rovers` of an object that also holds its `schema_version` and
+/// the `diagnostics` raised by the run.
+pub struct Json;
+
+impl OutputFormatter for Json {
+    fn format(
+        &self,
+        rovers: &[Rover],
+        _: Position,
+        warnings: &[Warning],
+        version: SchemaVersion,
+    ) -> Result<String, RoverErr> {
+        let rovers = rovers.iter().map(Rover::to_json).collect::<Vec<String>>();
+
+        Ok(match version {
+            SchemaVersion::V1 => match rovers.is_empty() {
+                true => "[]".to_string(),
+                false => format!("[\n  {}\n]", rovers.join(",\n  ")),
+            },
+            SchemaVersion::V2 => {
+                let list = |items: Vec<String>| match items.is_empty() {
+                    true => "[]".to_string(),
+                    false => format!("[\n    {}\n  ]", items.join(",\n    ")),
+                };
+                format!(
+                    "{{\n  \"schema_version\": {},\n  \"rovers\": {},\n  \"diagnostics\": {}\n}}",
+                    version.number(),
+                    list(rovers),
+                    list(warnings.iter().map(Warning::to_json).collect()),
+                )
+            }
+        })
+    }
+}
+
+/// A CSV with a header row
+///
+/// From version 2 every row starts with its `schema_version`.
+pub struct Csv;
+
+impl OutputFormatter for Csv {
+    fn format(
+        &self,
+        rovers: &[Rover],
+        _: Position,
+        _: &[Warning],
+        version: SchemaVersion,
+    ) -> Result<String, RoverErr> {
+        let prefix = match version {
+            SchemaVersion::V1 => String::new(),
+            SchemaVersion::V2 => format!("{},", version.number()),
+        };
+        let mut csv = match version {
+            SchemaVersion::V1 => String::from("id,x,y,facing,status,odometer,turns"),
+            SchemaVersion::V2 => String::from("schema_version,id,x,y,facing,status,odometer,turns"),
+        };
+        for rover in rovers {
+            csv.push_str(&format!(
+                "\n{prefix}{},{},{},{},{},{},{}",
+                rover.id,
+                rover.pose.position.x,
+                rover.pose.position.y,
+                rover.pose.facing,
+                rover.status(),
+                rover.odometer,
+                rover.turns
+            ));
+        }
+        Ok(csv)
+    }
+}
+
+/// A line per rover with `{id}`, `{x}`, `{y}`, `{facing}`, `{status}`, `{odometer}` and `{turns}`
+/// filled in
+pub struct Template(pub String);
+
+impl Default for Template {
+    fn default() -> Self {
+        Template("{id}: {x} {y} {facing} {status}".to_string())
+    }
+}
+
+impl OutputFormatter for Template {
+    fn format(
+        &self,
+        rovers: &[Rover],
+        _: Position,
+        _: &[Warning],
+        _: SchemaVersion,
+    ) -> Result<String, RoverErr> {
+        Ok(rovers
+            .iter()
+            .map(|rover| {
+                self.0
+                    .replace("{id}", &rover.id.to_string())
+                    .replace("{x}", &rover.pose.position.x.to_string())
+                    .replace("{y}", &rover.pose.position.y.to_string())
+                    .replace("{facing}", &rover.pose.facing.to_string())
+                    .replace("{status}", rover.status())
+                    .replace("{odometer}", &rover.odometer.to_string())
+                    .replace("{turns}", &rover.turns.to_string())
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+}
+
+/// The plateau drawn as an ASCII grid
+pub struct Grid;
+
+impl OutputFormatter for Grid {
+    fn format(
+        &self,
+        rovers: &[Rover],
+        bounderies: Position,
+        _: &[Warning],
+        _: SchemaVersion,
+    ) -> Result<String, RoverErr> {
+        render::render_frame(bounderies, rovers)
+    }
+}
+
+/// The output formats available by name
+pub struct Formatters {
+    formatters: Vec<(String, Box<dyn OutputFormatter>)>,
+}
+
+impl Default for Formatters {
+    /// The built in formats: `text`, `json`, `csv`, `template` and `grid`
+    fn default() -> Self {
+        let mut formatters = Formatters {
+            formatters: Vec::new(),
+        };
+        formatters.register("text", Box::new(Text));
+        formatters.register("json", Box::new(Json));
+        formatters.register("csv", Box::new(Csv));
+        formatters.register("template", Box::new(Template::default()));
+        formatters.register("grid", Box::new(Grid));
+        formatters
+    }
+}
+
+impl Formatters {
+    /// Add a format, replacing any already registered with the same name
+    pub fn register(&mut self, name: &str, formatter: Box<dyn OutputFormatter>) {
+        self.formatters.retain(|(existing, _)| existing != name);
+        self.formatters.push((name.to_string(), formatter));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn OutputFormatter> {
+        self.formatters
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, formatter)| formatter.as_ref())
+    }
+
+    /// The names of the registered formats, in the order they were registered
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod format_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod formatters {
+        use super::{Formatters, OutputFormatter, Template};
+        use crate::{
+            diagnostics::Warning,
+            enums::{Direction, RoverErr, SchemaVersion},
+            position::Pose,
+            position::Position,
+            rover::Rover,
+        };
+
+        fn rovers() -> Vec<Rover> {
+            let mut rovers = vec![
+                Rover::new(1, Pose::new(Position::new(1, 3), Direction::North)),
+                Rover::new(2, Pose::new(Position::new(5, 1), Direction::East)),
+            ];
+            rovers[1].halted = true;
+            rovers
+        }
+
+        #[test]
+        fn built_in() {
+            let formatters = Formatters::default();
+            let format = |name: &str| {
+                formatters
+                    .get(name)
+                    .unwrap()
+                    .format(&rovers(), Position::new(5, 3), &[], SchemaVersion::V1)
+                    .unwrap()
+            };
+
+            assert_eq!(format("text"), "1 3 N\n5 1 E halted");
+            assert_eq!(
+                format("json"),
+                "[\n  {\"id\": 1, \"x\": 1, \"y\": 3, \"facing\": \"N\", \"status\": \"active\", \"halted\": false, \"lost\": false, \"degraded\": false, \"odometer\": 0, \"turns\": 0},\n  {\"id\": 2, \"x\": 5, \"y\": 1, \"facing\": \"E\", \"status\": \"halted\", \"halted\": true, \"lost\": false, \"degraded\": false, \"odometer\": 0, \"turns\": 0}\n]"
+            );
+            assert_eq!(
+                format("csv"),
+                "id,x,y,facing,status,odometer,turns\n1,1,3,N,active,0,0\n2,5,1,E,halted,0,0"
+            );
+            assert_eq!(format("template"), "1: 1 3 N active\n2: 5 1 E halted");
+            assert_eq!(format("grid"), ".^....\n......\n.....>\n......");
+        }
+
+        #[test]
+        fn schema_version() {
+            let warnings = [Warning::UnusedDefine {
+                name: "TURN".to_string(),
+            }];
+            let formatters = Formatters::default();
+            let format = |name: &str| {
+                formatters
+                    .get(name)
+                    .unwrap()
+                    .format(
+                        &rovers()[..1],
+                        Position::new(5, 3),
+                        &warnings,
+                        SchemaVersion::V2,
+                    )
+                    .unwrap()
+            };
+
+            assert_eq!(
+                format("json"),
+                "{\n  \"schema_version\": 2,\n  \"rovers\": [\n    {\"id\": 1, \"x\": 1, \"y\": 3, \"facing\": \"N\", \"status\": \"active\", \"halted\": false, \"lost\": false, \"degraded\": false, \"odometer\": 0, \"turns\": 0}\n  ],\n  \"diagnostics\": [\n    {\"code\": \"unused-define\", \"message\": \"Rover Warning ⚠️ - TURN was defined with --define but never used\"}\n  ]\n}"
+            );
+            assert_eq!(
+                format("csv"),
+                "schema_version,id,x,y,facing,status,odometer,turns\n2,1,1,3,N,active,0,0"
+            );
+            assert_eq!(format("text"), "1 3 N");
+        }
+
+        #[test]
+        fn registered() {
+            struct Count;
+            impl OutputFormatter for Count {
+                fn format(
+                    &self,
+                    rovers: &[Rover],
+                    _: Position,
+                    _: &[Warning],
+                    _: SchemaVersion,
+                ) -> Result<String, RoverErr> {
+                    Ok(rovers.len().to_string())
+                }
+            }
+
+            let mut formatters = Formatters::default();
+            formatters.register("count", Box::new(Count));
+            formatters.register("template", Box::new(Template("{id}".to_string())));
+
+            assert_eq!(
+                formatters.names(),
+                vec!["text", "json", "csv", "grid", "count", "template"]
+            );
+            assert_eq!(
+                formatters
+                    .get("count")
+                    .unwrap()
+                    .format(&rovers(), Position::new(5, 3), &[], SchemaVersion::V2)
+                    .unwrap(),
+                "2"
+            );
+            assert_eq!(
+                formatters
+                    .get("template")
+                    .unwrap()
+                    .format(&rovers(), Position::new(5, 3), &[], SchemaVersion::V2)
+                    .unwrap(),
+                "1\n2"
+            );
+            assert!(formatters.get("yaml").is_none());
+        }
+    }
+}
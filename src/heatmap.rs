@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::{
+    enums::{Instruction, RoverErr, SchemaVersion},
+    position::{Position, MAX_CELLS},
+    rover::Mission,
+    trace::Trace,
+};
+
+/// How many times each cell of the plateau was traversed, across all rovers
+///
+/// A rover's starting cell counts as a visit, as does every cell it moves onto. Cells outside of
+/// the plateau are not counted. Only visited cells are kept, so the plateau can be any size.
+#[derive(Debug, PartialEq)]
+pub struct Heatmap {
+    pub bounderies: Position,
+    pub counts: HashMap<Position, usize>,
+}
+
+impl Heatmap {
+    /// Count the cells the rovers of a mission visited, from an unfiltered trace of its execution
+    pub fn new(mission: &Mission, trace: &Trace) -> Self {
+        let mut heatmap = Heatmap {
+            bounderies: mission.bounderies,
+            counts: HashMap::new(),
+        };
+
+        // Where each rover was last seen, a move that was refused does not visit its cell again
+        let mut positions = HashMap::new();
+        for event in trace.events() {
+            let position = event.pose.position;
+            let visited = match event.instruction {
+                None => true,
+                Some(Instruction::Move) => positions.get(&event.rover) != Some(&position),
+                Some(_) => false,
+            };
+            if visited && position.within_bounderies(heatmap.bounderies) {
+                *heatmap.counts.entry(position).or_default() += 1;
+            }
+            positions.insert(event.rover, position);
+        }
+
+        heatmap
+    }
+
+    /// Format the heatmap as a CSV matrix, laid out like the plateau with the highest row first
+    ///
+    /// From version 2 every row starts with the schema version. Plateaus with more than
+    /// [`MAX_CELLS`] cells are refused, as the matrix would be too large to write.
+    pub fn to_csv(&self, version: SchemaVersion) -> Result<String, RoverErr> {
+        let Position {
+            x: width,
+            y: height,
+        } = self.bounderies;
+        if self.bounderies.cells() > MAX_CELLS {
+            return Err(RoverErr::OversizedPlateau(self.bounderies, MAX_CELLS));
+        }
+
+        let prefix = match version {
+            SchemaVersion::V1 => String::new(),
+            SchemaVersion::V2 => format!("{},", version.number()),
+        };
+
+        Ok((0..=height)
+            .rev()
+            .map(|y| {
+                let row = (0..=width)
+                    .map(|x| {
+                        let count = self.counts.get(&Position::new(x, y));
+                        count.copied().unwrap_or_default().to_string()
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",");
+                format!("{prefix}{row}")
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod heatmap_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod heatmap {
+        use super::Heatmap;
+        use crate::{
+            budget::Budget,
+            diagnostics::Diagnostics,
+            enums::{Direction, Instruction, RoverErr, SchemaVersion},
+            position::{Pose, Position, MAX_CELLS},
+            rover::{Attributes, Mission, RoverControlSatellite},
+            trace::{Trace, TraceFilter},
+            world::World,
+        };
+
+        /// Trace every event of a mission, up to where it failed if it did
+        fn traced(mission: &Mission) -> Trace {
+            let mut trace = Trace::new(TraceFilter::default());
+            let _ = RoverControlSatellite::execute_mission(
+                mission,
+                &mut World::default(),
+                &mut Budget::default(),
+                &mut Diagnostics::default(),
+                &mut trace,
+            );
+            trace
+        }
+
+        #[test]
+        fn overlapping_paths() {
+            let mission = Mission {
+                bounderies: Position::new(2, 1),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(0, 0), Direction::East),
+                        vec![Instruction::Move, Instruction::Left, Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(0, 0), Direction::East),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            assert_eq!(
+                Heatmap::new(&mission, &traced(&mission))
+                    .to_csv(SchemaVersion::V1)
+                    .unwrap(),
+                "0,1,0\n2,2,0"
+            );
+            assert_eq!(
+                Heatmap::new(&mission, &traced(&mission))
+                    .to_csv(SchemaVersion::V2)
+                    .unwrap(),
+                "2,0,1,0\n2,2,2,0"
+            );
+        }
+
+        #[test]
+        fn ignores_cells_outside_plateau() {
+            let mission = Mission {
+                bounderies: Position::new(0, 0),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::West),
+                    vec![Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            assert_eq!(
+                Heatmap::new(&mission, &traced(&mission))
+                    .to_csv(SchemaVersion::V1)
+                    .unwrap(),
+                "1"
+            );
+        }
+
+        #[test]
+        fn oversized_plateau() {
+            let mission = Mission {
+                bounderies: Position::new(100_000, 100_000),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::North),
+                    vec![Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            let heatmap = Heatmap::new(&mission, &traced(&mission));
+            assert_eq!(heatmap.counts.len(), 2);
+            assert!(matches!(
+                heatmap.to_csv(SchemaVersion::V1),
+                Err(RoverErr::OversizedPlateau(_, MAX_CELLS))
+            ));
+        }
+    }
+}
@@ -0,0 +1,676 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostics::{Diagnostics, Warning},
+    dialect,
+    enums::{InputFormat, InstructionLocale, RoverErr},
+    expand::{self, Defines, Expansion, SourceMap},
+    parse::{coordinate, rover_header},
+    plateau::Plateau,
+    position::Position,
+    rover::{Mission, ResumedStream, RoverControlSatellite},
+};
+
+/// The environment variable read for the instructions when no other source is given
+pub const MISSION_ENV: &str = "MARS_ROVER_MISSION";
+
+/// The directive a mission gives in place of its bounderies to take its plateau from a world file
+pub const WORLD_DIRECTIVE: &str = "WORLD";
+
+/// Where the instructions are read from
+#[derive(Debug, PartialEq)]
+pub enum MissionSource {
+    /// The first file supplies the plateau, the rest add further rovers
+    Files(Vec<PathBuf>),
+    Inline(String),
+    Env(String),
+}
+
+impl MissionSource {
+    /// Pick a source by precedence: input paths, then `--inline`, then `MARS_ROVER_MISSION`
+    pub fn resolve(input_paths: Vec<PathBuf>, inline: Option<String>) -> Result<Self, RoverErr> {
+        Self::resolve_with_env(input_paths, inline, env::var(MISSION_ENV).ok())
+    }
+
+    pub fn resolve_with_env(
+        input_paths: Vec<PathBuf>,
+        inline: Option<String>,
+        env: Option<String>,
+    ) -> Result<Self, RoverErr> {
+        match (input_paths.is_empty(), inline, env) {
+            (false, _, _) => Ok(MissionSource::Files(input_paths)),
+            (true, Some(inline), _) => Ok(MissionSource::Inline(inline)),
+            (true, None, Some(env)) => Ok(MissionSource::Env(env)),
+            (true, None, None) => Err(RoverErr::MissingInput),
+        }
+    }
+
+    /// Read the instructions, inline and environment instructions may use `\n` for new lines
+    pub fn read(self) -> Result<Vec<MissionText>, RoverErr> {
+        match self {
+            MissionSource::Files(paths) => paths
+                .into_iter()
+                .map(|path| {
+                    // Anything that is not text is protobuf or binary, whatever its extension
+                    let text = String::from_utf8(fs::read(&path).map_err(RoverErr::Opening)?)
+                        .map_err(|err| {
+                            RoverErr::UnsupportedFormat(dialect::detect_binary(err.as_bytes()))
+                        })?;
+                    Ok(MissionText {
+                        path: Some(path),
+                        text,
+                        origins: None,
+                    })
+                })
+                .collect(),
+            MissionSource::Inline(text) | MissionSource::Env(text) => Ok(vec![MissionText {
+                path: None,
+                text: unescape_newlines(&text),
+                origins: None,
+            }]),
+        }
+    }
+}
+
+/// The contents of one source of instructions
+#[derive(Debug, PartialEq)]
+pub struct MissionText {
+    pub path: Option<PathBuf>,
+    pub text: String,
+    /// The line each line of the text was written on, if it was converted from another format
+    pub origins: Option<Vec<usize>>,
+}
+
+/// Rewrite each text in the classic format, detecting the format of each unless one is given
+pub fn to_classic(
+    texts: Vec<MissionText>,
+    format: Option<InputFormat>,
+) -> Result<Vec<MissionText>, RoverErr> {
+    texts
+        .into_iter()
+        .map(|MissionText { path, text, .. }| {
+            let format = format.unwrap_or_else(|| dialect::detect(&text));
+            let (text, origins) = dialect::to_classic(text, format)?;
+            Ok(MissionText {
+                path,
+                text,
+                origins,
+            })
+        })
+        .collect()
+}
+
+/// Get mission texts ready to parse, converting each to the classic format and replacing any
+/// `WORLD` line with the bounderies of its world file, see [`resolve_worlds`]
+///
+/// Every command reads its missions through here, so they all accept the same files.
+pub fn prepare(
+    texts: Vec<MissionText>,
+    format: Option<InputFormat>,
+    defines: &Defines,
+) -> Result<(Vec<MissionText>, Vec<Plateau>), RoverErr> {
+    let mut texts = to_classic(texts, format)?;
+    let plateaus = resolve_worlds(&mut texts, defines)?;
+    Ok((texts, plateaus))
+}
+
+/// The path of the world file a `WORLD <path>` line names, if the line is one
+pub fn world_directive(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix(WORLD_DIRECTIVE)
+        .filter(|path| path.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+/// The world file named by the `WORLD <path>` line starting the text, if it starts with one
+///
+/// The path is relative to the text's own path, and may use `${VAR}` variables.
+pub fn world_file(text: &MissionText, defines: &Defines) -> Option<Result<PathBuf, RoverErr>> {
+    let world_path = world_directive(text.text.lines().next()?)?;
+    Some(expand::substitute(world_path, defines).map(|world_path| {
+        text.path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""))
+            .join(world_path)
+    }))
+}
+
+/// The names of the variables the `WORLD <path>` line starting the text uses, if it starts with
+/// one
+pub fn world_variables(text: &MissionText, defines: &Defines) -> Vec<String> {
+    text.text
+        .lines()
+        .next()
+        .and_then(world_directive)
+        .and_then(|world_path| expand::substitute_with_spans(world_path, defines).ok())
+        .map(|(_, spans)| spans.into_iter().map(|(_, name)| name).collect())
+        .unwrap_or_default()
+}
+
+/// Replace the `WORLD <path>` line starting any of the texts with the bounderies of the world
+/// file it names, see [`world_file`], returning the world files in the order they were given
+pub fn resolve_worlds(
+    texts: &mut [MissionText],
+    defines: &Defines,
+) -> Result<Vec<Plateau>, RoverErr> {
+    let several = texts.len() > 1;
+    let mut plateaus = Vec::new();
+
+    for text in texts.iter_mut() {
+        let Some(world_path) = world_file(text, defines) else {
+            continue;
+        };
+
+        let result = world_path.and_then(|world_path| {
+            let file = fs::read_to_string(&world_path).map_err(|e| {
+                RoverErr::InFile(world_path.clone(), Box::new(RoverErr::OpeningPlateau(e)))
+            })?;
+            Plateau::parse(&file).map_err(|err| RoverErr::InFile(world_path, Box::new(err)))
+        });
+        let plateau = result.map_err(|err| match &text.path {
+            Some(path) if several => RoverErr::InFile(path.clone(), Box::new(err)),
+            _ => err,
+        })?;
+
+        // The line is replaced rather than removed, so errors still name the lines as written
+        let rest = text.text.split_once('\n').map_or("", |(_, rest)| rest);
+        let Position { x, y } = plateau.bounderies;
+        text.text = format!("{x} {y}\n{rest}");
+        plateaus.push(plateau);
+    }
+
+    Ok(plateaus)
+}
+
+/// Expand and parse the texts into a single mission, the first supplies the plateau
+///
+/// Errors are reported against the lines as written, naming the file when there are several
+/// texts or the line came from an included file.
+pub fn parse_mission(
+    texts: Vec<MissionText>,
+    locale: InstructionLocale,
+    defines: &Defines,
+    diagnostics: &mut Diagnostics,
+) -> Result<Mission, RoverErr> {
+    parse_mission_with_provenance(texts, locale, defines, diagnostics).map(|(mission, _)| mission)
+}
+
+/// Expand and parse the texts into a single mission, along with where each rover's instructions
+/// were written
+///
+/// Warnings are pointed at the lines as written, like errors.
+pub fn parse_mission_with_provenance(
+    texts: Vec<MissionText>,
+    locale: InstructionLocale,
+    defines: &Defines,
+    diagnostics: &mut Diagnostics,
+) -> Result<(Mission, SourceMap), RoverErr> {
+    let several = texts.len() > 1;
+    let mut mission: Option<Mission> = None;
+    let mut sources = SourceMap {
+        rovers: Vec::new(),
+        starts: Vec::new(),
+        implied: texts
+            .first()
+            .and_then(|text| text.path.clone())
+            .filter(|_| !several),
+        variables: Vec::new(),
+        included: Vec::new(),
+    };
+    // The `id=` of each rover, in the order they were written
+    let mut ids = Vec::new();
+
+    for MissionText {
+        path,
+        text,
+        origins,
+    } in texts
+    {
+        let parsed = diagnostics.len();
+        let result =
+            expand(&text, path.as_deref(), origins.as_deref(), defines).and_then(|expansion| {
+                let result = match &mut mission {
+                    None => RoverControlSatellite::parse_incoming_message(
+                        expansion.text.clone(),
+                        locale,
+                        diagnostics,
+                    )
+                    .map(|first| mission = Some(first)),
+                    Some(mission) => RoverControlSatellite::parse_additional_message(
+                        mission,
+                        expansion.text.clone(),
+                        locale,
+                        diagnostics,
+                    ),
+                };
+                diagnostics.relocate(parsed, |warning| expansion.locate_warning(warning));
+                result.map_err(|err| expansion.locate(err))?;
+                sources
+                    .variables
+                    .extend(expansion.variables().map(String::from));
+                sources
+                    .included
+                    .extend(expansion.included().iter().cloned());
+
+                // Each rover is a starting position line followed by an instructions line, after
+                // the bounderies if the text has them
+                let first = match expansion.text.lines().next().map(str::trim).map(coordinate) {
+                    Some(Ok(("", _))) => 1,
+                    _ => 0,
+                };
+                let rovers = mission.as_ref().map_or(0, |mission| mission.rovers.len());
+                for rover in 0..rovers - sources.rovers.len() {
+                    let header = expansion.text.lines().nth(first + rover * 2);
+                    ids.push(
+                        header
+                            .and_then(|header| rover_header(header.trim()).ok())
+                            .and_then(|(_, (_, attributes))| attributes.id),
+                    );
+                    sources
+                        .starts
+                        .extend(expansion.provenance(first + rover * 2).into_iter().next());
+                    sources
+                        .rovers
+                        .push(expansion.provenance(first + rover * 2 + 1));
+                }
+                Ok(())
+            });
+        if let Some(path) = path.as_ref().filter(|_| several) {
+            diagnostics.relocate(parsed, |warning| match warning {
+                Warning::InFile { .. } => warning,
+                warning => Warning::InFile {
+                    path: path.clone(),
+                    warning: Box::new(warning),
+                },
+            });
+        }
+
+        result.map_err(|err| match path {
+            Some(path) if several => RoverErr::InFile(path, Box::new(err)),
+            _ => err,
+        })?;
+    }
+
+    // Rovers given an `id=` were put in the order of their IDs, see `Mission::number`
+    let mut order = (0..ids.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|index| ids[*index]);
+    sources.rovers = order
+        .iter()
+        .filter_map(|index| sources.rovers.get(*index).cloned())
+        .collect();
+    sources.starts = order
+        .iter()
+        .filter_map(|index| sources.starts.get(*index).cloned())
+        .collect();
+
+    mission
+        .map(|mission| (mission, sources))
+        .ok_or(RoverErr::MissingInput)
+}
+
+/// Expand and parse the texts into further instructions for the rovers of a saved world, along
+/// with where each rover's instructions were written
+pub fn parse_resumption(
+    texts: Vec<MissionText>,
+    locale: InstructionLocale,
+    defines: &Defines,
+) -> Result<(Vec<ResumedStream>, SourceMap), RoverErr> {
+    let several = texts.len() > 1;
+    let mut streams = Vec::new();
+    let mut sources = SourceMap {
+        rovers: Vec::new(),
+        starts: Vec::new(),
+        implied: texts
+            .first()
+            .and_then(|text| text.path.clone())
+            .filter(|_| !several),
+        variables: Vec::new(),
+        included: Vec::new(),
+    };
+
+    for MissionText {
+        path,
+        text,
+        origins,
+    } in texts
+    {
+        let result =
+            expand(&text, path.as_deref(), origins.as_deref(), defines).and_then(|expansion| {
+                let parsed =
+                    RoverControlSatellite::parse_resumption_message(expansion.text.clone(), locale)
+                        .map_err(|err| expansion.locate(err))?;
+                sources
+                    .variables
+                    .extend(expansion.variables().map(String::from));
+                sources
+                    .included
+                    .extend(expansion.included().iter().cloned());
+
+                // A rover's streams are executed one after another, and its instructions end each
+                // stream's line after its ID
+                for (id, instructions, index) in &parsed {
+                    let provenance = expansion.provenance(*index);
+                    let Some(rover) = id.checked_sub(1) else {
+                        continue;
+                    };
+                    if sources.rovers.len() <= rover {
+                        sources.rovers.resize(rover + 1, Vec::new());
+                    }
+                    sources.rovers[rover].extend(
+                        provenance[provenance.len().saturating_sub(instructions.len())..]
+                            .iter()
+                            .cloned(),
+                    );
+                }
+                Ok(parsed)
+            });
+
+        match result {
+            Ok(parsed) => streams.extend(parsed),
+            Err(err) => {
+                return Err(match path {
+                    Some(path) if several => RoverErr::InFile(path, Box::new(err)),
+                    _ => err,
+                })
+            }
+        }
+    }
+
+    Ok((streams, sources))
+}
+
+/// Expand a text, pointing its lines back at where they were written if it was converted from
+/// another format
+pub fn expand(
+    text: &str,
+    path: Option<&Path>,
+    origins: Option<&[usize]>,
+    defines: &Defines,
+) -> Result<Expansion, RoverErr> {
+    let Some(origins) = origins else {
+        return Expansion::new(text, path, defines);
+    };
+    let written = |index: usize| origins.get(index).copied().unwrap_or(index);
+
+    Expansion::new(text, path, defines)
+        .map(|expansion| expansion.relocated(written))
+        .map_err(|err| match err.line_index() {
+            Some(index) => err.with_line_index(written(index)),
+            None => err,
+        })
+}
+
+/// Replace escaped `\n` sequences with new lines, so missions fit in one shell argument
+pub fn unescape_newlines(text: &str) -> String {
+    text.replace("\\n", "\n")
+}
+
+#[cfg(test)]
+mod input_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod resolve_with_env {
+        use std::path::PathBuf;
+
+        use super::MissionSource;
+
+        #[test]
+        fn path_before_inline() {
+            let result = MissionSource::resolve_with_env(
+                vec![PathBuf::from("foo.txt")],
+                Some("5 5".to_string()),
+                Some("3 3".to_string()),
+            );
+            assert_eq!(
+                result.unwrap(),
+                MissionSource::Files(vec![PathBuf::from("foo.txt")])
+            );
+        }
+
+        #[test]
+        fn inline_before_env() {
+            let result = MissionSource::resolve_with_env(
+                vec![],
+                Some("5 5".to_string()),
+                Some("3 3".to_string()),
+            );
+            assert_eq!(result.unwrap(), MissionSource::Inline("5 5".to_string()));
+        }
+
+        #[test]
+        fn env_fallback() {
+            let result = MissionSource::resolve_with_env(vec![], None, Some("3 3".to_string()));
+            assert_eq!(result.unwrap(), MissionSource::Env("3 3".to_string()));
+        }
+
+        #[test]
+        fn no_source() {
+            let result = MissionSource::resolve_with_env(vec![], None, None);
+            assert!(result.is_err());
+        }
+    }
+
+    #[cfg(test)]
+    mod read {
+        use super::MissionSource;
+
+        #[test]
+        fn escaped_new_lines() {
+            let result = MissionSource::Inline(r"5 5\n1 2 N\nLMLM".to_string()).read();
+            assert_eq!(result.unwrap()[0].text, "5 5\n1 2 N\nLMLM");
+        }
+    }
+
+    #[cfg(test)]
+    mod parse_mission {
+        use std::path::{Path, PathBuf};
+
+        use super::{parse_mission, to_classic, MissionText};
+        use crate::{
+            diagnostics::Diagnostics,
+            enums::{InstructionLocale, RoverErr},
+            expand::Defines,
+        };
+
+        #[test]
+        fn merges_rovers() {
+            let result = parse_mission(
+                vec![
+                    MissionText {
+                        path: Some(PathBuf::from("plateau.txt")),
+                        text: "5 5\n1 2 N\nM".to_string(),
+                        origins: None,
+                    },
+                    MissionText {
+                        path: Some(PathBuf::from("rover.txt")),
+                        text: "3 3 E\nM".to_string(),
+                        origins: None,
+                    },
+                ],
+                InstructionLocale::English,
+                &Defines::new(),
+                &mut Diagnostics::default(),
+            );
+            assert_eq!(result.unwrap().rovers.len(), 2);
+        }
+
+        #[test]
+        fn locates_converted_error() {
+            let texts = to_classic(
+                vec![MissionText {
+                    path: None,
+                    text: "{\"plateau\": [5, 5],\n \"rovers\": [{\"position\": [1, 2], \"facing\": \"N\",\n   \"instructions\": \"MQ\"}]}"
+                        .to_string(),
+                    origins: None,
+                }],
+                None,
+            );
+            let result = parse_mission(
+                texts.unwrap(),
+                InstructionLocale::English,
+                &Defines::new(),
+                &mut Diagnostics::default(),
+            );
+            assert_eq!(result.unwrap_err().line_index(), Some(2));
+        }
+
+        #[test]
+        fn names_file_of_error() {
+            let result = parse_mission(
+                vec![
+                    MissionText {
+                        path: Some(PathBuf::from("plateau.txt")),
+                        text: "5 5".to_string(),
+                        origins: None,
+                    },
+                    MissionText {
+                        path: Some(PathBuf::from("rover.txt")),
+                        text: "4 4\n3 3 E\nM".to_string(),
+                        origins: None,
+                    },
+                ],
+                InstructionLocale::English,
+                &Defines::new(),
+                &mut Diagnostics::default(),
+            );
+            assert!(matches!(
+                result,
+                Err(RoverErr::InFile(path, _)) if path == Path::new("rover.txt")
+            ));
+        }
+
+        #[test]
+        fn names_file_of_warning() {
+            let mut diagnostics = Diagnostics::default();
+            let result = parse_mission(
+                vec![
+                    MissionText {
+                        path: Some(PathBuf::from("plateau.txt")),
+                        text: "5 5\n1 2 N\nM".to_string(),
+                        origins: None,
+                    },
+                    MissionText {
+                        path: Some(PathBuf::from("rover.txt")),
+                        text: "3 3 E\nLRM".to_string(),
+                        origins: None,
+                    },
+                ],
+                InstructionLocale::English,
+                &Defines::new(),
+                &mut diagnostics,
+            );
+            assert!(result.is_ok());
+            assert_eq!(
+                diagnostics.warnings()[0].to_string(),
+                "rover.txt: Rover Warning ⚠️ - Turns cancel each other out: Instruction 1, At Line: 2"
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod parse_resumption {
+        use std::path::{Path, PathBuf};
+
+        use super::{parse_resumption, MissionText};
+        use crate::{
+            enums::{InstructionLocale, RoverErr},
+            expand::Defines,
+            rover::Rover,
+        };
+
+        #[test]
+        fn locates_instructions() {
+            let (streams, sources) = parse_resumption(
+                vec![
+                    MissionText {
+                        path: Some(PathBuf::from("day-2.txt")),
+                        text: "2 MM\n1 R".to_string(),
+                        origins: None,
+                    },
+                    MissionText {
+                        path: Some(PathBuf::from("day-3.txt")),
+                        text: "\n2 LM".to_string(),
+                        origins: None,
+                    },
+                ],
+                InstructionLocale::English,
+                &Defines::new(),
+            )
+            .unwrap();
+            assert_eq!(streams.len(), 3);
+            assert_eq!(sources.rovers[1].len(), 4);
+
+            // The third instruction of the second rover is on the second line of the second file
+            let err = sources.locate(RoverErr::Boundery(Rover::new(2, Default::default()), 2));
+            assert!(matches!(
+                err,
+                RoverErr::InFile(path, err)
+                    if path == Path::new("day-3.txt") && err.line_index() == Some(1)
+            ));
+        }
+    }
+
+    #[cfg(test)]
+    mod world_directive {
+        use super::world_directive;
+
+        #[test]
+        fn valid_input() {
+            assert_eq!(
+                world_directive("  WORLD  worlds/a.world "),
+                Some("worlds/a.world")
+            );
+            assert_eq!(world_directive("WORLDfoo"), None);
+            assert_eq!(world_directive("5 5"), None);
+        }
+    }
+
+    #[cfg(test)]
+    mod resolve_worlds {
+        use std::{env, fs};
+
+        use super::{resolve_worlds, MissionText};
+        use crate::{enums::RoverErr, expand::Defines, position::Position};
+
+        #[test]
+        fn replaces_directive() {
+            let dir = env::temp_dir().join(format!("rover-world-{}", std::process::id()));
+            fs::create_dir_all(dir.join("worlds")).unwrap();
+            fs::write(dir.join("worlds/crater-floor.world"), "7 7\ncrater 2,3").unwrap();
+
+            let mut texts = vec![MissionText {
+                path: Some(dir.join("mission.txt")),
+                text: "WORLD worlds/${FLOOR}.world\n1 2 N\nM".to_string(),
+                origins: None,
+            }];
+            let defines = Defines::from([("FLOOR".to_string(), "crater-floor".to_string())]);
+            let plateaus = resolve_worlds(&mut texts, &defines).unwrap();
+            assert_eq!(texts[0].text, "7 7\n1 2 N\nM");
+            assert_eq!(plateaus.len(), 1);
+            assert_eq!(
+                plateaus[0].craters().collect::<Vec<_>>(),
+                vec![Position::new(2, 3)]
+            );
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn missing_world() {
+            let mut texts = vec![MissionText {
+                path: None,
+                text: "WORLD missing.world\n1 2 N\nM".to_string(),
+                origins: None,
+            }];
+            let err = resolve_worlds(&mut texts, &Defines::new()).unwrap_err();
+            assert!(matches!(&err, RoverErr::InFile(path, err)
+                if path.ends_with("missing.world") && matches!(**err, RoverErr::OpeningPlateau(_))));
+            assert!(err.to_string().contains("opening the world file"));
+        }
+    }
+}
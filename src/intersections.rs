@@ -0,0 +1,169 @@
+use std::fmt::{self, Display};
+
+use crate::{position::Position, timeline::Timeline};
+
+/// A cell that the paths of two rovers both pass through
+#[derive(Debug, PartialEq)]
+pub struct Intersection {
+    pub rovers: (usize, usize),
+    pub position: Position,
+    /// The first tick at which both rovers occupied the cell, if they ever did at the same time
+    pub collision: Option<usize>,
+}
+
+impl Display for Intersection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Rover Intersection 🔀 - Rovers {} and {} both visited ({}, {}), ",
+            self.rovers.0, self.rovers.1, self.position.x, self.position.y
+        )?;
+
+        match self.collision {
+            Some(tick) => write!(f, "at the same time on tick {}", tick),
+            None => write!(f, "at different times"),
+        }
+    }
+}
+
+/// Find every cell where the paths of two rovers cross, in order of the first rover's path
+pub fn find_intersections(timeline: &Timeline) -> Vec<Intersection> {
+    let rovers = timeline.ticks.first().map_or(0, |rovers| rovers.len());
+
+    // The ticks each rover spent on each cell, in the order the cells were first visited
+    let mut paths: Vec<Vec<(Position, Vec<usize>)>> = vec![Vec::new(); rovers];
+    for (tick, positions) in timeline.ticks.iter().enumerate() {
+        for (path, rover) in paths.iter_mut().zip(positions) {
+            let position = rover.pose.position;
+            match path.iter_mut().find(|(cell, _)| *cell == position) {
+                Some((_, ticks)) => ticks.push(tick),
+                None => path.push((position, vec![tick])),
+            }
+        }
+    }
+
+    let mut intersections = Vec::new();
+    for (a, path_a) in paths.iter().enumerate() {
+        for (b, path_b) in paths.iter().enumerate().skip(a + 1) {
+            for (position, ticks_a) in path_a {
+                if let Some((_, ticks_b)) = path_b.iter().find(|(cell, _)| cell == position) {
+                    intersections.push(Intersection {
+                        // The ID of the rover should start from one
+                        rovers: (a + 1, b + 1),
+                        position: *position,
+                        collision: ticks_a.iter().copied().find(|tick| ticks_b.contains(tick)),
+                    });
+                }
+            }
+        }
+    }
+
+    intersections
+}
+
+#[cfg(test)]
+mod intersections_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod find_intersections {
+        use super::{find_intersections, Intersection};
+        use crate::{
+            budget::Budget,
+            diagnostics::Diagnostics,
+            enums::{Direction, Instruction},
+            position::{Pose, Position},
+            rover::{Attributes, Mission, RoverControlSatellite},
+            timeline::Timeline,
+            trace::{Trace, TraceFilter},
+            world::World,
+        };
+
+        /// Trace every event of a mission, up to where it failed if it did
+        fn traced(mission: &Mission) -> Trace {
+            let mut trace = Trace::new(TraceFilter::default());
+            let _ = RoverControlSatellite::execute_mission(
+                mission,
+                &mut World::default(),
+                &mut Budget::default(),
+                &mut Diagnostics::default(),
+                &mut trace,
+            );
+            trace
+        }
+
+        #[test]
+        fn crossing_at_different_times() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(0, 1), Direction::East),
+                        vec![Instruction::Move, Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(1, 3), Direction::South),
+                        vec![Instruction::Move, Instruction::Move],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            assert_eq!(
+                find_intersections(&Timeline::new(&mission, &traced(&mission))),
+                vec![Intersection {
+                    rovers: (1, 2),
+                    position: Position::new(1, 1),
+                    collision: None
+                }]
+            );
+        }
+
+        #[test]
+        fn collision() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(0, 1), Direction::East),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(1, 2), Direction::South),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            assert_eq!(
+                find_intersections(&Timeline::new(&mission, &traced(&mission))),
+                vec![Intersection {
+                    rovers: (1, 2),
+                    position: Position::new(1, 1),
+                    collision: Some(1)
+                }]
+            );
+        }
+
+        #[test]
+        fn separate_paths() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(0, 0), Direction::North),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(3, 3), Direction::North),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            assert!(find_intersections(&Timeline::new(&mission, &traced(&mission))).is_empty());
+        }
+    }
+}
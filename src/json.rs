@@ -0,0 +1,392 @@
+use std::{
+    fmt::{self, Display},
+    iter,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, tag, take_while_m_n},
+    character::complete::{char, multispace0},
+    combinator::{all_consuming, cut, map, map_opt, map_res, opt, value, verify},
+    multi::{many0, separated_list0},
+    number::complete::double,
+    sequence::{delimited, pair, preceded, separated_pair},
+    IResult,
+};
+
+/// A JSON value, enough to read back the files this tool writes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Look up a key of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+
+    /// The value as an integer, if it is a number without a fractional part
+    pub fn as_isize(&self) -> Option<isize> {
+        match self {
+            Json::Number(number) if number.fract() == 0.0 => Some(*number as isize),
+            _ => None,
+        }
+    }
+}
+
+/// Write the value compactly, on a single line
+impl Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(boolean) => write!(f, "{boolean}"),
+            Json::Number(number) => write!(f, "{number}"),
+            Json::String(string) => write!(f, "\"{}\"", escape(string)),
+            Json::Array(items) => {
+                let items = items.iter().map(Json::to_string).collect::<Vec<_>>();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Json::Object(members) => {
+                let members = members
+                    .iter()
+                    .map(|(name, value)| format!("\"{}\": {value}", escape(name)))
+                    .collect::<Vec<_>>();
+                write!(f, "{{{}}}", members.join(", "))
+            }
+        }
+    }
+}
+
+/// Parse a whole JSON document
+pub fn parse(input: &str) -> Option<Json> {
+    all_consuming(delimited(multispace0, json_value, multispace0))(input)
+        .ok()
+        .map(|(_, json)| json)
+}
+
+/// The line each part of a JSON document starts on, mirroring the document's structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lines {
+    pub line: usize,
+    /// The lines of an array's items or an object's values, in the order they were written
+    pub parts: Vec<Lines>,
+}
+
+impl Lines {
+    /// The lines of a member of an object, found by its key in the parsed value
+    pub fn member(&self, json: &Json, key: &str) -> Option<&Lines> {
+        match json {
+            Json::Object(members) => members
+                .iter()
+                .position(|(name, _)| name == key)
+                .and_then(|index| self.parts.get(index)),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a whole JSON document, along with the line each part of it starts on, or the index of
+/// the line it could not be parsed past
+pub fn parse_with_lines(input: &str) -> Result<(Json, Lines), usize> {
+    let mut starts = vec![0];
+    starts.extend(input.match_indices('\n').map(|(index, _)| index + 1));
+    let offsets = Offsets {
+        starts,
+        len: input.len(),
+    };
+
+    let located = all_consuming(delimited(
+        multispace0,
+        |input| located_value(input, &offsets),
+        multispace0,
+    ))(input);
+    match located {
+        Ok((_, located)) => Ok(located),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(offsets.line(err.input)),
+        Err(nom::Err::Incomplete(_)) => Err(offsets.line("")),
+    }
+}
+
+/// Where each line of a document starts, to find the line of what is left to parse
+struct Offsets {
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl Offsets {
+    fn line(&self, rest: &str) -> usize {
+        let offset = self.len - rest.len();
+        self.starts.partition_point(|start| *start <= offset) - 1
+    }
+}
+
+fn located_value<'a>(input: &'a str, offsets: &Offsets) -> IResult<&'a str, (Json, Lines)> {
+    let line = offsets.line(input);
+    let item = |input| located_value(input, offsets);
+    let member = |input| separated_pair(string, cut(token(":")), cut(item))(input);
+
+    // Once an array or object is opened it must be closed, so errors point at where it stopped
+    let (rest, (json, parts)) = alt((
+        map(
+            delimited(token("["), list(item), cut(token("]"))),
+            |items| {
+                let (items, parts) = items.into_iter().unzip();
+                (Json::Array(items), parts)
+            },
+        ),
+        map(
+            delimited(token("{"), list(member), cut(token("}"))),
+            |members| {
+                let (members, parts) = members
+                    .into_iter()
+                    .map(|(name, (json, lines))| ((name, json), lines))
+                    .unzip();
+                (Json::Object(members), parts)
+            },
+        ),
+        map(json_value, |json| (json, Vec::new())),
+    ))(input)?;
+
+    Ok((rest, (json, Lines { line, parts })))
+}
+
+/// A comma separated list, in which a comma must be followed by another item
+fn list<'a, O>(
+    item: impl FnMut(&'a str) -> IResult<&'a str, O> + Copy,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>> {
+    map(
+        opt(pair(item, many0(preceded(token(","), cut(item))))),
+        |items| match items {
+            Some((first, rest)) => iter::once(first).chain(rest).collect(),
+            None => Vec::new(),
+        },
+    )
+}
+
+/// Escape a string so it can be written between the quotes of a JSON string
+pub fn escape(input: &str) -> String {
+    let mut output = String::new();
+    for c in input.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Parse a token surrounded by optional whitespace
+fn token<'a>(t: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(multispace0, tag(t), multispace0)
+}
+
+fn string(input: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        map(
+            opt(escaped_transform(
+                is_not("\"\\"),
+                '\\',
+                alt((
+                    value('"', char('"')),
+                    value('\\', char('\\')),
+                    value('/', char('/')),
+                    value('\n', char('n')),
+                    value('\r', char('r')),
+                    value('\t', char('t')),
+                    value('\u{8}', char('b')),
+                    value('\u{c}', char('f')),
+                    preceded(char('u'), unicode_escape),
+                )),
+            )),
+            Option::unwrap_or_default,
+        ),
+        char('"'),
+    )(input)
+}
+
+/// Parse the `XXXX` of a `\uXXXX` escape, a high surrogate must be followed by the escape of
+/// its low surrogate
+fn unicode_escape(input: &str) -> IResult<&str, char> {
+    let hex = || {
+        map_res(
+            take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+            |hex| u32::from_str_radix(hex, 16),
+        )
+    };
+
+    alt((
+        map_opt(
+            separated_pair(
+                verify(hex(), |high| (0xD800..0xDC00).contains(high)),
+                tag("\\u"),
+                verify(hex(), |low| (0xDC00..0xE000).contains(low)),
+            ),
+            |(high, low)| char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)),
+        ),
+        // Lone surrogates are not characters, so they are refused here
+        map_opt(hex(), char::from_u32),
+    ))(input)
+}
+
+fn json_value(input: &str) -> IResult<&str, Json> {
+    alt((
+        value(Json::Null, tag("null")),
+        value(Json::Bool(true), tag("true")),
+        value(Json::Bool(false), tag("false")),
+        map(double, Json::Number),
+        map(string, Json::String),
+        map(
+            delimited(
+                token("["),
+                separated_list0(token(","), json_value),
+                token("]"),
+            ),
+            Json::Array,
+        ),
+        map(
+            delimited(
+                token("{"),
+                separated_list0(token(","), separated_pair(string, token(":"), json_value)),
+                token("}"),
+            ),
+            Json::Object,
+        ),
+    ))(input)
+}
+
+#[cfg(test)]
+mod json_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod parse {
+        use super::{parse, Json};
+
+        #[test]
+        fn valid_input() {
+            let result = parse(r#" {"a": [1, -2.5, "x\"y"], "b": {"c": true}, "d": null} "#);
+            assert_eq!(
+                result,
+                Some(Json::Object(vec![
+                    (
+                        "a".to_string(),
+                        Json::Array(vec![
+                            Json::Number(1.0),
+                            Json::Number(-2.5),
+                            Json::String("x\"y".to_string())
+                        ])
+                    ),
+                    (
+                        "b".to_string(),
+                        Json::Object(vec![("c".to_string(), Json::Bool(true))])
+                    ),
+                    ("d".to_string(), Json::Null),
+                ]))
+            );
+        }
+
+        #[test]
+        fn trailing_comma() {
+            assert_eq!(parse("[1, 2,]"), None);
+        }
+
+        #[test]
+        fn escapes() {
+            let result = parse(r#""a\u00e9\b\f\ud83d\ude80\u0041""#);
+            assert_eq!(result, Some(Json::String("aé\u{8}\u{c}🚀A".to_string())));
+        }
+
+        #[test]
+        fn lone_surrogate() {
+            assert_eq!(parse(r#""\ud83d""#), None);
+            assert_eq!(parse(r#""\ude80""#), None);
+            assert_eq!(parse(r#""\u12""#), None);
+        }
+    }
+
+    #[cfg(test)]
+    mod parse_with_lines {
+        use super::{parse, parse_with_lines};
+
+        #[test]
+        fn valid_input() {
+            let input = "{\"a\": 1,\n \"b\": [\n  2,\n  {\"c\":\n   3}]}";
+            let (json, lines) = parse_with_lines(input).unwrap();
+            assert_eq!(Some(json.clone()), parse(input));
+
+            let b = lines.member(&json, "b").unwrap();
+            assert_eq!(lines.member(&json, "a").unwrap().line, 0);
+            assert_eq!(b.line, 1);
+            assert_eq!(b.parts[0].line, 2);
+            assert_eq!(b.parts[1].line, 3);
+            let c = b.parts[1].member(&json.get("b").unwrap().as_array().unwrap()[1], "c");
+            assert_eq!(c.unwrap().line, 4);
+        }
+
+        #[test]
+        fn invalid_input() {
+            assert_eq!(
+                parse_with_lines("{\"a\": 1,\n \"b\": [\n  2,\n  x]}"),
+                Err(3)
+            );
+            assert_eq!(parse_with_lines("[1, 2,\n]"), Err(1));
+            assert_eq!(parse_with_lines("{\"a\": 1}\n\n}"), Err(2));
+        }
+    }
+
+    #[cfg(test)]
+    mod display {
+        use super::{parse, Json};
+
+        #[test]
+        fn round_trip() {
+            let input = r#"{"a": [1, -2.5, "x\"y"], "b": {"c": true}, "d": null}"#;
+            assert_eq!(parse(input).unwrap().to_string(), input);
+        }
+
+        #[test]
+        fn escaped_round_trip() {
+            let json = Json::String("tab\t bell\u{7} back\u{8} feed\u{c} 🚀 \"\\".to_string());
+            assert_eq!(parse(&json.to_string()), Some(json));
+        }
+    }
+}
@@ -0,0 +1,37 @@
+pub mod analyze;
+pub mod bench;
+pub mod budget;
+pub mod config;
+pub mod daemon;
+pub mod diagnostics;
+pub mod dialect;
+pub mod enums;
+pub mod estimate;
+pub mod expand;
+pub mod filter;
+pub mod format;
+pub mod heatmap;
+pub mod input;
+pub mod intersections;
+pub mod json;
+pub mod manifest;
+pub mod montecarlo;
+pub mod parse;
+pub mod plateau;
+pub mod position;
+pub mod pretty;
+pub mod query;
+pub mod render;
+pub mod rng;
+pub mod rover;
+pub mod rpc;
+pub mod sign;
+pub mod simulation;
+pub mod stepper;
+pub mod stochastic;
+pub mod summary;
+pub mod terrain;
+pub mod timeline;
+pub mod trace;
+pub mod watch;
+pub mod world;
@@ -1,40 +1,783 @@
-pub mod enums;
-pub mod parse;
-pub mod rover;
+use std::{env, fs, io, path::PathBuf, process::ExitCode, time::Duration};
 
-use std::{fs, path::PathBuf, process::ExitCode};
-
-use clap::Parser;
-use enums::RoverErr;
-
-use crate::rover::RoverControlSatellite;
+use clap::{
+    error::ErrorKind, parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser,
+    Subcommand,
+};
+use rover_cli::{
+    analyze, bench,
+    budget::Budget,
+    config::{self, ConfigFile, Setting},
+    daemon::SpoolDaemon,
+    diagnostics::{Diagnostics, Level, Policy, WarningCode},
+    enums::{
+        Autonomy, Generator, InputFormat, InstructionLocale, Recovery, RoverErr, SchemaVersion,
+        SortKey,
+    },
+    estimate::{self, CostModel},
+    expand::{self, parse_define, Defines, Expansion, SourceMap},
+    filter::RoverFilter,
+    format::{Formatters, Template},
+    heatmap::Heatmap,
+    input::{self, MissionSource, MissionText},
+    intersections::{find_intersections, Intersection},
+    manifest::Manifest,
+    montecarlo,
+    plateau::Plateau,
+    position::Position,
+    pretty,
+    query::{self, Query},
+    render,
+    rng::Rng,
+    rover::{Mission, RoverControlSatellite},
+    rpc::RpcSession,
+    sign::{self, Signature},
+    simulation::Simulation,
+    stochastic::StochasticModel,
+    summary::Summary,
+    terrain::parse_tagged_cell,
+    timeline::Timeline,
+    trace::{Trace, TraceFilter},
+    watch::Watch,
+    world::{parse_cell, World},
+};
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
-    /// The path to the instructions file.
-    input_path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The paths to the instructions files. The first supplies the plateau and any others add
+    /// further rovers. Takes precedence over `--inline` and `MARS_ROVER_MISSION`.
+    input_paths: Vec<PathBuf>,
+
+    /// The instructions themselves, with `\n` separating lines. Takes precedence over `MARS_ROVER_MISSION`.
+    #[arg(long, conflicts_with = "input_paths")]
+    inline: Option<String>,
+
+    /// The format the instructions are written in. By default it is detected from their contents.
+    #[arg(long, value_enum, env = "MARS_ROVER_INPUT_FORMAT")]
+    input_format: Option<InputFormat>,
+
+    /// Set a `${KEY}` variable used in the instructions, taking precedence over the environment.
+    #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = parse_define)]
+    defines: Vec<(String, String)>,
 
     /// Return an error if the rover exits plateau.
-    #[arg(short, long)]
+    #[arg(short, long, env = "MARS_ROVER_UNBOUNDED")]
     unbounded: bool,
 
     /// A path to save the output a a file. By default, the output will be printed to stdout.
     #[clap(short = 'o', long = "output")]
     output: Option<PathBuf>,
+
+    /// The alphabet the instruction streams are written in.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        env = "MARS_ROVER_INSTRUCTION_LOCALE"
+    )]
+    instruction_locale: InstructionLocale,
+
+    /// A config file of `key = value` lines setting how seriously warnings are taken, with the
+    /// `deny-warnings`, `allow`, `warn` and `deny` keys. The flags take precedence over it.
+    #[arg(long, global = true, env = "MARS_ROVER_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Treat warnings as errors.
+    #[arg(long, global = true, env = "MARS_ROVER_DENY_WARNINGS")]
+    deny_warnings: bool,
+
+    /// Do not report warnings with these codes, e.g. `no-op-turns`. Takes precedence over
+    /// `--deny-warnings`.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "CODE",
+        value_delimiter = ',',
+        env = "MARS_ROVER_ALLOW"
+    )]
+    allow: Vec<WarningCode>,
+
+    /// Report warnings with these codes, even with `--deny-warnings`. Takes precedence over
+    /// `--allow`.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "CODE",
+        value_delimiter = ',',
+        env = "MARS_ROVER_WARN"
+    )]
+    warn: Vec<WarningCode>,
+
+    /// Treat warnings with these codes as errors, e.g. `near-boundery`. Takes precedence over
+    /// `--allow` and `--warn`.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "CODE",
+        value_delimiter = ',',
+        env = "MARS_ROVER_DENY"
+    )]
+    deny: Vec<WarningCode>,
+
+    /// A path to save a trace of every executed instruction to.
+    #[arg(long)]
+    trace: Option<PathBuf>,
+
+    /// Only trace matching events, e.g. `rover=3,kind=move`.
+    #[arg(long, requires = "trace")]
+    trace_filter: Option<TraceFilter>,
+
+    /// The order to output the rovers in.
+    #[arg(long, value_enum, default_value_t, env = "MARS_ROVER_SORT")]
+    sort: SortKey,
+
+    /// Only output the rovers matching an expression, e.g. `x>=3 && facing==N`.
+    #[arg(long)]
+    filter: Option<RoverFilter>,
+
+    /// A path to save every rover's position at every tick to, as a CSV.
+    #[arg(long)]
+    timeline: Option<PathBuf>,
+
+    /// A path to save how many times each cell was traversed to, as a CSV matrix.
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+
+    /// Warn when a rover moves onto one of the outermost rows or columns of the plateau. An
+    /// unbounded plateau has no edges, so it cannot be combined with --unbounded.
+    #[arg(
+        long,
+        value_name = "CELLS",
+        default_value_t = 0,
+        env = "MARS_ROVER_SAFETY_MARGIN",
+        conflicts_with = "unbounded"
+    )]
+    safety_margin: usize,
+
+    /// A cell rovers can fall into, may be given several times.
+    #[arg(long = "crater", value_name = "X,Y", value_parser = parse_cell, allow_hyphen_values = true)]
+    craters: Vec<Position>,
+
+    /// Tag a cell, reporting an event whenever a rover moves onto it, e.g. `2,3=slippery`. May be
+    /// given several times, and a cell may have several tags.
+    #[arg(long = "tag", value_name = "X,Y=TAG", value_parser = parse_tagged_cell, allow_hyphen_values = true)]
+    tags: Vec<(Position, String)>,
+
+    /// Mark rovers that fall off the plateau or into a crater as LOST, instead of failing. Later
+    /// rovers ignore the move that lost them from the same cell.
+    #[arg(long, env = "MARS_ROVER_LOST_ROVERS")]
+    lost_rovers: bool,
+
+    /// Let rovers refuse moves that would take them off the plateau or into a crater, executing
+    /// the `--recovery` maneuver instead with a warning.
+    #[arg(long, value_enum, default_value_t, env = "MARS_ROVER_AUTONOMY")]
+    autonomy: Autonomy,
+
+    /// What a rover does instead of a fatal move with `--autonomy safe`.
+    #[arg(long, value_enum, default_value_t, env = "MARS_ROVER_RECOVERY")]
+    recovery: Recovery,
+
+    /// Give further instructions to the rovers of a world saved with `--world-state`. Each line of
+    /// the instructions is a rover's ID followed by its instructions, e.g. `2 MMRM`. The timeline,
+    /// heatmap and intersections follow a whole mission, so they cannot be combined with it.
+    #[arg(
+        long,
+        value_name = "WORLD_STATE",
+        conflicts_with_all = ["timeline", "heatmap", "check_intersections"]
+    )]
+    from_world: Option<PathBuf>,
+
+    /// A path to save the final state of the world to, as JSON.
+    #[arg(long)]
+    world_state: Option<PathBuf>,
+
+    /// Return an error if two rovers finish on the same cell.
+    #[arg(long, env = "MARS_ROVER_UNIQUE_FINAL_POSITIONS")]
+    unique_final_positions: bool,
+
+    /// Report where the rovers' paths cross, and whether they were on the same cell at once.
+    #[arg(long, env = "MARS_ROVER_CHECK_INTERSECTIONS")]
+    check_intersections: bool,
+
+    /// Print a summary of the fleet after the run, even if it failed: how many rovers ended
+    /// halted, lost or degraded, the faults that ended the mission, the shortest, longest and
+    /// mean distance moved, and the box holding every final position.
+    #[arg(long, env = "MARS_ROVER_SUMMARY")]
+    summary: bool,
+
+    /// The schema version of the trace, timeline, heatmap, world state and manifest files and of
+    /// the json and csv formats, older versions use older layouts.
+    #[arg(long, value_enum, default_value_t, env = "MARS_ROVER_OUTPUT_VERSION")]
+    output_version: SchemaVersion,
+
+    /// How to write out the rovers: text, json, csv, template or grid.
+    #[arg(long, default_value = "text", env = "MARS_ROVER_FORMAT")]
+    format: String,
+
+    /// The line written for each rover by `--format template`, e.g. `{id}: {x} {y} {facing}`.
+    /// Also fills in `{status}`, and can't be used with any other format.
+    #[arg(long)]
+    template: Option<String>,
+
+    /// The seed for anything random in the mission, runs with the same seed and input give the
+    /// same output.
+    #[arg(long, default_value_t = 0, env = "MARS_ROVER_SEED")]
+    seed: u64,
+
+    /// The generator anything random in the mission draws from.
+    #[arg(long, value_enum, default_value_t, env = "MARS_ROVER_RNG")]
+    rng: Generator,
+
+    #[command(flatten)]
+    model: StochasticModel,
+
+    /// A path to save a manifest of the run to, as JSON. It records the version, settings, seed
+    /// and hashes of the input and output, so the run can be repeated and checked.
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// A path to an Ed25519 private key in PEM format to sign the output with. The signature is
+    /// written alongside the output, with `.sig` appended, and covers the version, the input and
+    /// the output.
+    #[arg(
+        long,
+        value_name = "KEY",
+        requires = "output",
+        env = "MARS_ROVER_SIGN_OUTPUT"
+    )]
+    sign_output: Option<PathBuf>,
+
+    /// Print the settings that would be used, and where each came from, then exit.
+    #[arg(long)]
+    print_config: bool,
+
+    /// Serve JSON-RPC requests on stdin, one per line, answering each on stdout. Methods are
+    /// `loadMission`, `step`, `getState`, `reset`, `jumpToTick` and `jumpToPreviousViolation`.
+    #[arg(long, conflicts_with_all = ["input_paths", "inline", "from_world"])]
+    rpc: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Statically check an instructions file for likely mistakes, without executing it.
+    Analyze {
+        /// The path to the instructions file.
+        input_path: PathBuf,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+    },
+    /// Estimate the cost of each rover's instructions, without executing them.
+    Estimate {
+        /// The path to the instructions file.
+        input_path: PathBuf,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+
+        #[command(flatten)]
+        model: CostModel,
+    },
+    /// Watch a spool directory, executing each instructions file that appears in it.
+    Daemon {
+        /// The directory to watch.
+        #[arg(long)]
+        spool: PathBuf,
+
+        /// How often to check the spool, in milliseconds.
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+
+        /// Process the files currently in the spool and exit.
+        #[arg(long)]
+        once: bool,
+
+        /// Allow the rovers to exit the plateau.
+        #[arg(short, long)]
+        unbounded: bool,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+
+        /// The most instructions each file may execute, across all of its rovers.
+        #[arg(long, value_name = "COUNT")]
+        max_instructions: Option<usize>,
+
+        /// The longest each file may execute for, in milliseconds.
+        #[arg(long, value_name = "MS")]
+        max_time: Option<u64>,
+    },
+    /// Watch several instructions files, drawing where each mission left its rovers side by side
+    /// and re-running a file whenever it changes.
+    Watch {
+        /// The paths to the instructions files.
+        #[arg(required = true)]
+        input_paths: Vec<PathBuf>,
+
+        /// How often to check the files, in milliseconds.
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+
+        /// Draw the files once and exit.
+        #[arg(long)]
+        once: bool,
+
+        /// Allow the rovers to exit the plateau.
+        #[arg(short, long)]
+        unbounded: bool,
+
+        /// Mark rovers that fall off the plateau or into a crater as LOST, instead of failing.
+        #[arg(long)]
+        lost_rovers: bool,
+
+        /// Set a `${KEY}` variable used in the instructions.
+        #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = parse_define)]
+        defines: Vec<(String, String)>,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+    },
+    /// Draw the plateau at every tick of the mission, as an ASCII grid.
+    Render {
+        /// The path to the instructions file.
+        input_path: PathBuf,
+
+        /// Record the frames as an asciinema v2 cast file, instead of printing them.
+        #[arg(long, value_name = "CAST")]
+        asciinema: Option<PathBuf>,
+
+        /// How long each frame is shown for in the cast, in milliseconds.
+        #[arg(long, default_value_t = 500)]
+        frame_delay: u64,
+
+        /// Allow the rovers to exit the plateau.
+        #[arg(short, long)]
+        unbounded: bool,
+
+        /// Mark rovers that fall off the plateau or into a crater as LOST, instead of failing.
+        #[arg(long)]
+        lost_rovers: bool,
+
+        /// A cell rovers can fall into, may be given several times.
+        #[arg(long = "crater", value_name = "X,Y", value_parser = parse_cell, allow_hyphen_values = true)]
+        craters: Vec<Position>,
+
+        /// Tag a cell, reporting an event whenever a rover moves onto it, e.g. `2,3=slippery`.
+        #[arg(long = "tag", value_name = "X,Y=TAG", value_parser = parse_tagged_cell, allow_hyphen_values = true)]
+        tags: Vec<(Position, String)>,
+
+        /// Let rovers refuse moves that would take them off the plateau or into a crater.
+        #[arg(long, value_enum, default_value_t)]
+        autonomy: Autonomy,
+
+        /// What a rover does instead of a fatal move with `--autonomy safe`.
+        #[arg(long, value_enum, default_value_t)]
+        recovery: Recovery,
+
+        /// The seed for anything random in the mission.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// The generator anything random in the mission draws from.
+        #[arg(long, value_enum, default_value_t)]
+        rng: Generator,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+
+        #[command(flatten)]
+        model: StochasticModel,
+    },
+    /// Lay out an instructions file canonically, failing if it does not parse.
+    Fmt {
+        /// The path to the instructions file.
+        input_path: PathBuf,
+
+        /// Write the instructions with their `!include` and `${VAR}` macros expanded.
+        #[arg(long)]
+        expand: bool,
+
+        /// Write the mission in a single canonical form, so missions that mean the same are
+        /// identical. Expands macros, writes English instructions and sorts the rovers, giving
+        /// them an `id=` if that moves any.
+        #[arg(long, conflicts_with = "expand")]
+        canonical: bool,
+
+        /// Rewrite the file in place, instead of printing it.
+        #[arg(long)]
+        write: bool,
+
+        /// Set a `${KEY}` variable used in the instructions.
+        #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = parse_define)]
+        defines: Vec<(String, String)>,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+    },
+    /// Time parsing and executing an instructions file, repeated a number of times.
+    Bench {
+        /// The path to the instructions file.
+        input_path: PathBuf,
+
+        /// How many times to parse and execute the instructions.
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+
+        /// Allow the rovers to exit the plateau.
+        #[arg(short, long)]
+        unbounded: bool,
+
+        /// Set a `${KEY}` variable used in the instructions.
+        #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = parse_define)]
+        defines: Vec<(String, String)>,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+    },
+    /// Execute a mission many times under the stochastic models, reporting the distribution of
+    /// where each rover finished, how often it failed and the fuel it used.
+    Montecarlo {
+        /// The path to the instructions file.
+        input_path: PathBuf,
+
+        /// How many times to execute the mission.
+        #[arg(long, default_value_t = 1000)]
+        runs: usize,
+
+        /// The seed the runs are drawn from, the same seed gives the same distributions.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// The generator each run draws from.
+        #[arg(long, value_enum, default_value_t)]
+        rng: Generator,
+
+        /// Allow the rovers to exit the plateau.
+        #[arg(short, long)]
+        unbounded: bool,
+
+        /// Count rovers that fall off the plateau, into a crater or are struck by a hazard as
+        /// lost, instead of failing the run.
+        #[arg(long)]
+        lost_rovers: bool,
+
+        /// A cell rovers can fall into, may be given several times.
+        #[arg(long = "crater", value_name = "X,Y", value_parser = parse_cell, allow_hyphen_values = true)]
+        craters: Vec<Position>,
+
+        /// Set a `${KEY}` variable used in the instructions.
+        #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = parse_define)]
+        defines: Vec<(String, String)>,
+
+        /// The alphabet the instruction streams are written in.
+        #[arg(long, value_enum, default_value_t)]
+        instruction_locale: InstructionLocale,
+
+        #[command(flatten)]
+        model: StochasticModel,
+
+        #[command(flatten)]
+        costs: CostModel,
+    },
+    /// Work with world files, which missions name with `WORLD <path>` in place of their bounderies.
+    World {
+        #[command(subcommand)]
+        command: WorldCommand,
+    },
+    /// Check the signature of an output file, written by `--sign-output`.
+    Verify {
+        /// The path to the output file, its signature is read from alongside it.
+        output_path: PathBuf,
+
+        /// The paths to the instructions files the output should have been produced from, in
+        /// the order they were given. The world files and included files they read are checked
+        /// where they were read from.
+        #[arg(required = true)]
+        input_paths: Vec<PathBuf>,
+
+        /// The path to the public key of the private key the output was signed with, in PEM
+        /// format.
+        #[arg(long)]
+        key: PathBuf,
+    },
+    /// Search a saved trace, printing the matching events.
+    Query {
+        /// The path to the trace file.
+        trace_path: PathBuf,
+
+        /// Only show events for this rover.
+        #[arg(long)]
+        rover: Option<usize>,
+
+        /// Show where each rover was after this tick.
+        #[arg(long)]
+        tick: Option<usize>,
+
+        /// Only show events on this cell.
+        #[arg(long, num_args = 2, value_names = ["X", "Y"], allow_negative_numbers = true)]
+        cell: Option<Vec<isize>>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorldCommand {
+    /// Check world files for features that could never come into play, e.g. craters off the
+    /// plateau.
+    Validate {
+        /// The paths to the world files.
+        #[arg(required = true)]
+        world_paths: Vec<PathBuf>,
+    },
 }
 
 /// Cli wrapper function
 fn main() -> ExitCode {
-    let args = match Args::try_parse() {
+    // The matches are kept to find out where each setting came from
+    let matches = match Args::command().try_get_matches() {
+        Ok(matches) => matches,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let args = match Args::from_arg_matches(&matches) {
         Ok(args) => args,
         Err(err) => {
             eprintln!("{err}");
             return ExitCode::FAILURE;
         }
     };
+    // Clap can only tie arguments together by presence, not by the value of `--format`
+    if args.template.is_some() && args.format != "template" {
+        eprintln!(
+            "{}",
+            Args::command().error(
+                ErrorKind::ArgumentConflict,
+                "the argument '--template <TEMPLATE>' can only be used with '--format template'"
+            )
+        );
+        return ExitCode::FAILURE;
+    }
 
-    if let Err(err) = parse_input_and_output_result(args) {
+    if args.print_config {
+        let config = match args.config.as_deref().map(ConfigFile::open).transpose() {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::FAILURE;
+            }
+        };
+        if let Some(Err(err)) = config.as_ref().map(ConfigFile::policy) {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+        let env = |var: &str| env::var(var).ok();
+        for setting in config::settings(&Args::command(), &matches, &env, config.as_ref()) {
+            println!("{setting}");
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let policy = match warning_policy(&args, &matches) {
+        Ok(policy) => policy,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match args.command {
+        Some(Command::Analyze {
+            input_path,
+            instruction_locale,
+        }) => analyze_input(input_path, instruction_locale, policy),
+        Some(Command::Estimate {
+            input_path,
+            instruction_locale,
+            model,
+        }) => estimate_input(input_path, instruction_locale, model, policy),
+        Some(Command::Daemon {
+            spool,
+            interval,
+            once,
+            unbounded,
+            instruction_locale,
+            max_instructions,
+            max_time,
+        }) => SpoolDaemon {
+            spool,
+            simulation: Simulation {
+                unbounded,
+                locale: instruction_locale,
+                instruction_budget: max_instructions,
+                time_budget: max_time.map(Duration::from_millis),
+                policy,
+                ..Simulation::default()
+            },
+        }
+        .run((!once).then_some(Duration::from_millis(interval))),
+        Some(Command::Watch {
+            input_paths,
+            interval,
+            once,
+            unbounded,
+            lost_rovers,
+            defines,
+            instruction_locale,
+        }) => Watch::new(
+            input_paths,
+            Simulation {
+                unbounded,
+                lost_rovers,
+                locale: instruction_locale,
+                policy,
+                ..Simulation::default()
+            },
+            defines.into_iter().collect(),
+        )
+        .run((!once).then_some(Duration::from_millis(interval))),
+        Some(Command::Render {
+            input_path,
+            asciinema,
+            frame_delay,
+            unbounded,
+            lost_rovers,
+            craters,
+            tags,
+            autonomy,
+            recovery,
+            seed,
+            rng,
+            instruction_locale,
+            model,
+        }) => render_input(
+            input_path,
+            asciinema,
+            Duration::from_millis(frame_delay),
+            Simulation {
+                unbounded,
+                lost_rovers,
+                craters,
+                tags,
+                autonomy,
+                recovery,
+                seed,
+                generator: rng,
+                locale: instruction_locale,
+                model,
+                policy,
+                ..Simulation::default()
+            },
+        ),
+        Some(Command::Fmt {
+            input_path,
+            expand,
+            canonical,
+            write,
+            defines,
+            instruction_locale,
+        }) => fmt_input(
+            input_path,
+            expand,
+            canonical,
+            write,
+            defines.into_iter().collect(),
+            instruction_locale,
+        ),
+        Some(Command::Bench {
+            input_path,
+            iterations,
+            unbounded,
+            defines,
+            instruction_locale,
+        }) => bench_input(
+            input_path,
+            iterations,
+            Simulation {
+                unbounded,
+                locale: instruction_locale,
+                ..Simulation::default()
+            },
+            defines.into_iter().collect(),
+        ),
+        Some(Command::Montecarlo {
+            input_path,
+            runs,
+            seed,
+            rng,
+            unbounded,
+            lost_rovers,
+            craters,
+            defines,
+            instruction_locale,
+            model,
+            costs,
+        }) => montecarlo_input(
+            input_path,
+            runs,
+            seed,
+            Simulation {
+                generator: rng,
+                unbounded,
+                locale: instruction_locale,
+                craters,
+                lost_rovers,
+                model,
+                policy,
+                ..Simulation::default()
+            },
+            costs,
+            defines.into_iter().collect(),
+        ),
+        Some(Command::World {
+            command: WorldCommand::Validate { world_paths },
+        }) => validate_worlds(world_paths),
+        Some(Command::Verify {
+            output_path,
+            input_paths,
+            key,
+        }) => verify_output(output_path, input_paths, key),
+        Some(Command::Query {
+            trace_path,
+            rover,
+            tick,
+            cell,
+        }) => query_trace(
+            trace_path,
+            Query {
+                rover,
+                tick,
+                cell: cell.map(|cell| Position::new(cell[0], cell[1])),
+            },
+        ),
+        None if args.rpc => serve_rpc(args, policy),
+        None => {
+            let settings = config::effective(&Args::command(), &matches);
+            parse_input_and_output_result(args, settings, policy)
+        }
+    };
+
+    if let Err(err) = result {
         eprintln!("{err}");
         return ExitCode::FAILURE;
     }
@@ -42,24 +785,588 @@ fn main() -> ExitCode {
     ExitCode::SUCCESS
 }
 
+/// How seriously to take each kind of warning, as the config file says if one is given, with
+/// the flags taking precedence
+fn warning_policy(args: &Args, matches: &ArgMatches) -> Result<Policy, RoverErr> {
+    let mut policy = match &args.config {
+        Some(config_path) => ConfigFile::open(config_path)?.policy()?,
+        None => Policy::default(),
+    };
+    // `MARS_ROVER_DENY_WARNINGS=false` turns off what the config file turned on
+    if !matches!(
+        matches.value_source("deny_warnings"),
+        None | Some(ValueSource::DefaultValue)
+    ) {
+        policy.default = match args.deny_warnings {
+            true => Level::Deny,
+            false => Level::Warn,
+        };
+    }
+    for (codes, level) in [
+        (&args.allow, Level::Allow),
+        (&args.warn, Level::Warn),
+        (&args.deny, Level::Deny),
+    ] {
+        for code in codes {
+            policy.set(*code, level);
+        }
+    }
+
+    Ok(policy)
+}
+
 #[inline]
-fn parse_input_and_output_result(args: Args) -> Result<(), RoverErr> {
+fn parse_input_and_output_result(
+    args: Args,
+    settings: Vec<Setting>,
+    policy: Policy,
+) -> Result<(), RoverErr> {
+    let mut formatters = Formatters::default();
+    if let Some(template) = args.template {
+        formatters.register("template", Box::new(Template(template)));
+    }
+
+    // An unknown format is reported before anything is executed
+    let formatter = formatters.get(&args.format).ok_or_else(|| {
+        RoverErr::UnknownFormat(
+            args.format.clone(),
+            formatters.names().into_iter().map(String::from).collect(),
+        )
+    })?;
+
+    // The key is read up front, so a missing key does not waste a run
+    let key = match &args.sign_output {
+        Some(key_path) => Some(sign::signing_key(
+            &fs::read_to_string(key_path).map_err(RoverErr::OpeningKey)?,
+        )?),
+        None => None,
+    };
+
     // Open instructions file
-    let file = fs::read_to_string(args.input_path).map_err(RoverErr::Opening)?;
+    let texts = MissionSource::resolve(args.input_paths, args.inline)?.read()?;
+    let source = |text: &MissionText| match &text.path {
+        Some(path) => path.display().to_string(),
+        None => "inline".to_string(),
+    };
+    let mut inputs = texts
+        .iter()
+        .map(|text| (source(text), sign::digest(&text.text)))
+        .collect::<Vec<_>>();
+    let defines = args.defines.iter().cloned().collect::<Defines>();
+    let world_paths = texts
+        .iter()
+        .filter_map(|text| input::world_file(text, &defines)?.ok())
+        .collect::<Vec<PathBuf>>();
+    // The variables of `WORLD` lines are substituted before the texts are expanded
+    let world_variables = texts
+        .iter()
+        .flat_map(|text| input::world_variables(text, &defines))
+        .collect::<Vec<String>>();
+    let (texts, plateaus) = input::prepare(texts, args.input_format, &defines)?;
 
-    let rovers = RoverControlSatellite::parse_and_execute_incoming_message(file, args.unbounded)?;
-    let output = rovers
-        .into_iter()
-        .map(|rover| rover.to_string())
-        .collect::<Vec<String>>()
-        .join("\n");
+    let mut diagnostics = Diagnostics::default().with_policy(policy);
+    // The timeline, heatmap and intersections need every event, otherwise only the events the
+    // saved trace keeps are recorded, so moves it leaves out can still be folded
+    let every_event = args.timeline.is_some() || args.heatmap.is_some() || args.check_intersections;
+    let mut trace = match (every_event, args.trace.is_some()) {
+        (true, _) => Trace::new(TraceFilter::default()),
+        (false, true) => Trace::new(args.trace_filter.clone().unwrap_or_default()),
+        (false, false) => Trace::default(),
+    };
+    let mut intersections: Vec<Intersection> = Vec::new();
+    let mut world = World::new(args.craters, args.lost_rovers);
+    world.add_tags(args.tags);
+
+    if let Some(world_path) = &args.from_world {
+        let file = fs::read_to_string(world_path).map_err(RoverErr::OpeningWorld)?;
+        // Craters and tags given on the command line are added to the saved world's
+        let mut saved = World::from_json(&file)?;
+        saved.craters.extend(world.craters);
+        saved.add_tags(
+            world
+                .tags
+                .into_iter()
+                .flat_map(|(position, tags)| tags.into_iter().map(move |tag| (position, tag))),
+        );
+        saved.lost_rovers |= world.lost_rovers;
+        world = saved;
+    }
+    for plateau in &plateaus {
+        plateau.apply(&mut world);
+    }
+    world.rng = Rng::with_generator(args.rng, args.seed);
+    world.model = args.model;
+    world.autonomy = args.autonomy;
+    world.recovery = args.recovery;
+    world.unbounded = args.unbounded;
+    world.safety_margin = args.safety_margin;
+    let mut sources = SourceMap::default();
+    let unused_defines = |sources: &SourceMap| {
+        expand::unused_defines(
+            &defines,
+            &[&world_variables[..], &sources.variables].concat(),
+        )
+    };
+
+    let result = match &args.from_world {
+        Some(_) => input::parse_resumption(texts, args.instruction_locale, &defines).and_then(
+            |(streams, stream_sources)| {
+                sources = stream_sources;
+                for warning in unused_defines(&sources) {
+                    diagnostics.warn(warning);
+                }
+
+                let parsed = diagnostics.len();
+                let result = RoverControlSatellite::resume_mission(
+                    streams,
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut diagnostics,
+                    &mut trace,
+                );
+                diagnostics.relocate(parsed, |warning| sources.locate_warning(warning));
+                result.map_err(|err| sources.locate(err))
+            },
+        ),
+        None => input::parse_mission_with_provenance(
+            texts,
+            args.instruction_locale,
+            &defines,
+            &mut diagnostics,
+        )
+        .and_then(|(mission, mission_sources)| {
+            sources = mission_sources;
+            for warning in unused_defines(&sources) {
+                diagnostics.warn(warning);
+            }
+
+            let parsed = diagnostics.len();
+            let result = RoverControlSatellite::execute_mission(
+                &mission,
+                &mut world,
+                &mut Budget::default(),
+                &mut diagnostics,
+                &mut trace,
+            );
+            // Point what happened whilst executing at where its instructions were written
+            diagnostics.relocate(parsed, |warning| sources.locate_warning(warning));
+            let result = result.map_err(|err| sources.locate(err));
+
+            // The timeline and heatmap are saved even if the mission failed, as they show where a
+            // rover went wrong
+            let timeline = (args.timeline.is_some() || args.check_intersections)
+                .then(|| Timeline::new(&mission, &trace));
+            if let Some(timeline) = timeline.as_ref().filter(|_| args.check_intersections) {
+                intersections = find_intersections(timeline);
+            }
+            let saved = match (&args.timeline, &timeline) {
+                (Some(timeline_path), Some(timeline)) => {
+                    fs::write(timeline_path, timeline.to_csv(args.output_version))
+                        .map_err(RoverErr::Saving)
+                }
+                _ => Ok(()),
+            }
+            .and_then(|_| match &args.heatmap {
+                Some(heatmap_path) => Heatmap::new(&mission, &trace)
+                    .to_csv(args.output_version)
+                    .and_then(|heatmap| fs::write(heatmap_path, heatmap).map_err(RoverErr::Saving)),
+                None => Ok(()),
+            });
+            if let (Err(_), Err(err)) = (&result, &saved) {
+                eprintln!("{err}");
+            }
+
+            let rovers = result?;
+            saved?;
+            Ok(rovers)
+        }),
+    };
+
+    // Warnings and events are reported even if the mission failed
+    for warning in diagnostics.warnings() {
+        eprintln!("{warning} [{}]", warning.code());
+    }
+    for event in &world.events {
+        eprintln!("{event}");
+    }
+
+    // Save the trace and the world, they are most useful when the mission failed
+    let saved = match args.trace {
+        Some(trace_path) => {
+            trace.retain(&args.trace_filter.unwrap_or_default());
+            trace.attribute(&sources.rovers);
+            fs::write(trace_path, trace.to_file(args.output_version)).map_err(RoverErr::Saving)
+        }
+        None => Ok(()),
+    }
+    .and_then(|_| match &args.world_state {
+        Some(world_state_path) => fs::write(world_state_path, world.to_json(args.output_version))
+            .map_err(RoverErr::Saving),
+        None => Ok(()),
+    });
+    // The mission's error is returned, so a failure to save alongside it is only reported
+    if let (Err(_), Err(err)) = (&result, &saved) {
+        eprintln!("{err}");
+    }
+
+    // The summary covers the whole fleet, whatever is filtered out of the output, and is shown
+    // even if the mission failed
+    let summary = args
+        .summary
+        .then(|| Summary::new(&world.rovers, usize::from(result.is_err())));
+    if let (Some(summary), Err(_)) = (&summary, &result) {
+        eprintln!("{summary}");
+    }
+
+    let mut rovers = result?;
+    saved?;
+
+    if diagnostics.denied() > 0 {
+        return Err(RoverErr::DeniedWarnings(diagnostics.denied()));
+    }
+
+    if args.unique_final_positions {
+        RoverControlSatellite::check_final_positions(&rovers)?;
+    }
+
+    // Intersections are only reported, they never fail the mission
+    for intersection in &intersections {
+        eprintln!("{intersection}");
+    }
+
+    args.sort.sort(&mut rovers);
+    if let Some(filter) = args.filter {
+        rovers.retain(|rover| filter.matches(rover));
+    }
+
+    let output = formatter.format(
+        &rovers,
+        world.bounderies,
+        diagnostics.warnings(),
+        args.output_version,
+    )?;
+
+    // The world files and included files the missions read are covered along with the missions
+    for path in world_paths.iter().chain(&sources.included) {
+        let source = path.display().to_string();
+        if !inputs.iter().any(|(other, _)| *other == source) {
+            let file = fs::read_to_string(path).map_err(RoverErr::Opening)?;
+            inputs.push((source, sign::digest(&file)));
+        }
+    }
+
+    if let Some(manifest_path) = &args.manifest {
+        let manifest = Manifest {
+            settings,
+            inputs: inputs.clone(),
+            seed: world.rng.seed(),
+            output_hash: sign::digest(&output),
+        };
+        fs::write(manifest_path, manifest.to_json(args.output_version))
+            .map_err(RoverErr::Saving)?;
+    }
 
     // Output the result
     if let Some(output_path) = args.output {
+        if let Some(key) = key {
+            let signature = Signature::new(&key, inputs, &output);
+            fs::write(sign::signature_path(&output_path), signature.to_file())
+                .map_err(RoverErr::Saving)?;
+        }
         fs::write(output_path, output).map_err(RoverErr::Saving)?;
     } else {
         println!("{}", output);
     }
 
+    if let Some(summary) = summary {
+        eprintln!("{summary}");
+    }
+
+    Ok(())
+}
+
+/// Read an instructions file the way the main run does, along with the world files it names
+fn open_mission(
+    input_path: PathBuf,
+    defines: &Defines,
+) -> Result<(Vec<MissionText>, Vec<Plateau>), RoverErr> {
+    let texts = MissionSource::Files(vec![input_path]).read()?;
+    input::prepare(texts, None, defines)
+}
+
+/// Read an instructions file and expand its macros, along with the world files it names
+fn expand_mission_file(
+    input_path: PathBuf,
+    defines: &Defines,
+) -> Result<(Expansion, Vec<Plateau>), RoverErr> {
+    let (texts, plateaus) = open_mission(input_path, defines)?;
+    let MissionText {
+        path,
+        text,
+        origins,
+    } = &texts[0];
+    let expansion = input::expand(text, path.as_deref(), origins.as_deref(), defines)?;
+    Ok((expansion, plateaus))
+}
+
+/// Read and parse an instructions file, along with the world files it names
+fn parse_mission_file(
+    input_path: PathBuf,
+    locale: InstructionLocale,
+    diagnostics: &mut Diagnostics,
+) -> Result<(Mission, Vec<Plateau>), RoverErr> {
+    let (texts, plateaus) = open_mission(input_path, &Defines::new())?;
+    let mission = input::parse_mission(texts, locale, &Defines::new(), diagnostics)?;
+    Ok((mission, plateaus))
+}
+
+#[inline]
+fn analyze_input(
+    input_path: PathBuf,
+    locale: InstructionLocale,
+    policy: Policy,
+) -> Result<(), RoverErr> {
+    let mut diagnostics = Diagnostics::default().with_policy(policy);
+    let defines = Defines::new();
+    let texts = MissionSource::Files(vec![input_path]).read()?;
+    let world_path = texts
+        .first()
+        .and_then(|text| input::world_file(text, &defines))
+        .transpose()?;
+    let (texts, plateaus) = input::prepare(texts, None, &defines)?;
+    let (mission, sources) =
+        input::parse_mission_with_provenance(texts, locale, &defines, &mut diagnostics)?;
+
+    for warning in diagnostics.warnings() {
+        eprintln!("{warning} [{}]", warning.code());
+    }
+    if diagnostics.denied() > 0 {
+        return Err(RoverErr::DeniedWarnings(diagnostics.denied()));
+    }
+
+    // Point each finding at where it was written, as errors raised whilst executing are
+    let findings = analyze::analyze(&mission, plateaus.first())
+        .into_iter()
+        .map(|finding| finding.locate(&sources, world_path.as_deref()))
+        .collect::<Vec<_>>();
+    for finding in &findings {
+        println!("{finding}");
+    }
+
+    if !findings.is_empty() {
+        return Err(RoverErr::Analysis(findings.len()));
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn estimate_input(
+    input_path: PathBuf,
+    locale: InstructionLocale,
+    model: CostModel,
+    policy: Policy,
+) -> Result<(), RoverErr> {
+    let mut diagnostics = Diagnostics::default().with_policy(policy);
+    let (mission, _) = parse_mission_file(input_path, locale, &mut diagnostics)?;
+
+    for warning in diagnostics.warnings() {
+        eprintln!("{warning} [{}]", warning.code());
+    }
+    if diagnostics.denied() > 0 {
+        return Err(RoverErr::DeniedWarnings(diagnostics.denied()));
+    }
+
+    let estimates = estimate::estimate(&mission, &model);
+    for estimate in &estimates {
+        println!("{estimate}");
+    }
+    println!("{}", estimate::total(&estimates));
+
+    Ok(())
+}
+
+#[inline]
+fn render_input(
+    input_path: PathBuf,
+    asciinema: Option<PathBuf>,
+    frame_delay: Duration,
+    simulation: Simulation,
+) -> Result<(), RoverErr> {
+    let mut diagnostics = simulation.diagnostics();
+    let (mission, plateaus) = parse_mission_file(input_path, simulation.locale, &mut diagnostics)?;
+    let simulation = simulation.with_plateaus(&plateaus);
+    let mut world = simulation.world();
+    let mut trace = Trace::new(TraceFilter::default());
+    let result = RoverControlSatellite::execute_mission(
+        &mission,
+        &mut world,
+        &mut simulation.budget(),
+        &mut diagnostics,
+        &mut trace,
+    );
+    // The frames are drawn even if the mission failed, up to where it did
+    let frames = render::render_frames(mission.bounderies, &Timeline::new(&mission, &trace))?;
+
+    match asciinema {
+        Some(cast_path) => fs::write(
+            cast_path,
+            render::to_asciinema(&frames, frame_delay.as_secs_f64()),
+        )
+        .map_err(RoverErr::Saving)?,
+        None => println!("{}", frames.join("\n\n")),
+    }
+
+    for warning in diagnostics.warnings() {
+        eprintln!("{warning} [{}]", warning.code());
+    }
+    result?;
+    if diagnostics.denied() > 0 {
+        return Err(RoverErr::DeniedWarnings(diagnostics.denied()));
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn fmt_input(
+    input_path: PathBuf,
+    expand: bool,
+    canonical: bool,
+    write: bool,
+    defines: Defines,
+    locale: InstructionLocale,
+) -> Result<(), RoverErr> {
+    // Open instructions file
+    let file = fs::read_to_string(&input_path).map_err(RoverErr::Opening)?;
+
+    let text = MissionText {
+        path: Some(input_path.clone()),
+        text: file,
+        origins: None,
+    };
+    let formatted = match canonical {
+        true => pretty::canonical(text, locale, &defines)?,
+        false => pretty::format_mission(text, locale, &defines, expand)?,
+    };
+
+    match write {
+        true => fs::write(input_path, formatted).map_err(RoverErr::Saving)?,
+        false => print!("{formatted}"),
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn bench_input(
+    input_path: PathBuf,
+    iterations: usize,
+    simulation: Simulation,
+    defines: Defines,
+) -> Result<(), RoverErr> {
+    let (expansion, plateaus) = expand_mission_file(input_path, &defines)?;
+    let simulation = simulation.with_plateaus(&plateaus);
+    let result = bench::bench(&simulation, &expansion.text, iterations)
+        .map_err(|err| expansion.locate(err))?;
+
+    println!("{result}");
+
+    Ok(())
+}
+
+fn serve_rpc(args: Args, policy: Policy) -> Result<(), RoverErr> {
+    let mut session = RpcSession::new(Simulation {
+        unbounded: args.unbounded,
+        locale: args.instruction_locale,
+        safety_margin: args.safety_margin,
+        craters: args.craters,
+        tags: args.tags,
+        lost_rovers: args.lost_rovers,
+        model: args.model,
+        seed: args.seed,
+        generator: args.rng,
+        autonomy: args.autonomy,
+        recovery: args.recovery,
+        policy,
+        ..Simulation::default()
+    });
+
+    session.serve(io::stdin().lock(), io::stdout().lock())
+}
+
+fn montecarlo_input(
+    input_path: PathBuf,
+    runs: usize,
+    seed: u64,
+    simulation: Simulation,
+    costs: CostModel,
+    defines: Defines,
+) -> Result<(), RoverErr> {
+    let (expansion, plateaus) = expand_mission_file(input_path, &defines)?;
+    let simulation = simulation.with_plateaus(&plateaus);
+    let result = montecarlo::montecarlo(&simulation, &expansion.text, runs, seed, &costs)
+        .map_err(|err| expansion.locate(err))?;
+
+    println!("{result}");
+
+    Ok(())
+}
+
+#[inline]
+fn validate_worlds(world_paths: Vec<PathBuf>) -> Result<(), RoverErr> {
+    let mut count = 0;
+    for world_path in world_paths {
+        let issues = Plateau::open(&world_path)?.validate();
+        for issue in &issues {
+            println!("{}: {issue}", world_path.display());
+        }
+        count += issues.len();
+    }
+
+    if count > 0 {
+        return Err(RoverErr::InvalidPlateau(count));
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn verify_output(
+    output_path: PathBuf,
+    input_paths: Vec<PathBuf>,
+    key_path: PathBuf,
+) -> Result<(), RoverErr> {
+    let key = sign::verifying_key(&fs::read_to_string(key_path).map_err(RoverErr::OpeningKey)?)?;
+    let output = fs::read_to_string(&output_path).map_err(RoverErr::Opening)?;
+    let file = fs::read_to_string(sign::signature_path(&output_path)).map_err(RoverErr::Opening)?;
+    let signature = Signature::from_file(&file)?;
+    // The world files and included files signed after the missions are read from where the run
+    // read them
+    let missions = input_paths.len();
+    let inputs = input_paths
+        .into_iter()
+        .chain(
+            signature
+                .inputs
+                .iter()
+                .skip(missions)
+                .map(|(source, _)| PathBuf::from(source)),
+        )
+        .map(|input_path| fs::read_to_string(input_path).map_err(RoverErr::Opening))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    signature.verify(&key, &output, &inputs)
+}
+
+#[inline]
+fn query_trace(trace_path: PathBuf, query: Query) -> Result<(), RoverErr> {
+    let file = fs::read_to_string(trace_path).map_err(RoverErr::OpeningTrace)?;
+    let events = query::parse_trace(&file)?;
+
+    for event in query.run(&events) {
+        println!("{event}");
+    }
+
     Ok(())
 }
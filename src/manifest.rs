@@ -0,0 +1,97 @@
+use crate::{config::Setting, enums::SchemaVersion, json};
+
+/// Everything needed to repeat a run and check that it produced the same output
+#[derive(Debug, PartialEq)]
+pub struct Manifest {
+    pub settings: Vec<Setting>,
+    /// Where each text of instructions came from, and the SHA-256 digest of its contents
+    pub inputs: Vec<(String, String)>,
+    pub seed: u64,
+    pub output_hash: String,
+}
+
+impl Manifest {
+    pub fn to_json(&self, version: SchemaVersion) -> String {
+        let settings = self
+            .settings
+            .iter()
+            .map(|setting| {
+                format!(
+                    r#"{{"name": "{}", "value": "{}", "source": "{}"}}"#,
+                    json::escape(&setting.name),
+                    json::escape(&setting.value),
+                    json::escape(&setting.source.to_string())
+                )
+            })
+            .collect::<Vec<String>>();
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|(source, hash)| {
+                format!(
+                    r#"{{"source": "{}", "hash": "{}"}}"#,
+                    json::escape(source),
+                    hash
+                )
+            })
+            .collect::<Vec<String>>();
+
+        format!(
+            "{{\n  \"schema_version\": {},\n  \"crate_version\": \"{}\",\n  \"seed\": {},\n  \"settings\": [\n    {}\n  ],\n  \"inputs\": [\n    {}\n  ],\n  \"output_hash\": \"{}\"\n}}\n",
+            version.number(),
+            env!("CARGO_PKG_VERSION"),
+            self.seed,
+            settings.join(",\n    "),
+            inputs.join(",\n    "),
+            self.output_hash,
+        )
+    }
+}
+
+#[cfg(test)]
+mod manifest_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod to_json {
+        use super::Manifest;
+        use crate::{
+            config::{Setting, Source},
+            enums::SchemaVersion,
+        };
+
+        #[test]
+        fn valid_input() {
+            let manifest = Manifest {
+                settings: vec![Setting {
+                    name: "sort".to_string(),
+                    value: "x".to_string(),
+                    source: Source::Environment("MARS_ROVER_SORT".to_string()),
+                }],
+                inputs: vec![("foo.txt".to_string(), "sha256:0".to_string())],
+                seed: 7,
+                output_hash: "sha256:1".to_string(),
+            };
+
+            assert_eq!(
+                manifest.to_json(SchemaVersion::V2),
+                format!(
+                    r#"{{
+  "schema_version": 2,
+  "crate_version": "{}",
+  "seed": 7,
+  "settings": [
+    {{"name": "sort", "value": "x", "source": "environment: MARS_ROVER_SORT"}}
+  ],
+  "inputs": [
+    {{"source": "foo.txt", "hash": "sha256:0"}}
+  ],
+  "output_hash": "sha256:1"
+}}
+"#,
+                    env!("CARGO_PKG_VERSION")
+                )
+            );
+        }
+    }
+}
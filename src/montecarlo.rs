@@ -0,0 +1,240 @@
+use std::{
+    cmp::Reverse,
+    fmt::{self, Display},
+};
+
+use crate::{
+    enums::RoverErr, estimate::CostModel, position::Pose, rng::Rng, rover::RoverControlSatellite,
+    simulation::Simulation, trace::Trace,
+};
+
+/// What became of one rover over every run of a mission
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RoverStats {
+    pub id: usize,
+    /// Where the rover finished and how many runs it finished there, most common first
+    pub finishes: Vec<(Pose, usize)>,
+    /// Runs the rover was lost in
+    pub lost: usize,
+    /// Runs the mission failed before the rover could finish
+    pub failed: usize,
+    /// The fuel used in each run the rover finished or was lost in
+    pub fuel: Vec<f64>,
+}
+
+impl RoverStats {
+    fn failures(&self) -> usize {
+        self.lost + self.failed
+    }
+
+    fn mean_fuel(&self) -> f64 {
+        match self.fuel.is_empty() {
+            true => 0.0,
+            false => self.fuel.iter().sum::<f64>() / self.fuel.len() as f64,
+        }
+    }
+}
+
+/// The distributions of many runs of one mission
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarlo {
+    pub runs: usize,
+    pub seed: u64,
+    /// Runs that ended with an error rather than a report
+    pub failed_missions: usize,
+    pub rovers: Vec<RoverStats>,
+}
+
+impl Display for MonteCarlo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Runs: {}, Seed: {}, Failed Missions: {} ({:.1}%)",
+            self.runs,
+            self.seed,
+            self.failed_missions,
+            percent(self.failed_missions, self.runs)
+        )?;
+
+        for rover in &self.rovers {
+            let fuel = |pick: fn(f64, f64) -> f64| rover.fuel.iter().copied().reduce(pick);
+            write!(
+                f,
+                "\nRover {} - Failures: {:.1}% (lost: {}, failed: {}), Fuel: mean {:.1}, min {:.1}, max {:.1}",
+                rover.id,
+                percent(rover.failures(), self.runs),
+                rover.lost,
+                rover.failed,
+                rover.mean_fuel(),
+                fuel(f64::min).unwrap_or_default(),
+                fuel(f64::max).unwrap_or_default()
+            )?;
+            for (pose, count) in &rover.finishes {
+                write!(
+                    f,
+                    "\n  {pose}: {count} ({:.1}%)",
+                    percent(*count, self.runs)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn percent(count: usize, runs: usize) -> f64 {
+    count as f64 * 100.0 / runs.max(1) as f64
+}
+
+/// Execute a mission `runs` times with the simulation's stochastic model, collecting where each
+/// rover finished, how often it failed and the fuel it used
+///
+/// Each run draws from its own generator, seeded from `seed`, so the same seed gives the same
+/// distributions. The mission is parsed once, and a parse error fails every run.
+pub fn montecarlo(
+    simulation: &Simulation,
+    message: &str,
+    runs: usize,
+    seed: u64,
+    costs: &CostModel,
+) -> Result<MonteCarlo, RoverErr> {
+    let mission = RoverControlSatellite::parse_incoming_message(
+        message.to_string(),
+        simulation.locale,
+        &mut simulation.diagnostics(),
+    )?;
+
+    let mut result = MonteCarlo {
+        runs,
+        seed,
+        failed_missions: 0,
+        rovers: (1..=mission.rovers.len())
+            .map(|id| RoverStats {
+                id,
+                ..RoverStats::default()
+            })
+            .collect(),
+    };
+
+    let mut seeds = Rng::new(seed);
+    for _ in 0..runs {
+        let mut world = simulation.world();
+        world.rng = Rng::with_generator(simulation.generator, seeds.next_u64());
+
+        let mut diagnostics = simulation.diagnostics();
+        let outcome = RoverControlSatellite::execute_mission(
+            &mission,
+            &mut world,
+            &mut simulation.budget(),
+            &mut diagnostics,
+            &mut Trace::default(),
+        );
+        // A run raising a denied warning fails, but its rovers still finished
+        if outcome.is_err() || diagnostics.denied() > 0 {
+            result.failed_missions += 1;
+        }
+
+        // Rovers that finished are kept in the world even if a later one failed the mission
+        for stats in result.rovers.iter_mut() {
+            let Some(rover) = world.rovers.iter().find(|rover| rover.id == stats.id) else {
+                stats.failed += 1;
+                continue;
+            };
+            stats.fuel.push(costs.fuel(rover.odometer, rover.turns));
+            if rover.lost {
+                stats.lost += 1;
+                continue;
+            }
+
+            let finish = rover.pose;
+            match stats.finishes.iter_mut().find(|(seen, _)| *seen == finish) {
+                Some((_, count)) => *count += 1,
+                None => stats.finishes.push((finish, 1)),
+            }
+        }
+    }
+
+    for stats in result.rovers.iter_mut() {
+        // Stable, so ties stay in the order they were first seen
+        stats.finishes.sort_by_key(|(_, count)| Reverse(*count));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod montecarlo_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod montecarlo {
+        use super::montecarlo;
+        use crate::{
+            enums::{Direction, RoverErr},
+            estimate::CostModel,
+            position::{Pose, Position},
+            simulation::Simulation,
+            stochastic::StochasticModel,
+        };
+
+        const MISSION: &str = "9 9\n1 2 N\nLMLMLMLMM\n3 3 E\nMMRMMRMRRM";
+
+        #[test]
+        fn deterministic_mission() {
+            let result = montecarlo(
+                &Simulation::default(),
+                MISSION,
+                10,
+                1,
+                &CostModel::default(),
+            )
+            .unwrap();
+            assert_eq!(result.failed_missions, 0);
+            assert_eq!(
+                result.rovers[0].finishes,
+                vec![(Pose::new(Position::new(1, 3), Direction::North), 10)]
+            );
+            assert_eq!(
+                result.rovers[1].finishes,
+                vec![(Pose::new(Position::new(5, 1), Direction::East), 10)]
+            );
+            assert_eq!(result.rovers[0].fuel, vec![7.0; 10]);
+        }
+
+        #[test]
+        fn stochastic_mission() {
+            let simulation = Simulation {
+                model: StochasticModel {
+                    slippage: 0.2,
+                    hazard: 0.05,
+                    link_loss: 0.1,
+                },
+                lost_rovers: true,
+                ..Simulation::default()
+            };
+            let run = |seed| montecarlo(&simulation, MISSION, 200, seed, &CostModel::default());
+
+            let result = run(1).unwrap();
+            assert_eq!(result, run(1).unwrap());
+            assert_ne!(result, run(2).unwrap());
+            for rover in &result.rovers {
+                let finished = rover.finishes.iter().map(|(_, count)| count).sum::<usize>();
+                assert_eq!(finished + rover.lost + rover.failed, 200);
+                assert!(rover.finishes.len() > 1);
+                assert!(rover.lost > 0);
+            }
+        }
+
+        #[test]
+        fn invalid_input() {
+            let result = montecarlo(
+                &Simulation::default(),
+                "9 9\n1 2 N",
+                10,
+                1,
+                &CostModel::default(),
+            );
+            assert!(matches!(result, Err(RoverErr::Parse(_, _))));
+        }
+    }
+}
@@ -1,15 +1,23 @@
+use std::path::PathBuf;
+
 use nom::{
     branch::alt,
-    bytes::complete::tag_no_case,
-    character::complete::{char, multispace0, multispace1, one_of},
-    combinator::{map_res, recognize},
+    bytes::complete::{tag, tag_no_case, take_while1},
+    character::complete::{anychar, char, multispace0, multispace1, one_of},
+    combinator::{map, map_opt, map_res, opt, recognize, value},
     error::context,
-    multi::{many0, many1},
-    sequence::{separated_pair, terminated},
+    multi::{fold_many0, many0, many1, separated_list1},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
-use crate::enums::{Coordinate, Direction, Instruction};
+use crate::{
+    enums::{Direction, Instruction, InstructionLocale},
+    expand::Provenance,
+    position::{Pose, Position},
+    rover::Attributes,
+    trace::TraceEvent,
+};
 
 /// Parse a number as `isize`
 pub fn decimal(input: &str) -> IResult<&str, isize> {
@@ -22,9 +30,24 @@ pub fn decimal(input: &str) -> IResult<&str, isize> {
     )(input)
 }
 
+/// Parse a number as `isize`, which may be negative
+pub fn signed_decimal(input: &str) -> IResult<&str, isize> {
+    map(pair(opt(char('-')), decimal), |(sign, n)| match sign {
+        Some(_) => -n,
+        None => n,
+    })(input)
+}
+
+/// Parse a number as `usize`
+pub fn unsigned_decimal(input: &str) -> IResult<&str, usize> {
+    map_res(decimal, usize::try_from)(input)
+}
+
 /// Parse a co-ordinate form a pair of numbers seperated by a space
-pub fn coordinate(input: &str) -> IResult<&str, (isize, isize)> {
-    separated_pair(decimal, multispace1, decimal)(input)
+pub fn coordinate(input: &str) -> IResult<&str, Position> {
+    map(separated_pair(decimal, multispace1, decimal), |(x, y)| {
+        Position::new(x, y)
+    })(input)
 }
 
 /// Parse a direction (North, East, South or West)
@@ -43,21 +66,180 @@ pub fn direction(input: &str) -> IResult<&str, Direction> {
 
 /// Parse an instruction (move, turn left or turn right)
 pub fn instruction(input: &str) -> IResult<&str, Instruction> {
-    context(
-        "instruction",
-        alt((tag_no_case("M"), tag_no_case("L"), tag_no_case("R"))),
-    )(input)
-    .map(|(next_input, res)| (next_input, res.into()))
+    localized_instruction(InstructionLocale::English)(input)
+}
+
+/// Build a parser for an instruction written in the alphabet of the given locale
+pub fn localized_instruction(
+    locale: InstructionLocale,
+) -> impl Fn(&str) -> IResult<&str, Instruction> {
+    move |input| {
+        context(
+            "instruction",
+            map_opt(anychar, |letter| locale.instruction(letter)),
+        )(input)
+    }
 }
 
 /// Parse a starting position of a rover (co-ordinate + direction)
-pub fn starting_position(input: &str) -> IResult<&str, (Coordinate, Direction)> {
-    separated_pair(coordinate, multispace1, direction)(input)
+pub fn starting_position(input: &str) -> IResult<&str, Pose> {
+    map(
+        separated_pair(coordinate, multispace1, direction),
+        |(position, facing)| Pose::new(position, facing),
+    )(input)
+}
+
+/// A setting written after a rover's starting position
+enum Attribute<'a> {
+    Count(&'a str, usize),
+    Name(&'a str),
+}
+
+/// Parse a rover's starting position, optionally followed by its attributes
+/// (e.g. `1 2 N priority=3 maintenance=20 name=spirit`)
+pub fn rover_header(input: &str) -> IResult<&str, (Pose, Attributes)> {
+    let count = map(
+        separated_pair(
+            alt((tag("id"), tag("priority"), tag("maintenance"))),
+            char('='),
+            unsigned_decimal,
+        ),
+        |(key, value)| Attribute::Count(key, value),
+    );
+    let name = map(
+        preceded(
+            tag("name="),
+            take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        ),
+        Attribute::Name,
+    );
+
+    pair(
+        starting_position,
+        fold_many0(
+            preceded(multispace1, alt((count, name))),
+            Attributes::default,
+            |mut attributes, attribute| {
+                match attribute {
+                    Attribute::Count("id", value) => attributes.id = Some(value),
+                    Attribute::Count("priority", value) => attributes.priority = value,
+                    Attribute::Count(_, value) => attributes.maintenance = Some(value),
+                    Attribute::Name(name) => attributes.name = Some(name.to_string()),
+                }
+                attributes
+            },
+        ),
+    )(input)
 }
 
 /// Parse a vector of instructions
 pub fn instruction_stream(input: &str) -> IResult<&str, Vec<Instruction>> {
-    many1(terminated(instruction, multispace0))(input)
+    localized_instruction_stream(InstructionLocale::English)(input)
+}
+
+/// Build a parser for a vector of instructions written in the alphabet of the given locale
+pub fn localized_instruction_stream(
+    locale: InstructionLocale,
+) -> impl Fn(&str) -> IResult<&str, Vec<Instruction>> {
+    move |input| many1(terminated(localized_instruction(locale), multispace0))(input)
+}
+
+/// Build a parser for a rover's ID followed by further instructions for it (e.g. `2 MMRM`)
+pub fn resumed_stream(
+    locale: InstructionLocale,
+) -> impl Fn(&str) -> IResult<&str, (usize, Vec<Instruction>)> {
+    move |input| {
+        separated_pair(
+            unsigned_decimal,
+            multispace1,
+            localized_instruction_stream(locale),
+        )(input)
+    }
+}
+
+/// Parse the kind of an instruction as written in traces (move, left, right, halt or service)
+pub fn instruction_kind(input: &str) -> IResult<&str, Option<Instruction>> {
+    context(
+        "instruction kind",
+        alt((
+            value(None, tag("start")),
+            value(Some(Instruction::Move), tag("move")),
+            value(Some(Instruction::Left), tag("left")),
+            value(Some(Instruction::Right), tag("right")),
+            value(Some(Instruction::Halt), tag("halt")),
+            value(Some(Instruction::Service), tag("service")),
+        )),
+    )(input)
+}
+
+/// Parse a line of a trace file (tick, rover, instruction kind, co-ordinate + direction), which
+/// may end with `lost` if the rover was lost and the tags of the cell entered (e.g.
+/// `tags=slippery,science-target`)
+pub fn trace_event(input: &str) -> IResult<&str, TraceEvent> {
+    map(
+        tuple((
+            unsigned_decimal,
+            preceded(multispace1, unsigned_decimal),
+            preceded(multispace1, instruction_kind),
+            preceded(multispace1, signed_decimal),
+            preceded(multispace1, signed_decimal),
+            preceded(multispace1, direction),
+            opt(preceded(multispace1, tag("lost"))),
+            opt(preceded(
+                tuple((multispace1, tag("tags="))),
+                separated_list1(
+                    char(','),
+                    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                ),
+            )),
+            opt(preceded(multispace1, provenance)),
+        )),
+        |(tick, rover, instruction, x, y, facing, lost, tags, source)| TraceEvent {
+            tick,
+            rover,
+            instruction,
+            pose: Pose::new(Position::new(x, y), facing),
+            lost: lost.is_some(),
+            tags: tags
+                .unwrap_or_default()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            source,
+        },
+    )(input)
+}
+
+/// Parse where a traced instruction was written, as `source=FILE:LINE` (or `source=LINE` if it
+/// was not read from a file) optionally followed by `macro=NAME:INDEX`
+pub fn provenance(input: &str) -> IResult<&str, Provenance> {
+    let word = |c: char| !c.is_whitespace() && c != ':';
+    map(
+        pair(
+            preceded(
+                tag("source="),
+                map_opt(take_while1(|c: char| !c.is_whitespace()), |source: &str| {
+                    let (file, line) = match source.rsplit_once(':') {
+                        Some((file, line)) => (Some(PathBuf::from(file)), line),
+                        None => (None, source),
+                    };
+                    Some((file, line.parse::<usize>().ok()?.checked_sub(1)?))
+                }),
+            ),
+            opt(preceded(
+                tuple((multispace1, tag("macro="))),
+                map_opt(
+                    separated_pair(take_while1(word), char(':'), unsigned_decimal),
+                    |(name, index): (&str, usize)| Some((name.to_string(), index.checked_sub(1)?)),
+                ),
+            )),
+        ),
+        |((file, line), expansion)| Provenance {
+            file,
+            line,
+            expansion,
+        },
+    )(input)
 }
 
 #[cfg(test)]
@@ -87,14 +269,26 @@ mod parse_module {
         }
     }
 
+    #[cfg(test)]
+    mod signed_decimal {
+        use super::signed_decimal;
+
+        #[test]
+        fn negative_number() {
+            let result = signed_decimal("-12");
+            assert_eq!(result, Ok(("", -12)));
+        }
+    }
+
     #[cfg(test)]
     mod coordinate {
         use super::coordinate;
+        use crate::position::Position;
 
         #[test]
         fn valid_input() {
             let result = coordinate("123   456");
-            assert_eq!(result, Ok(("", (123, 456))));
+            assert_eq!(result, Ok(("", Position::new(123, 456))));
         }
 
         #[test]
@@ -144,16 +338,43 @@ mod parse_module {
         }
     }
 
+    #[cfg(test)]
+    mod localized_instruction {
+        use crate::enums::{Instruction, InstructionLocale};
+
+        use super::localized_instruction;
+
+        #[test]
+        fn valid_input() {
+            let parser = localized_instruction(InstructionLocale::French);
+            assert_eq!(parser("A"), Ok(("", Instruction::Move)));
+            assert_eq!(parser("g"), Ok(("", Instruction::Left)));
+            assert_eq!(parser("D"), Ok(("", Instruction::Right)));
+        }
+
+        #[test]
+        fn letter_from_another_locale() {
+            let result = localized_instruction(InstructionLocale::French)("M");
+            assert!(result.is_err());
+        }
+    }
+
     #[cfg(test)]
     mod starting_position {
-        use crate::enums::Direction;
+        use crate::{
+            enums::Direction,
+            position::{Pose, Position},
+        };
 
         use super::starting_position;
 
         #[test]
         fn valid_input() {
             let result = starting_position("0 0 E");
-            assert_eq!(result, Ok(("", ((0, 0), Direction::East))));
+            assert_eq!(
+                result,
+                Ok(("", Pose::new(Position::new(0, 0), Direction::East)))
+            );
         }
 
         #[test]
@@ -163,6 +384,101 @@ mod parse_module {
         }
     }
 
+    #[cfg(test)]
+    mod rover_header {
+        use crate::{
+            enums::Direction,
+            position::{Pose, Position},
+            rover::Attributes,
+        };
+
+        use super::rover_header;
+
+        #[test]
+        fn with_priority() {
+            let result = rover_header("1 2 N priority=3");
+            let attributes = Attributes {
+                id: None,
+                name: None,
+                priority: 3,
+                maintenance: None,
+            };
+            assert_eq!(
+                result,
+                Ok((
+                    "",
+                    (Pose::new(Position::new(1, 2), Direction::North), attributes)
+                ))
+            );
+        }
+
+        #[test]
+        fn with_id() {
+            let result = rover_header("1 2 N id=2");
+            let attributes = Attributes {
+                id: Some(2),
+                ..Attributes::default()
+            };
+            assert_eq!(
+                result,
+                Ok((
+                    "",
+                    (Pose::new(Position::new(1, 2), Direction::North), attributes)
+                ))
+            );
+        }
+
+        #[test]
+        fn with_name() {
+            let result = rover_header("1 2 N name=spirit-2 priority=1");
+            let attributes = Attributes {
+                name: Some("spirit-2".to_string()),
+                priority: 1,
+                ..Attributes::default()
+            };
+            assert_eq!(
+                result,
+                Ok((
+                    "",
+                    (Pose::new(Position::new(1, 2), Direction::North), attributes)
+                ))
+            );
+        }
+
+        #[test]
+        fn with_maintenance() {
+            let result = rover_header("1 2 N maintenance=20 priority=3");
+            let attributes = Attributes {
+                id: None,
+                name: None,
+                priority: 3,
+                maintenance: Some(20),
+            };
+            assert_eq!(
+                result,
+                Ok((
+                    "",
+                    (Pose::new(Position::new(1, 2), Direction::North), attributes)
+                ))
+            );
+        }
+
+        #[test]
+        fn without_priority() {
+            let result = rover_header("1 2 N");
+            assert_eq!(
+                result,
+                Ok((
+                    "",
+                    (
+                        Pose::new(Position::new(1, 2), Direction::North),
+                        Attributes::default()
+                    )
+                ))
+            );
+        }
+    }
+
     #[cfg(test)]
     mod instruction_stream {
         use crate::enums::Instruction;
@@ -193,4 +509,59 @@ mod parse_module {
             );
         }
     }
+
+    #[cfg(test)]
+    mod trace_event {
+        use crate::enums::{Direction, Instruction};
+
+        use super::trace_event;
+
+        #[test]
+        fn valid_input() {
+            let result = trace_event("3 2 move -1 4 W").map(|(_, event)| event.to_string());
+            assert_eq!(result, Ok("3 2 move -1 4 W".to_string()));
+            let (_, event) = trace_event("0 1 left 0 0 W").unwrap();
+            assert_eq!(event.instruction, Some(Instruction::Left));
+            assert_eq!(event.pose.facing, Direction::West);
+        }
+
+        #[test]
+        fn starting_pose() {
+            let (_, event) = trace_event("0 2 start 1 2 N").unwrap();
+            assert_eq!(event.instruction, None);
+        }
+
+        #[test]
+        fn with_tags() {
+            let (_, event) = trace_event("4 1 move 2 3 E tags=slippery,science-target").unwrap();
+            assert_eq!(event.tags, vec!["slippery", "science-target"]);
+        }
+
+        #[test]
+        fn lost() {
+            let (_, event) = trace_event("4 1 move 2 3 E lost tags=slippery").unwrap();
+            assert!(event.lost);
+            assert_eq!(event.to_string(), "4 1 move 2 3 E lost tags=slippery");
+        }
+
+        #[test]
+        fn with_source() {
+            let line = "2 1 right 1 2 E source=missions/launch.txt:4 macro=TURN:2";
+            let (_, event) = trace_event(line).unwrap();
+            let source = event.source.as_ref().unwrap();
+            assert_eq!(source.file, Some("missions/launch.txt".into()));
+            assert_eq!(source.line, 3);
+            assert_eq!(source.expansion, Some(("TURN".to_string(), 1)));
+            assert_eq!(event.to_string(), line);
+
+            let (_, event) = trace_event("0 1 move 1 3 N source=2").unwrap();
+            assert_eq!(event.source.unwrap().file, None);
+        }
+
+        #[test]
+        fn unknown_kind() {
+            let result = trace_event("0 1 jump 0 0 N");
+            assert!(result.is_err());
+        }
+    }
 }
@@ -0,0 +1,255 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    enums::RoverErr,
+    parse::coordinate,
+    position::{Bounds, Position},
+    terrain::parse_tagged_cell,
+    world::{parse_cell, World},
+};
+
+/// A feature of the plateau, given on a line of a world file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Feature {
+    /// `crater X,Y`
+    Crater(Position),
+    /// `tag X,Y=TAG`
+    Tag(Position, String),
+}
+
+/// A plateau and its terrain, kept in a world file of its own so many missions can share it
+///
+/// The first line is the plateau's bounderies, written as in a mission, and each line after it
+/// is a feature. Blank lines and lines starting with `#` are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plateau {
+    pub bounderies: Position,
+    /// Each feature, with the index of the line it was given on
+    pub features: Vec<(usize, Feature)>,
+}
+
+impl Plateau {
+    pub fn parse(text: &str) -> Result<Self, RoverErr> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let (index, first) = lines.next().ok_or(RoverErr::PlateauParse(0))?;
+        let bounderies = RoverErr::from_parse_result(coordinate(first), index)
+            .map_err(|_| RoverErr::PlateauParse(index))?;
+
+        let features = lines
+            .map(|(index, line)| {
+                let feature = match line.split_once(char::is_whitespace) {
+                    Some(("crater", cell)) => parse_cell(cell.trim()).ok().map(Feature::Crater),
+                    Some(("tag", cell)) => parse_tagged_cell(cell.trim())
+                        .ok()
+                        .map(|(position, tag)| Feature::Tag(position, tag)),
+                    _ => None,
+                };
+                feature
+                    .map(|feature| (index, feature))
+                    .ok_or(RoverErr::PlateauParse(index))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Plateau {
+            bounderies,
+            features,
+        })
+    }
+
+    /// Read and parse a world file, errors name the file
+    pub fn open(path: &Path) -> Result<Self, RoverErr> {
+        let file = fs::read_to_string(path).map_err(RoverErr::OpeningPlateau)?;
+        Plateau::parse(&file).map_err(|err| RoverErr::InFile(path.to_path_buf(), Box::new(err)))
+    }
+
+    pub fn craters(&self) -> impl Iterator<Item = Position> + '_ {
+        self.features
+            .iter()
+            .filter_map(|(_, feature)| match feature {
+                Feature::Crater(position) => Some(*position),
+                Feature::Tag(..) => None,
+            })
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = (Position, String)> + '_ {
+        self.features
+            .iter()
+            .filter_map(|(_, feature)| match feature {
+                Feature::Tag(position, tag) => Some((*position, tag.clone())),
+                Feature::Crater(_) => None,
+            })
+    }
+
+    /// Add the plateau's craters and tags to those already in the world
+    pub fn apply(&self, world: &mut World) {
+        world.craters.extend(self.craters());
+        world.add_tags(self.tags());
+    }
+
+    /// The cells of the plateau
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(self.bounderies)
+    }
+
+    /// Check for features that could never come into play
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        for (index, (line, feature)) in self.features.iter().enumerate() {
+            let (Feature::Crater(position) | Feature::Tag(position, _)) = feature;
+            let earlier = &self.features[..index];
+
+            if !position.within(self.bounds()) {
+                issues.push(Issue::OutsidePlateau {
+                    position: *position,
+                    line: *line,
+                });
+            } else if earlier.iter().any(|(_, other)| other == feature) {
+                issues.push(Issue::Duplicate {
+                    position: *position,
+                    line: *line,
+                });
+            } else if let Feature::Tag(_, tag) = feature {
+                if self.craters().any(|crater| crater == *position) {
+                    issues.push(Issue::TagOnCrater {
+                        tag: tag.clone(),
+                        position: *position,
+                        line: *line,
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// A problem found by validating a world file
+#[derive(Debug, PartialEq)]
+pub enum Issue {
+    /// A feature on a cell that is not on the plateau
+    OutsidePlateau { position: Position, line: usize },
+    /// A feature given again on the same cell
+    Duplicate { position: Position, line: usize },
+    /// A tag on a crater, which no rover can reach
+    TagOnCrater {
+        tag: String,
+        position: Position,
+        line: usize,
+    },
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::OutsidePlateau { position, line } => write!(
+                f,
+                "World Issue 🗺️ - ({}, {}) is outside the plateau, At line: {}",
+                position.x,
+                position.y,
+                line + 1
+            ),
+            Issue::Duplicate { position, line } => write!(
+                f,
+                "World Issue 🗺️ - ({}, {}) is given the same feature twice, At line: {}",
+                position.x,
+                position.y,
+                line + 1
+            ),
+            Issue::TagOnCrater {
+                tag,
+                position,
+                line,
+            } => write!(
+                f,
+                "World Issue 🗺️ - ({}, {}) is tagged {} but is a crater no rover can reach, At line: {}",
+                position.x,
+                position.y,
+                tag,
+                line + 1
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod plateau_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod parse {
+        use super::{Feature, Plateau};
+        use crate::{enums::RoverErr, position::Position};
+
+        #[test]
+        fn valid_input() {
+            let plateau =
+                Plateau::parse("# The crater floor\n5 5\n\ncrater 2,3\ntag 0,0=base").unwrap();
+            assert_eq!(plateau.bounderies, Position::new(5, 5));
+            assert_eq!(
+                plateau.features,
+                vec![
+                    (3, Feature::Crater(Position::new(2, 3))),
+                    (4, Feature::Tag(Position::new(0, 0), "base".to_string()))
+                ]
+            );
+        }
+
+        #[test]
+        fn invalid_feature() {
+            let result = Plateau::parse("5 5\ncrater 2,3\nboulder 1,1");
+            assert!(matches!(result, Err(RoverErr::PlateauParse(2))));
+        }
+
+        #[test]
+        fn missing_bounderies() {
+            let result = Plateau::parse("crater 2,3");
+            assert!(matches!(result, Err(RoverErr::PlateauParse(0))));
+        }
+    }
+
+    #[cfg(test)]
+    mod validate {
+        use super::{Issue, Plateau};
+        use crate::position::Position;
+
+        #[test]
+        fn valid_input() {
+            let plateau = Plateau::parse("5 5\ncrater 2,3\ntag 0,0=base").unwrap();
+            assert!(plateau.validate().is_empty());
+        }
+
+        #[test]
+        fn issues() {
+            let plateau =
+                Plateau::parse("5 5\ncrater 2,3\ncrater 6,1\ncrater 2,3\ntag 2,3=science").unwrap();
+            assert_eq!(
+                plateau.validate(),
+                vec![
+                    Issue::OutsidePlateau {
+                        position: Position::new(6, 1),
+                        line: 2
+                    },
+                    Issue::Duplicate {
+                        position: Position::new(2, 3),
+                        line: 3
+                    },
+                    Issue::TagOnCrater {
+                        tag: "science".to_string(),
+                        position: Position::new(2, 3),
+                        line: 4
+                    }
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::{self, Display};
+
+use enum_iterator::{next_cycle, previous_cycle};
+
+use crate::enums::Direction;
+
+/// The most cells a plateau can have for it to be written out cell by cell
+pub const MAX_CELLS: usize = 1_000_000;
+
+/// A cell of the plateau, `x` grows to the east and `y` to the north
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Position {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Position { x, y }
+    }
+
+    /// The next cell in a direction
+    pub fn translate(self, direction: Direction) -> Self {
+        self.translate_by(direction, 1)
+    }
+
+    /// The cell some distance away in a direction
+    pub fn translate_by(self, direction: Direction, distance: isize) -> Self {
+        match direction {
+            Direction::North => Position::new(self.x, self.y + distance),
+            Direction::East => Position::new(self.x + distance, self.y),
+            Direction::South => Position::new(self.x, self.y - distance),
+            Direction::West => Position::new(self.x - distance, self.y),
+        }
+    }
+
+    /// How many moves apart two cells are, without turning more than once
+    pub fn manhattan_distance(self, other: Position) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Whether the cell is on a plateau stretching from the origin to its bounderies
+    pub fn within_bounderies(self, bounderies: Position) -> bool {
+        self.within(Bounds::new(bounderies))
+    }
+
+    /// How many cells a plateau stretching from the origin to these bounderies has, at most
+    /// `usize::MAX`
+    pub fn cells(self) -> usize {
+        Bounds::new(self).cells()
+    }
+
+    pub fn within(self, bounds: Bounds) -> bool {
+        bounds.contains(self)
+    }
+}
+
+impl From<(isize, isize)> for Position {
+    fn from((x, y): (isize, isize)) -> Self {
+        Position::new(x, y)
+    }
+}
+
+/// The cells of a plateau, stretching from the origin to its bounderies
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bounds {
+    pub bounderies: Position,
+}
+
+impl Bounds {
+    pub const fn new(bounderies: Position) -> Self {
+        Bounds { bounderies }
+    }
+
+    pub fn contains(self, position: Position) -> bool {
+        (0..=self.bounderies.x).contains(&position.x)
+            && (0..=self.bounderies.y).contains(&position.y)
+    }
+
+    /// How many cells there are, at most `usize::MAX`
+    pub fn cells(self) -> usize {
+        let width = self.bounderies.x.max(-1).saturating_add(1).unsigned_abs();
+        let height = self.bounderies.y.max(-1).saturating_add(1).unsigned_abs();
+        width.saturating_mul(height)
+    }
+}
+
+/// Where a rover is, and which way it is facing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pose {
+    pub position: Position,
+    pub facing: Direction,
+}
+
+impl Pose {
+    pub const fn new(position: Position, facing: Direction) -> Self {
+        Pose { position, facing }
+    }
+
+    /// The pose some distance forwards, facing the same way
+    pub fn forwards(self, distance: isize) -> Self {
+        Pose {
+            position: self.position.translate_by(self.facing, distance),
+            ..self
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        Pose {
+            facing: previous_cycle(&self.facing).unwrap_or_default(),
+            ..self
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        Pose {
+            facing: next_cycle(&self.facing).unwrap_or_default(),
+            ..self
+        }
+    }
+}
+
+impl Display for Pose {
+    /// As written in missions and reports, e.g. `1 3 N`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.position.x, self.position.y, self.facing)
+    }
+}
+
+#[cfg(test)]
+mod position_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod position {
+        use super::{Bounds, Position};
+        use crate::enums::Direction;
+
+        #[test]
+        fn translate() {
+            let position = Position::new(1, 2);
+            assert_eq!(position.translate(Direction::North), Position::new(1, 3));
+            assert_eq!(position.translate(Direction::West), Position::new(0, 2));
+            assert_eq!(
+                position.translate_by(Direction::South, 3),
+                Position::new(1, -1)
+            );
+        }
+
+        #[test]
+        fn manhattan_distance() {
+            let distance = Position::new(1, 2).manhattan_distance(Position::new(-2, 4));
+            assert_eq!(distance, 5);
+        }
+
+        #[test]
+        fn within() {
+            let bounds = Bounds::new(Position::new(5, 5));
+            assert!(Position::new(0, 5).within(bounds));
+            assert!(!Position::new(6, 5).within(bounds));
+            assert!(!Position::new(2, -1).within(bounds));
+        }
+    }
+
+    #[cfg(test)]
+    mod pose {
+        use super::{Pose, Position};
+        use crate::enums::Direction;
+
+        #[test]
+        fn moves() {
+            let pose = Pose::new(Position::new(1, 2), Direction::North);
+            assert_eq!(pose.turn_left().forwards(2).to_string(), "-1 2 W");
+            assert_eq!(pose.turn_right().turn_right().to_string(), "1 2 S");
+        }
+    }
+}
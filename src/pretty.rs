@@ -0,0 +1,272 @@
+use crate::{
+    diagnostics::Diagnostics,
+    enums::{InstructionLocale, RoverErr},
+    expand::{Defines, Expansion},
+    input::{self, MissionText},
+    parse::{coordinate, rover_header},
+    position::{Pose, Position},
+    rover::Attributes,
+};
+
+/// A line of a mission, as far as laying it out is concerned
+#[derive(Debug, PartialEq)]
+enum Line {
+    Bounderies(Position),
+    Header(Pose, Attributes),
+    Instructions(String),
+    /// A line using `!include`, `WORLD` or `${VAR}`, which is kept as written
+    Macro(String),
+}
+
+impl Line {
+    fn classify(line: &str) -> Self {
+        let line = line.trim();
+
+        if let Ok(("", bounderies)) = coordinate(line) {
+            Line::Bounderies(bounderies)
+        } else if let Ok(("", (pose, attributes))) = rover_header(line) {
+            Line::Header(pose, attributes)
+        } else if line.starts_with("!include")
+            || input::world_directive(line).is_some()
+            || line.contains("${")
+        {
+            Line::Macro(line.to_string())
+        } else {
+            Line::Instructions(line.split_whitespace().collect::<String>().to_uppercase())
+        }
+    }
+}
+
+/// Parse a mission and lay it out canonically, failing if it does not parse
+///
+/// Macros are kept as written unless `expand` is set, in which case the mission is written out
+/// with them expanded, and a `WORLD` line with the bounderies of its world file.
+pub fn format_mission(
+    text: MissionText,
+    locale: InstructionLocale,
+    defines: &Defines,
+    expand: bool,
+) -> Result<String, RoverErr> {
+    let mut texts = input::to_classic(vec![text], None)?;
+    let written = texts[0].text.clone();
+    input::resolve_worlds(&mut texts, defines)?;
+    let written = match expand {
+        true => Expansion::new(&texts[0].text, texts[0].path.as_deref(), defines)
+            .map(|expansion| expansion.text),
+        false => Ok(written),
+    };
+    // Errors are reported by the parser, which knows where converted lines were written
+    input::parse_mission(texts, locale, defines, &mut Diagnostics::default())?;
+
+    Ok(layout(&written?))
+}
+
+/// Write a mission in a single canonical form, so missions that mean the same are byte-identical
+///
+/// Macros are expanded and instructions are written in the English alphabet whatever locale they
+/// were read with. The rovers are sorted by their starting position, instructions and attributes.
+/// If that moves any of them, every rover is given an `id=` so they keep their IDs, and with them
+/// the order they execute in.
+pub fn canonical(
+    text: MissionText,
+    locale: InstructionLocale,
+    defines: &Defines,
+) -> Result<String, RoverErr> {
+    let (texts, _) = input::prepare(vec![text], None, defines)?;
+    let mission = input::parse_mission(texts, locale, defines, &mut Diagnostics::default())?;
+
+    let mut rovers = mission
+        .rovers
+        .iter()
+        .map(|(pose, instructions, attributes)| {
+            let instructions = instructions
+                .iter()
+                .map(|instruction| InstructionLocale::English.letter(*instruction))
+                .collect::<String>();
+            (*pose, instructions, attributes.clone())
+        })
+        .enumerate()
+        .collect::<Vec<_>>();
+    rovers.sort_by_key(|(_, (pose, instructions, attributes))| {
+        let Position { x, y } = pose.position;
+        let facing = pose.facing.to_string();
+        (
+            x,
+            y,
+            facing,
+            instructions.clone(),
+            attributes.name.clone(),
+            attributes.priority,
+            attributes.maintenance,
+        )
+    });
+    let sorted = rovers
+        .iter()
+        .enumerate()
+        .all(|(index, (written, _))| index == *written);
+
+    let mut lines = vec![format!("{} {}", mission.bounderies.x, mission.bounderies.y)];
+    for (index, (pose, instructions, mut attributes)) in rovers {
+        // The ID of the rover starts from one
+        attributes.id = (!sorted).then_some(index + 1);
+        lines.push(format!("{pose}{attributes}"));
+        lines.push(instructions);
+    }
+
+    Ok(layout(&lines.join("\n")))
+}
+
+/// Collapse whitespace, write letters in upper case and align the columns of starting positions
+pub fn layout(text: &str) -> String {
+    let lines = text.lines().map(Line::classify).collect::<Vec<Line>>();
+    let width = |column: fn(&Position) -> isize| {
+        lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Header(pose, _) => Some(column(&pose.position).to_string().len()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default()
+    };
+    let (x_width, y_width) = (width(|position| position.x), width(|position| position.y));
+
+    let mut formatted = lines
+        .into_iter()
+        .map(|line| match line {
+            Line::Bounderies(Position { x, y }) => format!("{x} {y}"),
+            Line::Header(Pose { position, facing }, attributes) => {
+                let Position { x, y } = position;
+                format!("{x:<x_width$} {y:<y_width$} {facing}{attributes}")
+            }
+            Line::Instructions(line) | Line::Macro(line) => line,
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    formatted.push('\n');
+    formatted
+}
+
+#[cfg(test)]
+mod pretty_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod format_mission {
+        use super::format_mission;
+        use crate::{
+            enums::{InstructionLocale, RoverErr},
+            expand::Defines,
+            input::MissionText,
+        };
+
+        fn text(text: &str) -> MissionText {
+            MissionText {
+                path: None,
+                text: text.to_string(),
+                origins: None,
+            }
+        }
+
+        #[test]
+        fn valid_input() {
+            let result = format_mission(
+                text("  5   5\n1 2 n\nl m l m\n12  3 e priority=2\nmmrmmrmrrm"),
+                InstructionLocale::English,
+                &Defines::new(),
+                false,
+            );
+            assert_eq!(
+                result.unwrap(),
+                "5 5\n1  2 N\nLMLM\n12 3 E priority=2\nMMRMMRMRRM\n"
+            );
+        }
+
+        #[test]
+        fn macros() {
+            let defines = Defines::from([("MOVES".to_string(), "mm".to_string())]);
+            let mission = "5 5\n1 2 n\n${MOVES}";
+
+            let preserved =
+                format_mission(text(mission), InstructionLocale::English, &defines, false);
+            assert_eq!(preserved.unwrap(), "5 5\n1 2 N\n${MOVES}\n");
+
+            let expanded =
+                format_mission(text(mission), InstructionLocale::English, &defines, true);
+            assert_eq!(expanded.unwrap(), "5 5\n1 2 N\nMM\n");
+        }
+
+        #[test]
+        fn invalid_input() {
+            let result = format_mission(
+                text("5 5\n1 2 N"),
+                InstructionLocale::English,
+                &Defines::new(),
+                false,
+            );
+            assert!(matches!(result, Err(RoverErr::Parse(_, 1))));
+        }
+    }
+
+    #[cfg(test)]
+    mod canonical {
+        use super::canonical;
+        use crate::{enums::InstructionLocale, expand::Defines, input::MissionText};
+
+        fn text(text: &str) -> MissionText {
+            MissionText {
+                path: None,
+                text: text.to_string(),
+                origins: None,
+            }
+        }
+
+        #[test]
+        fn equivalent_missions() {
+            let first = canonical(
+                text("5 5\n1 2 N priority=0\nLM${TURN}MLMLMM\n  3 3 e\nMMRMMRMRRM"),
+                InstructionLocale::English,
+                &Defines::from([("TURN".to_string(), "L".to_string())]),
+            );
+            let second = canonical(
+                text(
+                    r#"{"plateau": [5, 5], "rovers": [
+                    {"position": [1, 2], "facing": "N", "instructions": "gagagagaa"},
+                    {"position": [3, 3], "facing": "E", "instructions": "aadaadadda"}
+                ]}"#,
+                ),
+                InstructionLocale::French,
+                &Defines::new(),
+            );
+            assert_eq!(first.unwrap(), "5 5\n1 2 N\nLMLMLMLMM\n3 3 E\nMMRMMRMRRM\n");
+            assert_eq!(
+                second.unwrap(),
+                "5 5\n1 2 N\nLMLMLMLMM\n3 3 E\nMMRMMRMRRM\n"
+            );
+        }
+
+        #[test]
+        fn sorts_rovers() {
+            let result = canonical(
+                text("5 5\n3 3 E\nMMRMMRMRRM\n1 2 N\nLMLMLMLMM"),
+                InstructionLocale::English,
+                &Defines::new(),
+            );
+            assert_eq!(
+                result.unwrap(),
+                "5 5\n1 2 N id=2\nLMLMLMLMM\n3 3 E id=1\nMMRMMRMRRM\n"
+            );
+
+            // Rovers written in any order with the same IDs are the same mission
+            let reordered = canonical(
+                text("5 5\n1 2 N id=2\nLMLMLMLMM\n3 3 E id=1\nMMRMMRMRRM"),
+                InstructionLocale::English,
+                &Defines::new(),
+            );
+            assert_eq!(
+                reordered.unwrap(),
+                "5 5\n1 2 N id=2\nLMLMLMLMM\n3 3 E id=1\nMMRMMRMRRM\n"
+            );
+        }
+    }
+}
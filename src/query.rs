@@ -0,0 +1,195 @@
+use crate::{
+    enums::{RoverErr, SchemaVersion},
+    parse::trace_event,
+    position::Position,
+    trace::TraceEvent,
+};
+
+/// Read the events back out of a saved trace file, blank lines are skipped
+///
+/// Traces of any schema version are accepted, the original layout has no `schema_version` line.
+pub fn parse_trace(file: &str) -> Result<Vec<TraceEvent>, RoverErr> {
+    let mut lines = file
+        .lines()
+        .map(|line| line.trim())
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+
+    if let Some((index, line)) = lines.next_if(|(_, line)| line.starts_with("schema_version")) {
+        line.strip_prefix("schema_version")
+            .and_then(|version| version.trim().parse().ok())
+            .and_then(SchemaVersion::from_number)
+            .ok_or(RoverErr::TraceParse(index))?;
+    }
+
+    lines
+        .map(|(index, line)| match trace_event(line) {
+            Ok(("", event)) => Ok(event),
+            _ => Err(RoverErr::TraceParse(index)),
+        })
+        .collect()
+}
+
+/// A question about a saved trace, each part that is given narrows the answer
+#[derive(Debug, Default)]
+pub struct Query {
+    /// Only consider this rover
+    pub rover: Option<usize>,
+    /// Where each rover was after this tick, i.e. their latest event at or before it
+    pub tick: Option<usize>,
+    /// Only consider events that happened on this cell
+    pub cell: Option<Position>,
+}
+
+impl Query {
+    /// Find the events answering the query, in trace order
+    pub fn run<'a>(&self, events: &'a [TraceEvent]) -> Vec<&'a TraceEvent> {
+        let events = events
+            .iter()
+            .filter(|event| self.rover.is_none_or(|rover| event.rover == rover));
+
+        let events: Vec<&TraceEvent> = match self.tick {
+            Some(tick) => {
+                let mut latest: Vec<&TraceEvent> = Vec::new();
+                for event in events.filter(|event| event.tick <= tick) {
+                    match latest.iter_mut().find(|latest| latest.rover == event.rover) {
+                        Some(latest) if latest.tick <= event.tick => *latest = event,
+                        Some(_) => (),
+                        None => latest.push(event),
+                    }
+                }
+                latest
+            }
+            None => events.collect(),
+        };
+
+        events
+            .into_iter()
+            .filter(|event| self.cell.is_none_or(|cell| event.pose.position == cell))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod query_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod parse_trace {
+        use super::parse_trace;
+
+        #[test]
+        fn valid_input() {
+            let result = parse_trace("0 1 move 1 2 N\n\n1 1 left 1 2 W\n");
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap().len(), 2);
+        }
+
+        #[test]
+        fn schema_version() {
+            let result = parse_trace("schema_version 2\n0 1 move 1 2 N");
+            assert_eq!(result.unwrap().len(), 1);
+
+            let result = parse_trace("schema_version 57\n0 1 move 1 2 N");
+            assert!(matches!(result, Err(crate::enums::RoverErr::TraceParse(0))));
+        }
+
+        #[test]
+        fn invalid_line() {
+            let result = parse_trace("0 1 move 1 2 N\n1 1 left");
+            assert!(matches!(result, Err(crate::enums::RoverErr::TraceParse(1))));
+        }
+    }
+
+    #[cfg(test)]
+    mod run {
+        use super::{parse_trace, Query};
+        use crate::{
+            budget::Budget,
+            diagnostics::Diagnostics,
+            enums::{InstructionLocale, SchemaVersion},
+            position::Position,
+            rover::RoverControlSatellite,
+            timeline::Timeline,
+            trace::{Trace, TraceFilter},
+            world::World,
+        };
+
+        const TRACE: &str = "0 1 move 1 3 N\n\
+                             1 1 right 1 3 E\n\
+                             2 1 move 2 3 E\n\
+                             0 2 move 2 3 N\n\
+                             1 2 move 2 4 N";
+
+        #[test]
+        fn rover_at_tick() {
+            let events = parse_trace(TRACE).unwrap();
+            let query = Query {
+                rover: Some(1),
+                tick: Some(1),
+                cell: None,
+            };
+            let result = query.run(&events);
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].to_string(), "1 1 right 1 3 E");
+        }
+
+        #[test]
+        fn rover_at_tick_past_the_end() {
+            let events = parse_trace(TRACE).unwrap();
+            let query = Query {
+                rover: Some(2),
+                tick: Some(57),
+                cell: None,
+            };
+            let result = query.run(&events);
+            assert_eq!(result[0].to_string(), "1 2 move 2 4 N");
+        }
+
+        #[test]
+        fn visits_to_cell() {
+            let events = parse_trace(TRACE).unwrap();
+            let query = Query {
+                cell: Some(Position::new(2, 3)),
+                ..Query::default()
+            };
+            let rovers = query
+                .run(&events)
+                .iter()
+                .map(|event| event.rover)
+                .collect::<Vec<usize>>();
+            assert_eq!(rovers, vec![1, 2]);
+        }
+
+        #[test]
+        fn agrees_with_timeline() {
+            let mut diagnostics = Diagnostics::default();
+            let mission = RoverControlSatellite::parse_incoming_message(
+                "5 5\n1 2 N\nMMR".to_string(),
+                InstructionLocale::default(),
+                &mut diagnostics,
+            )
+            .unwrap();
+            let mut trace = Trace::new(TraceFilter::default());
+            RoverControlSatellite::execute_mission(
+                &mission,
+                &mut World::default(),
+                &mut Budget::default(),
+                &mut diagnostics,
+                &mut trace,
+            )
+            .unwrap();
+            let events = parse_trace(&trace.to_file(SchemaVersion::V2)).unwrap();
+
+            for (tick, rovers) in Timeline::new(&mission, &trace).ticks.iter().enumerate() {
+                let query = Query {
+                    rover: Some(1),
+                    tick: Some(tick),
+                    cell: None,
+                };
+                assert_eq!(query.run(&events)[0].pose, rovers[0].pose, "tick {tick}");
+            }
+        }
+    }
+}
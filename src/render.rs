@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use crate::{
+    enums::{Direction, RoverErr},
+    json,
+    position::{Position, MAX_CELLS},
+    rover::Rover,
+    timeline::Timeline,
+};
+
+/// Clear the terminal and move the cursor back to the top left
+pub const CLEAR_SCREEN: &str = "\u{1b}[2J\u{1b}[H";
+
+/// Draw the plateau as an ASCII grid with the highest row first
+///
+/// Each rover is drawn as an arrow pointing the way it is facing, cells holding more than one
+/// rover are drawn as `*`. Rovers outside of the plateau are not drawn, and plateaus with more
+/// than [`MAX_CELLS`] cells are refused.
+pub fn render_frame(bounderies: Position, rovers: &[Rover]) -> Result<String, RoverErr> {
+    if bounderies.cells() > MAX_CELLS {
+        return Err(RoverErr::OversizedPlateau(bounderies, MAX_CELLS));
+    }
+
+    let mut occupied = HashMap::new();
+    for rover in rovers {
+        occupied
+            .entry(rover.pose.position)
+            .and_modify(|glyph| *glyph = '*')
+            .or_insert_with(|| glyph(rover.pose.facing));
+    }
+
+    Ok((0..=bounderies.y)
+        .rev()
+        .map(|y| {
+            (0..=bounderies.x)
+                .map(|x| occupied.get(&Position::new(x, y)).copied().unwrap_or('.'))
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn glyph(facing: Direction) -> char {
+    match facing {
+        Direction::North => '^',
+        Direction::East => '>',
+        Direction::South => 'v',
+        Direction::West => '<',
+    }
+}
+
+/// Draw a frame for every tick of the timeline, each headed by its tick
+pub fn render_frames(bounderies: Position, timeline: &Timeline) -> Result<Vec<String>, RoverErr> {
+    timeline
+        .ticks
+        .iter()
+        .enumerate()
+        .map(|(tick, rovers)| {
+            Ok(format!(
+                "Tick {}\n{}",
+                tick,
+                render_frame(bounderies, rovers)?
+            ))
+        })
+        .collect()
+}
+
+/// Record the frames as an asciinema v2 cast, showing each frame for `delay` seconds
+pub fn to_asciinema(frames: &[String], delay: f64) -> String {
+    let width = frames
+        .iter()
+        .flat_map(|frame| frame.lines())
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or_default();
+    let height = frames
+        .iter()
+        .map(|frame| frame.lines().count())
+        .max()
+        .unwrap_or_default();
+
+    let mut cast = format!(r#"{{"version": 2, "width": {width}, "height": {height}}}"#);
+    for (index, frame) in frames.iter().enumerate() {
+        // Terminals need a carriage return to go back to the start of the line
+        let output = format!("{CLEAR_SCREEN}{}", frame.replace('\n', "\r\n"));
+        cast.push_str(&format!(
+            "\n[{:.3}, \"o\", \"{}\"]",
+            index as f64 * delay,
+            json::escape(&output)
+        ));
+    }
+    cast
+}
+
+#[cfg(test)]
+mod render_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod render_frame {
+        use super::render_frame;
+        use crate::{
+            enums::{Direction, RoverErr},
+            position::{Pose, Position, MAX_CELLS},
+            rover::Rover,
+        };
+
+        #[test]
+        fn valid_input() {
+            let result = render_frame(
+                Position::new(2, 1),
+                &[
+                    Rover::new(1, Pose::new(Position::new(0, 1), Direction::East)),
+                    Rover::new(2, Pose::new(Position::new(2, 0), Direction::South)),
+                    Rover::new(3, Pose::new(Position::new(2, 0), Direction::North)),
+                    Rover::new(4, Pose::new(Position::new(5, 5), Direction::North)),
+                ],
+            );
+            assert_eq!(result.unwrap(), ">..\n..*");
+        }
+
+        #[test]
+        fn oversized_plateau() {
+            let result = render_frame(Position::new(100_000, 100_000), &[]);
+            assert!(matches!(
+                result,
+                Err(RoverErr::OversizedPlateau(_, MAX_CELLS))
+            ));
+        }
+    }
+
+    #[cfg(test)]
+    mod to_asciinema {
+        use super::{render_frames, to_asciinema};
+        use crate::{
+            budget::Budget,
+            diagnostics::Diagnostics,
+            enums::{Direction, Instruction},
+            position::{Pose, Position},
+            rover::{Attributes, Mission, RoverControlSatellite},
+            timeline::Timeline,
+            trace::{Trace, TraceFilter},
+            world::World,
+        };
+
+        /// Trace every event of a mission, up to where it failed if it did
+        fn traced(mission: &Mission) -> Trace {
+            let mut trace = Trace::new(TraceFilter::default());
+            let _ = RoverControlSatellite::execute_mission(
+                mission,
+                &mut World::default(),
+                &mut Budget::default(),
+                &mut Diagnostics::default(),
+                &mut trace,
+            );
+            trace
+        }
+
+        #[test]
+        fn valid_input() {
+            let mission = Mission {
+                bounderies: Position::new(1, 0),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::East),
+                    vec![Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            let frames = render_frames(
+                mission.bounderies,
+                &Timeline::new(&mission, &traced(&mission)),
+            )
+            .unwrap();
+            let cast = to_asciinema(&frames, 0.5);
+            assert_eq!(
+                cast,
+                "{\"version\": 2, \"width\": 6, \"height\": 2}\n\
+                 [0.000, \"o\", \"\\u001b[2J\\u001b[HTick 0\\r\\n>.\"]\n\
+                 [0.500, \"o\", \"\\u001b[2J\\u001b[HTick 1\\r\\n.>\"]"
+            );
+        }
+    }
+}
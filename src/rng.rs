@@ -0,0 +1,224 @@
+use std::fmt::Debug;
+
+use crate::enums::Generator;
+
+/// Where the random numbers of a mission come from
+///
+/// A source can be injected into a world to replay a fixed sequence in tests, or to swap in
+/// another generator. Every source must be seedable, so that a run can be repeated exactly.
+pub trait RngSource: Debug + Send + Sync {
+    fn next_u64(&mut self) -> u64;
+
+    /// The seed the source was started from, recorded so the run can be repeated
+    fn seed(&self) -> u64;
+
+    /// The name of the source, sources with different names never produce the same numbers
+    fn name(&self) -> &'static str;
+
+    /// Everything the numbers still to come depend on, so two sources can be compared
+    fn state(&self) -> Vec<u64>;
+
+    fn boxed_clone(&self) -> Box<dyn RngSource>;
+}
+
+/// The SplitMix64 generator, small and fast with good enough statistics for simulations
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SplitMix64 {
+    seed: u64,
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { seed, state: seed }
+    }
+}
+
+impl RngSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn name(&self) -> &'static str {
+        "splitmix64"
+    }
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.seed, self.state]
+    }
+
+    fn boxed_clone(&self) -> Box<dyn RngSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// The xorshift64* generator, a few shifts per number
+#[derive(Debug, Clone, PartialEq)]
+pub struct XorShift64Star {
+    seed: u64,
+    state: u64,
+}
+
+impl XorShift64Star {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero, so the seed is scrambled into one that is not
+        let state = SplitMix64::new(seed).next_u64().max(1);
+        XorShift64Star { seed, state }
+    }
+}
+
+impl RngSource for XorShift64Star {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn name(&self) -> &'static str {
+        "xorshift64star"
+    }
+
+    fn state(&self) -> Vec<u64> {
+        vec![self.seed, self.state]
+    }
+
+    fn boxed_clone(&self) -> Box<dyn RngSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// The random number generator of a world, drawing from a source of random numbers
+///
+/// Anything random in a mission must draw from the world's generator rather than the system's.
+#[derive(Debug)]
+pub struct Rng {
+    source: Box<dyn RngSource>,
+}
+
+impl Rng {
+    /// A generator drawing from the default source
+    pub fn new(seed: u64) -> Self {
+        Rng::with_generator(Generator::default(), seed)
+    }
+
+    pub fn with_generator(generator: Generator, seed: u64) -> Self {
+        match generator {
+            Generator::Splitmix64 => Rng::from_source(SplitMix64::new(seed)),
+            Generator::Xorshift64Star => Rng::from_source(XorShift64Star::new(seed)),
+        }
+    }
+
+    pub fn from_source(source: impl RngSource + 'static) -> Self {
+        Rng {
+            source: Box::new(source),
+        }
+    }
+
+    /// The seed the generator was started from
+    pub fn seed(&self) -> u64 {
+        self.source.seed()
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.source.next_u64()
+    }
+
+    /// A number from `0` up to, but not including, `bound`
+    pub fn below(&mut self, bound: u64) -> u64 {
+        match bound {
+            0 => 0,
+            bound => self.next_u64() % bound,
+        }
+    }
+
+    /// Whether an event with the given probability happens, nothing is drawn if it never can
+    pub fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && (self.next_u64() as f64) < probability * u64::MAX as f64
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0)
+    }
+}
+
+impl Clone for Rng {
+    fn clone(&self) -> Self {
+        Rng {
+            source: self.source.boxed_clone(),
+        }
+    }
+}
+
+/// Generators are equal if they draw from the same kind of source in the same state
+impl PartialEq for Rng {
+    fn eq(&self, other: &Self) -> bool {
+        self.source.name() == other.source.name() && self.source.state() == other.source.state()
+    }
+}
+
+#[cfg(test)]
+mod rng_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod next_u64 {
+        use super::Rng;
+        use crate::enums::Generator;
+
+        #[test]
+        fn same_seed() {
+            for generator in [Generator::Splitmix64, Generator::Xorshift64Star] {
+                let mut first = Rng::with_generator(generator, 42);
+                let mut second = Rng::with_generator(generator, 42);
+                for _ in 0..16 {
+                    assert_eq!(first.next_u64(), second.next_u64());
+                }
+            }
+        }
+
+        #[test]
+        fn known_sequence() {
+            let mut rng = Rng::new(0);
+            assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+            assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        }
+
+        #[test]
+        fn generators_differ() {
+            let mut splitmix = Rng::with_generator(Generator::Splitmix64, 0);
+            let mut xorshift = Rng::with_generator(Generator::Xorshift64Star, 0);
+            assert_ne!(splitmix.next_u64(), xorshift.next_u64());
+            assert_eq!(xorshift.seed(), 0);
+            assert_ne!(splitmix, xorshift);
+        }
+    }
+
+    #[cfg(test)]
+    mod chance {
+        use super::Rng;
+
+        #[test]
+        fn never_and_always() {
+            let mut rng = Rng::new(7);
+            assert!(!rng.chance(0.0));
+            assert_eq!(rng, Rng::new(7));
+            assert!((0..64).all(|_| rng.chance(1.0)));
+            assert_ne!(rng, Rng::new(7));
+        }
+    }
+}
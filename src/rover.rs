@@ -1,115 +1,843 @@
-use enum_iterator::{next_cycle, previous_cycle};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    thread,
+};
 
 use crate::{
-    enums::{Coordinate, Direction, Instruction, ParsingErr, RoverErr},
-    parse::{coordinate, instruction_stream, starting_position},
+    budget::Budget,
+    diagnostics::{no_op_turns, Diagnostics, Warning},
+    enums::{Autonomy, Direction, Instruction, InstructionLocale, ParsingErr, Recovery, RoverErr},
+    json,
+    parse::{coordinate, localized_instruction_stream, resumed_stream, rover_header},
+    position::{Pose, Position},
+    terrain::{self, CellEvent},
+    trace::Trace,
+    world::World,
 };
 
-#[derive(Debug, PartialEq)]
+/// Missions with fewer rovers than this are parsed on a single thread
+const PARALLEL_PARSE_THRESHOLD: usize = 1024;
+
+/// A line of the instructions file alongside its index
+pub type Line<'a> = (usize, &'a str);
+
+/// The parsed starting position, instructions and attributes of a single rover
+pub type RoverEntry = (Pose, Vec<Instruction>, Attributes);
+
+/// A rover's ID, the further instructions given to it, and the index of their line
+pub type ResumedStream = (usize, Vec<Instruction>, usize);
+
+/// The optional settings written after a rover's starting position, e.g. `priority=3`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Attributes {
+    /// The rover's ID, when it is not numbered by the order the rovers were written in
+    pub id: Option<usize>,
+    /// What the rover is called, letters, digits, `-` and `_`
+    pub name: Option<String>,
+    /// Rovers with a higher priority are executed first
+    pub priority: usize,
+    /// How many cells the rover can move before it needs servicing
+    pub maintenance: Option<usize>,
+}
+
+impl Display for Attributes {
+    /// Each attribute that is set, preceded by a space
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(id) = self.id {
+            write!(f, " id={}", id)?;
+        }
+        if let Some(name) = &self.name {
+            write!(f, " name={}", name)?;
+        }
+        if self.priority > 0 {
+            write!(f, " priority={}", self.priority)?;
+        }
+        if let Some(maintenance) = self.maintenance {
+            write!(f, " maintenance={}", maintenance)?;
+        }
+        Ok(())
+    }
+}
+
+/// A parsed instructions file, prior to execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mission {
+    pub bounderies: Position,
+    pub rovers: Vec<RoverEntry>,
+}
+
+impl Mission {
+    /// A rover at its starting position, ready to execute its instructions
+    pub fn launch(&self, index: usize) -> Rover {
+        let (pose, _, attributes) = &self.rovers[index];
+        // The ID of the rover should start from one
+        let mut rover = Rover::new(index + 1, *pose);
+        rover.maintenance = attributes.maintenance;
+        rover.name = attributes.name.clone();
+        rover
+    }
+
+    /// The indexes of the rovers, highest priority first, ties are kept in file order
+    pub fn execution_order(&self) -> Vec<usize> {
+        let mut order = (0..self.rovers.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|index| std::cmp::Reverse(self.rovers[*index].2.priority));
+        order
+    }
+
+    /// Put the rovers in the order of their `id=` attributes, if they were given them
+    ///
+    /// Either every rover is given an ID or none is, and the IDs must number the rovers from
+    /// one, so a rover's ID is still one more than its index.
+    pub fn number(&mut self) -> Result<(), RoverErr> {
+        let mut ids = self
+            .rovers
+            .iter()
+            .filter_map(|(_, _, attributes)| attributes.id)
+            .collect::<Vec<usize>>();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        ids.sort_unstable();
+        if ids.len() != self.rovers.len() || !ids.into_iter().eq(1..=self.rovers.len()) {
+            return Err(RoverErr::RoverIds);
+        }
+        self.rovers.sort_by_key(|(_, _, attributes)| attributes.id);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Rover {
     pub id: usize,
-    pub x: isize,
-    pub y: isize,
-    pub facing: Direction,
+    /// The name it was given with `name=`, if any
+    pub name: Option<String>,
+    pub pose: Pose,
+    /// Whether the rover was halted by an `X` instruction, it will not step again
+    pub halted: bool,
+    /// Whether the rover fell off the plateau or into a crater, it stays where it was last seen
+    pub lost: bool,
+    /// How many cells the rover has moved
+    pub odometer: usize,
+    /// How many times the rover has turned
+    pub turns: usize,
+    /// How many cells the rover can move between services, if it needs servicing at all
+    pub maintenance: Option<usize>,
+    /// How many cells the rover has moved since it was last serviced, if it needs servicing
+    pub since_service: usize,
+    /// Whether the rover went past its maintenance threshold without being serviced
+    pub degraded: bool,
 }
 
 impl Rover {
-    pub fn new(id: usize, coordinates: Coordinate, facing: Direction) -> Self {
+    pub fn new(id: usize, pose: Pose) -> Self {
         Rover {
             id,
-            x: coordinates.0,
-            y: coordinates.1,
-            facing,
+            name: None,
+            pose,
+            halted: false,
+            lost: false,
+            odometer: 0,
+            turns: 0,
+            maintenance: None,
+            since_service: 0,
+            degraded: false,
         }
     }
 
-    /// Execute a given set of instructions
+    /// Execute a given set of instructions, spending them from the budget
+    ///
+    /// If the world loses rovers, a rover that falls off the plateau or into a crater is marked as
+    /// lost at its last position instead of failing. It leaves a scent so later rovers ignore the
+    /// same move from the same cell.
     pub fn execute_instructions(
+        self,
+        instructions: &[Instruction],
+        world: &mut World,
+        budget: &mut Budget,
+        diagnostics: &mut Diagnostics,
+        trace: &mut Trace,
+    ) -> Result<Self, RoverErr> {
+        self.execute_instructions_from(instructions, 0, world, budget, diagnostics, trace)
+    }
+
+    /// Carry on executing a set of instructions from the instruction at index `start`, as if
+    /// those before it had already been executed
+    pub fn execute_instructions_from(
         mut self,
-        instructions: Vec<Instruction>,
-        boundery: Option<Coordinate>,
+        instructions: &[Instruction],
+        start: usize,
+        world: &mut World,
+        budget: &mut Budget,
+        diagnostics: &mut Diagnostics,
+        trace: &mut Trace,
     ) -> Result<Self, RoverErr> {
-        for (i, instruction) in instructions.iter().enumerate() {
-            match instruction {
-                Instruction::Left => self.facing = previous_cycle(&self.facing).unwrap_or_default(),
-                Instruction::Right => self.facing = next_cycle(&self.facing).unwrap_or_default(),
-                Instruction::Move => match self.facing {
-                    Direction::North => self.y += 1,
-                    Direction::East => self.x += 1,
-                    Direction::South => self.y -= 1,
-                    Direction::West => self.x -= 1,
-                },
+        let boundery = world.boundery();
+        let margin = world.safety_margin;
+        // Moves can only be folded together when nothing needs to see each step
+        let foldable = world.model.is_deterministic()
+            && !trace.is_recording(self.id, Some(&Instruction::Move));
+
+        if start == 0 {
+            trace.start(&self);
+        }
+
+        let mut next = start;
+        // Where the run of moves being executed ends, so each run is only counted once
+        let mut run_end = start;
+        while let Some(instruction) = instructions.get(next) {
+            let i = next;
+            next += 1;
+
+            if foldable && *instruction == Instruction::Move {
+                if run_end <= i {
+                    run_end = i + instructions[i..]
+                        .iter()
+                        .take_while(|instruction| **instruction == Instruction::Move)
+                        .count();
+                }
+                let moves = (run_end - i)
+                    .min(self.uneventful_moves(boundery, margin))
+                    .min(self.moves_until_maintenance())
+                    .min(budget.remaining_instructions());
+                // Craters and tagged cells are stepped onto one at a time
+                let moves = world.plain_cells_ahead(self.pose, moves);
+                if moves > 0 {
+                    budget
+                        .spend(moves)
+                        .map_err(|limit| RoverErr::BudgetExceeded(self.clone(), i, limit))?;
+                    self.advance(moves);
+                    next = i + moves;
+                    continue;
+                }
             }
 
+            budget
+                .spend(1)
+                .map_err(|limit| RoverErr::BudgetExceeded(self.clone(), i, limit))?;
+
+            // A lost instruction never reaches the rover, but was still sent
+            if world.model.link_lost(&mut world.rng) {
+                continue;
+            }
+
+            if *instruction == Instruction::Move
+                && world.autonomy == Autonomy::Safe
+                && self.is_fatal_move(boundery, world)
+            {
+                let recovery = match world.recovery {
+                    Recovery::TurnRight => {
+                        self.step(&Instruction::Right);
+                        trace.record(i, &self, &Instruction::Right, &[]);
+                        match self.is_fatal_move(boundery, world) {
+                            true => Recovery::Stop,
+                            false => Recovery::TurnRight,
+                        }
+                    }
+                    Recovery::Stop => Recovery::Stop,
+                };
+                diagnostics.warn(Warning::Intervention {
+                    rover: self.id,
+                    position: self.pose.position,
+                    instruction: i,
+                    recovery,
+                });
+                if recovery == Recovery::Stop {
+                    self.halted = true;
+                    trace.record(i, &self, &Instruction::Halt, &[]);
+                    break;
+                }
+            }
+
+            let was_near_boundery = self.is_near_boundery(boundery, margin);
+            let previous = self.pose.position;
+            // Where the rover last stood safely, and what it had counted getting there
+            let mut safe = (self.pose.position, self.odometer, self.since_service);
+            self.step(instruction);
+            let moved = *instruction == Instruction::Move && !self.halted;
+            // A slip carries the rover one cell further, so each cell it crosses is checked
+            if moved && world.model.slips(&mut world.rng) && !self.has_fallen(boundery, world) {
+                safe = (self.pose.position, self.odometer, self.since_service);
+                self.advance(1);
+            }
+
+            if self.has_fallen(boundery, world) && world.lost_rovers {
+                // The move that would have doomed the rover is never made, so it is not counted
+                (self.pose.position, self.odometer, self.since_service) = safe;
+                match world.has_scent(self.pose) {
+                    true if self.pose.position == previous => continue,
+                    // Only the slip is ignored, the move before it was made
+                    true => (),
+                    false => {
+                        world.leave_scent(self.pose);
+                        self.lost = true;
+                        trace.record(i, &self, instruction, &[]);
+                        return Ok(self);
+                    }
+                }
+            }
+
+            if *instruction == Instruction::Service {
+                match world.tags_at(self.pose.position).contains(&terrain::BASE) {
+                    true => self.since_service = 0,
+                    false => diagnostics.warn(Warning::ServiceOffBase {
+                        rover: self.id,
+                        position: self.pose.position,
+                        instruction: i,
+                    }),
+                }
+            }
+            if let Some(maintenance) = self.maintenance.filter(|_| !self.degraded) {
+                if self.since_service > maintenance {
+                    self.degraded = true;
+                    diagnostics.warn(Warning::Degraded {
+                        rover: self.id,
+                        instruction: i,
+                        maintenance,
+                    });
+                }
+            }
+
+            // Hazards only strike rovers that are still on solid ground
+            let struck =
+                moved && !self.has_fallen(boundery, world) && world.model.strikes(&mut world.rng);
+            self.lost |= struck && world.lost_rovers;
+
+            // Only moving onto a cell enters it, turning on a tagged cell does not
+            let entered = match instruction {
+                Instruction::Move => world.tags_at(self.pose.position),
+                _ => Vec::new(),
+            };
+            trace.record(i, &self, instruction, &entered);
+            let events = entered
+                .into_iter()
+                .map(|tag| CellEvent {
+                    rover: self.id,
+                    instruction: i,
+                    position: self.pose.position,
+                    tag: tag.to_string(),
+                })
+                .collect::<Vec<CellEvent>>();
+            world.events.extend(events);
+
             if self.has_crossed_boundery(boundery) {
                 return Err(RoverErr::Boundery(self, i));
             }
+            if world.is_crater(self.pose.position) {
+                return Err(RoverErr::Crater(self, i));
+            }
+            if struck {
+                match world.lost_rovers {
+                    true => return Ok(self),
+                    false => return Err(RoverErr::Hazard(self, i)),
+                }
+            }
+
+            if !was_near_boundery && self.is_near_boundery(boundery, margin) {
+                diagnostics.warn(Warning::NearBoundery {
+                    rover: self.id,
+                    position: self.pose.position,
+                    instruction: i,
+                });
+            }
+
+            if self.halted {
+                let ignored = instructions.len() - i - 1;
+                if ignored > 0 {
+                    diagnostics.warn(Warning::Halted {
+                        rover: self.id,
+                        instruction: i,
+                        ignored,
+                    });
+                }
+                break;
+            }
+        }
+
+        if self.is_on_boundery(boundery) {
+            diagnostics.warn(Warning::EndedOnBoundery {
+                rover: self.id,
+                position: self.pose.position,
+            });
         }
 
         Ok(self)
     }
 
-    pub fn has_crossed_boundery(&self, boundery: Option<Coordinate>) -> bool {
+    /// Apply a single instruction, without any boundery checks
+    ///
+    /// Once the rover has been halted further instructions have no effect.
+    pub fn step(&mut self, instruction: &Instruction) {
+        if self.halted {
+            return;
+        }
+
+        match instruction {
+            Instruction::Left => {
+                self.pose = self.pose.turn_left();
+                self.turns += 1;
+            }
+            Instruction::Right => {
+                self.pose = self.pose.turn_right();
+                self.turns += 1;
+            }
+            Instruction::Move => self.advance(1),
+            Instruction::Halt => self.halted = true,
+            // Servicing depends on the cell, so it is handled during execution
+            Instruction::Service => (),
+        }
+    }
+
+    /// Move forwards several cells at once, without any boundery checks
+    pub fn advance(&mut self, moves: usize) {
+        if self.halted {
+            return;
+        }
+
+        self.odometer += moves;
+        if self.maintenance.is_some() {
+            self.since_service += moves;
+        }
+
+        self.pose = self.pose.forwards(moves as isize);
+    }
+
+    /// Whether moving forwards would take the rover off the plateau or into a crater
+    pub fn is_fatal_move(&self, boundery: Option<Position>, world: &World) -> bool {
+        let mut moved = self.clone();
+        moved.advance(1);
+        moved.has_fallen(boundery, world)
+    }
+
+    /// Whether the rover has gone off the plateau or into a crater
+    pub fn has_fallen(&self, boundery: Option<Position>, world: &World) -> bool {
+        self.has_crossed_boundery(boundery) || world.is_crater(self.pose.position)
+    }
+
+    /// How many cells the rover can move before going past its maintenance threshold
+    pub fn moves_until_maintenance(&self) -> usize {
+        match self.maintenance {
+            Some(maintenance) if !self.degraded => maintenance.saturating_sub(self.since_service),
+            _ => usize::MAX,
+        }
+    }
+
+    /// How many cells the rover can move forwards without leaving the plateau or entering the
+    /// safety margin of the edge it is facing
+    pub fn uneventful_moves(&self, boundery: Option<Position>, margin: usize) -> usize {
         match boundery {
-            Some((x, y)) => self.x < 0 || self.y < 0 || self.x > x || self.y > y,
+            Some(_) if self.has_crossed_boundery(boundery) => 0,
+            Some(bounderies) => {
+                let Position { x, y } = self.pose.position;
+                let to_edge = match self.pose.facing {
+                    Direction::North => bounderies.y - y,
+                    Direction::East => bounderies.x - x,
+                    Direction::South => y,
+                    Direction::West => x,
+                };
+                let margin = isize::try_from(margin).unwrap_or(isize::MAX);
+                to_edge.saturating_sub(margin).max(0) as usize
+            }
+            None => usize::MAX,
+        }
+    }
+
+    pub fn has_crossed_boundery(&self, boundery: Option<Position>) -> bool {
+        match boundery {
+            Some(bounderies) => !self.pose.position.within_bounderies(bounderies),
             None => false,
         }
     }
+
+    pub fn is_on_boundery(&self, boundery: Option<Position>) -> bool {
+        match boundery {
+            Some(bounderies) => {
+                let Position { x, y } = self.pose.position;
+                x == 0 || y == 0 || x == bounderies.x || y == bounderies.y
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the rover is on one of the outermost `margin` rows or columns of the plateau
+    pub fn is_near_boundery(&self, boundery: Option<Position>, margin: usize) -> bool {
+        match boundery {
+            Some(bounderies) => {
+                let Position { x, y } = self.pose.position;
+                let distance = x.min(y).min(bounderies.x - x).min(bounderies.y - y);
+                usize::try_from(distance).map_or(true, |distance| distance < margin)
+            }
+            None => false,
+        }
+    }
+}
+
+impl Rover {
+    /// Whether the rover can still be given instructions, as written in reports
+    pub fn status(&self) -> &'static str {
+        match (self.halted, self.lost, self.degraded) {
+            (_, true, _) => "lost",
+            (true, _, _) => "halted",
+            (_, _, true) => "degraded",
+            _ => "active",
+        }
+    }
+
+    /// Serialise the rover as a JSON object, its name is only included if it has one and the
+    /// maintenance counters if it needs servicing
+    ///
+    /// The status is the most serious of the rover's flags, which are each written out as well.
+    pub fn to_json(&self) -> String {
+        let name = self
+            .name
+            .as_ref()
+            .map(|name| format!(r#", "name": "{}""#, json::escape(name)))
+            .unwrap_or_default();
+        let maintenance = self
+            .maintenance
+            .map(|maintenance| {
+                format!(
+                    r#", "maintenance": {maintenance}, "since_service": {}"#,
+                    self.since_service
+                )
+            })
+            .unwrap_or_default();
+        format!(
+            r#"{{"id": {}{name}, "x": {}, "y": {}, "facing": "{}", "status": "{}", "halted": {}, "lost": {}, "degraded": {}, "odometer": {}, "turns": {}{maintenance}}}"#,
+            self.id,
+            self.pose.position.x,
+            self.pose.position.y,
+            self.pose.facing,
+            self.status(),
+            self.halted,
+            self.lost,
+            self.degraded,
+            self.odometer,
+            self.turns
+        )
+    }
 }
 
-impl ToString for Rover {
-    fn to_string(&self) -> String {
-        format!("{} {} {}", self.x, self.y, self.facing)
+impl Display for Rover {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pose)
     }
 }
 
 pub struct RoverControlSatellite;
 
 impl RoverControlSatellite {
+    /// Format the final positions of the rovers, one per line
+    ///
+    /// Rovers that were halted, lost or degraded are marked as such.
+    pub fn format_report(rovers: &[Rover]) -> String {
+        rovers
+            .iter()
+            .map(|rover| match rover.status() {
+                "lost" => format!("{rover} LOST"),
+                "active" => rover.to_string(),
+                status => format!("{rover} {status}"),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Check that no two rovers finished on the same cell
+    pub fn check_final_positions(rovers: &[Rover]) -> Result<(), RoverErr> {
+        let mut occupied = HashMap::new();
+        for rover in rovers {
+            if let Some(other) = occupied.insert(rover.pose.position, rover.id) {
+                return Err(RoverErr::SharedFinalPosition(
+                    other,
+                    rover.id,
+                    rover.pose.position,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse and execute a message on a plateau without any craters, or any budget
     pub fn parse_and_execute_incoming_message(
         message: String,
         unbounded: bool,
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+        trace: &mut Trace,
     ) -> Result<Vec<Rover>, RoverErr> {
-        let mut lines = message.lines().map(|line| line.trim()).enumerate();
-        let bounderies = Self::parse_bounderies(lines.next())?;
+        let mission = Self::parse_incoming_message(message, locale, diagnostics)?;
+        let mut world = World::default();
+        world.unbounded = unbounded;
+        Self::execute_mission(
+            &mission,
+            &mut world,
+            &mut Budget::default(),
+            diagnostics,
+            trace,
+        )
+    }
 
-        let mut instructions_and_positions = Vec::new();
-        while let Some(entry) = Self::parse_instructions_and_position((lines.next(), lines.next()))?
-        {
-            instructions_and_positions.push(entry)
+    /// Execute each rover's instructions in turn, in order of priority
+    ///
+    /// The rovers are returned in order of their IDs, and are kept in the world. If the mission
+    /// fails, the world keeps the rovers that finished before it did.
+    pub fn execute_mission(
+        mission: &Mission,
+        world: &mut World,
+        budget: &mut Budget,
+        diagnostics: &mut Diagnostics,
+        trace: &mut Trace,
+    ) -> Result<Vec<Rover>, RoverErr> {
+        world.bounderies = mission.bounderies;
+        world.rovers = Vec::new();
+
+        for index in mission.execution_order() {
+            let rover = mission.launch(index).execute_instructions(
+                &mission.rovers[index].1,
+                world,
+                budget,
+                diagnostics,
+                trace,
+            )?;
+            world.rovers.push(rover);
+        }
+        world.rovers.sort_by_key(|rover| rover.id);
+
+        Ok(world.rovers.clone())
+    }
+
+    /// Execute further instructions for the rovers of a saved world, in order of their IDs
+    ///
+    /// Rovers without further instructions stay where they are. Halted rovers start a new
+    /// mission, but lost rovers can not be given instructions. If the mission fails, the world
+    /// keeps every rover but the one that failed.
+    pub fn resume_mission(
+        streams: Vec<ResumedStream>,
+        world: &mut World,
+        budget: &mut Budget,
+        diagnostics: &mut Diagnostics,
+        trace: &mut Trace,
+    ) -> Result<Vec<Rover>, RoverErr> {
+        for (id, _, index) in &streams {
+            match world.rovers.iter().find(|rover| rover.id == *id) {
+                None => return Err(RoverErr::UnknownRover(*id, *index)),
+                Some(rover) if rover.lost => return Err(RoverErr::LostRover(*id, *index)),
+                Some(_) => (),
+            }
         }
 
-        instructions_and_positions
-            .into_iter()
+        let mut executed = Vec::new();
+        let mut rovers = std::mem::take(&mut world.rovers).into_iter();
+        while let Some(mut rover) = rovers.next() {
+            let instructions = streams
+                .iter()
+                .filter(|(id, _, _)| *id == rover.id)
+                .flat_map(|(_, instructions, _)| instructions.iter().copied())
+                .collect::<Vec<Instruction>>();
+            if instructions.is_empty() {
+                executed.push(rover);
+                continue;
+            }
+
+            rover.halted = false;
+            match rover.execute_instructions(&instructions, world, budget, diagnostics, trace) {
+                Ok(rover) => executed.push(rover),
+                Err(err) => {
+                    world.rovers = executed.into_iter().chain(rovers).collect();
+                    return Err(err);
+                }
+            }
+        }
+        world.rovers = executed.clone();
+
+        Ok(executed)
+    }
+
+    /// Parse further instructions for the rovers of a saved world, one rover per line
+    pub fn parse_resumption_message(
+        message: String,
+        locale: InstructionLocale,
+    ) -> Result<Vec<ResumedStream>, RoverErr> {
+        message
+            .lines()
+            .map(|line| line.trim())
             .enumerate()
-            .map(|(index, ((coordinates, direction), instructions))| {
-                // The ID of the rover should start from one
-                Rover::new(index + 1, coordinates, direction)
-                    .execute_instructions(instructions, (!unbounded).then(|| bounderies))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(index, line)| {
+                RoverErr::from_parse_result(resumed_stream(locale)(line), index)
+                    .map(|(id, instructions)| (id, instructions, index))
             })
             .collect()
     }
 
+    /// Parse the plateau bounderies and every rover's starting position and instructions
+    pub fn parse_incoming_message(
+        message: String,
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Mission, RoverErr> {
+        let mut lines = message.lines().map(|line| line.trim()).enumerate();
+        let bounderies = Self::parse_bounderies(lines.next())?;
+
+        let mut mission = Mission {
+            bounderies,
+            rovers: Vec::new(),
+        };
+        Self::parse_rovers(&mut mission, lines, locale, diagnostics)?;
+        mission.number()?;
+
+        Ok(mission)
+    }
+
+    /// Parse a further instructions file, adding its rovers to the mission
+    ///
+    /// The file may repeat the plateau bounderies, as long as they match the mission's.
+    pub fn parse_additional_message(
+        mission: &mut Mission,
+        message: String,
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<(), RoverErr> {
+        let mut lines = message
+            .lines()
+            .map(|line| line.trim())
+            .enumerate()
+            .peekable();
+
+        // A starting position has a direction, so it can not be mistaken for the bounderies
+        if let Some((index, line)) =
+            lines.next_if(|(_, line)| matches!(coordinate(line), Ok(("", _))))
+        {
+            let bounderies = Self::parse_bounderies(Some((index, line)))?;
+            if bounderies != mission.bounderies {
+                return Err(RoverErr::ConflictingBounderies(
+                    mission.bounderies,
+                    bounderies,
+                    index,
+                ));
+            }
+        }
+
+        Self::parse_rovers(mission, lines, locale, diagnostics)?;
+        mission.number()
+    }
+
+    /// Parse pairs of starting position and instruction lines until the input runs out
+    ///
+    /// Large missions are split into blocks of rovers which are parsed on separate threads. The
+    /// first error in the file is returned, along with the warnings raised before it.
+    fn parse_rovers<'a>(
+        mission: &mut Mission,
+        lines: impl Iterator<Item = Line<'a>>,
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<(), RoverErr> {
+        let lines = lines.collect::<Vec<Line>>();
+        let pairs = lines
+            .chunks(2)
+            .map(|pair| (pair.first().copied(), pair.get(1).copied()))
+            .collect::<Vec<(Option<Line>, Option<Line>)>>();
+
+        let threads = thread::available_parallelism().map_or(1, usize::from);
+        if threads == 1 || pairs.len() < PARALLEL_PARSE_THRESHOLD {
+            return Self::parse_pairs(mission, &pairs, locale, diagnostics);
+        }
+
+        Self::parse_pairs_in_parallel(mission, &pairs, threads, locale, diagnostics)
+    }
+
+    /// Parse pairs of starting position and instruction lines in blocks, one per thread
+    fn parse_pairs_in_parallel(
+        mission: &mut Mission,
+        pairs: &[(Option<Line>, Option<Line>)],
+        threads: usize,
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<(), RoverErr> {
+        let blocks = thread::scope(|scope| {
+            pairs
+                .chunks(pairs.len().div_ceil(threads))
+                .map(|block| {
+                    scope.spawn(move || {
+                        let mut mission = Mission {
+                            bounderies: Position::default(),
+                            rovers: Vec::new(),
+                        };
+                        let mut diagnostics = Diagnostics::default();
+                        let result =
+                            Self::parse_pairs(&mut mission, block, locale, &mut diagnostics);
+                        (mission, diagnostics, result)
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().expect("a parsing thread panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        for (block, block_diagnostics, result) in blocks {
+            diagnostics.merge(block_diagnostics);
+            result?;
+            mission.rovers.extend(block.rovers);
+        }
+
+        Ok(())
+    }
+
+    /// Parse pairs of starting position and instruction lines in order, stopping at the first error
+    fn parse_pairs(
+        mission: &mut Mission,
+        pairs: &[(Option<Line>, Option<Line>)],
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<(), RoverErr> {
+        for pair in pairs {
+            if let Some(entry) = Self::parse_instructions_and_position(*pair, locale, diagnostics)?
+            {
+                mission.rovers.push(entry);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get the bounderies of the plateau
-    pub fn parse_bounderies(input: Option<(usize, &str)>) -> Result<Coordinate, RoverErr> {
+    pub fn parse_bounderies(input: Option<Line>) -> Result<Position, RoverErr> {
         match input {
             Some((_, line)) => RoverErr::from_parse_result(coordinate(line), 0),
             None => Err(RoverErr::Parse(ParsingErr::MissingPlateauBounderies, 0)),
         }
     }
 
-    /// Get the starting positions and instructions for a rover
+    /// Get the starting positions, attributes and instructions for a rover
     pub fn parse_instructions_and_position(
-        input: (Option<(usize, &str)>, Option<(usize, &str)>),
-    ) -> Result<Option<((Coordinate, Direction), Vec<Instruction>)>, RoverErr> {
+        input: (Option<Line>, Option<Line>),
+        locale: InstructionLocale,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<RoverEntry>, RoverErr> {
         match input {
             (
                 Some((starting_pos_index, starting_pos)),
                 Some((instructions_index, instructions)),
-            ) => Ok(Some((
-                RoverErr::from_parse_result(starting_position(starting_pos), starting_pos_index)?,
-                RoverErr::from_parse_result(instruction_stream(instructions), instructions_index)?,
-            ))),
+            ) => {
+                let (starting_pos, attributes) =
+                    RoverErr::from_parse_result(rover_header(starting_pos), starting_pos_index)?;
+                let instructions = RoverErr::from_parse_result(
+                    localized_instruction_stream(locale)(instructions),
+                    instructions_index,
+                )?;
+
+                for instruction in no_op_turns(&instructions) {
+                    diagnostics.warn(Warning::NoOpTurns {
+                        instruction,
+                        line: instructions_index,
+                    });
+                }
+
+                Ok(Some((starting_pos, instructions, attributes)))
+            }
             // Catch when there is an uneven number of co-ordinate/instruction groupings
             (Some((previous_index, _)), None) => Err(RoverErr::Parse(
                 ParsingErr::MissingInstructions,
@@ -131,38 +859,91 @@ mod rover_module {
         #[cfg(test)]
         mod has_crossed_boundery {
             use super::Rover;
-            use crate::enums::Direction;
+            use crate::{
+                enums::Direction,
+                position::{Pose, Position},
+            };
 
             #[test]
             fn none_input() {
-                let rover = Rover::new(0, (-1, -1), Direction::North);
+                let rover = Rover::new(0, Pose::new(Position::new(-1, -1), Direction::North));
                 assert!(!rover.has_crossed_boundery(None));
             }
 
             #[test]
             fn out_of_supplied_bounds() {
-                let rover = Rover::new(0, (5, 5), Direction::North);
-                assert!(rover.has_crossed_boundery(Some((2, 2))));
+                let rover = Rover::new(0, Pose::new(Position::new(5, 5), Direction::North));
+                assert!(rover.has_crossed_boundery(Some(Position::new(2, 2))));
             }
 
             #[test]
             fn out_of_implied_bounds() {
-                let rover = Rover::new(0, (-1, -1), Direction::North);
-                assert!(rover.has_crossed_boundery(Some((2, 2))));
+                let rover = Rover::new(0, Pose::new(Position::new(-1, -1), Direction::North));
+                assert!(rover.has_crossed_boundery(Some(Position::new(2, 2))));
             }
         }
 
         #[cfg(test)]
-        mod execute_instructions {
+        mod is_near_boundery {
+            use super::Rover;
+            use crate::{
+                enums::Direction,
+                position::{Pose, Position},
+            };
+
+            #[test]
+            fn within_margin() {
+                let rover = Rover::new(0, Pose::new(Position::new(4, 2), Direction::North));
+                assert!(rover.is_near_boundery(Some(Position::new(5, 5)), 2));
+                assert!(!rover.is_near_boundery(Some(Position::new(5, 5)), 1));
+            }
+
+            #[test]
+            fn zero_margin() {
+                let rover = Rover::new(0, Pose::new(Position::new(0, 0), Direction::North));
+                assert!(!rover.is_near_boundery(Some(Position::new(5, 5)), 0));
+            }
+
+            #[test]
+            fn huge_margin() {
+                let rover = Rover::new(0, Pose::new(Position::new(4, 2), Direction::North));
+                assert!(rover.is_near_boundery(Some(Position::new(5, 5)), usize::MAX));
+                assert_eq!(
+                    rover.uneventful_moves(Some(Position::new(5, 5)), usize::MAX),
+                    0
+                );
+            }
+        }
 
+        #[cfg(test)]
+        mod execute_instructions {
             use super::Rover;
-            use crate::enums::{Direction, Instruction};
+            use crate::{
+                budget::Budget,
+                diagnostics::{Diagnostics, Warning},
+                enums::{Autonomy, Direction, Instruction, Recovery, RoverErr},
+                position::{Pose, Position},
+                stochastic::StochasticModel,
+                terrain,
+                trace::{Trace, TraceFilter},
+                world::World,
+            };
+
+            /// A world on a plateau with these bounderies, or one without any edges
+            fn plateau(bounderies: Option<Position>) -> World {
+                let mut world = World::default();
+                match bounderies {
+                    Some(bounderies) => world.bounderies = bounderies,
+                    None => world.unbounded = true,
+                }
+                world
+            }
 
             #[test]
             fn valid_input_with_all_directions() {
-                let rover = Rover::new(0, (0, 0), Direction::North);
+                let rover = Rover::new(0, Pose::new(Position::new(0, 0), Direction::North));
                 let result = rover.execute_instructions(
-                    vec![
+                    &[
                         Instruction::Move,
                         Instruction::Right,
                         Instruction::Move,
@@ -172,17 +953,509 @@ mod rover_module {
                         Instruction::Move,
                         Instruction::Right,
                     ],
-                    None,
+                    &mut plateau(None),
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
                 );
                 assert!(result.is_ok());
-                assert_eq!(result.unwrap(), Rover::new(0, (0, 0), Direction::North));
+                assert_eq!(
+                    result.unwrap(),
+                    Rover {
+                        odometer: 4,
+                        turns: 4,
+                        ..Rover::new(0, Pose::new(Position::new(0, 0), Direction::North))
+                    }
+                );
             }
 
             #[test]
-            fn crosses_boundery() {
-                let rover = Rover::new(0, (0, 0), Direction::North);
+            fn ends_on_boundery() {
+                let mut diagnostics = Diagnostics::default();
+                let rover = Rover::new(1, Pose::new(Position::new(0, 0), Direction::North));
+                let result = rover.execute_instructions(
+                    &[Instruction::Move],
+                    &mut plateau(Some(Position::new(5, 5))),
+                    &mut Budget::default(),
+                    &mut diagnostics,
+                    &mut Trace::default(),
+                );
+                assert!(result.is_ok());
+                assert_eq!(
+                    diagnostics.warnings(),
+                    &[Warning::EndedOnBoundery {
+                        rover: 1,
+                        position: Position::new(0, 1)
+                    }]
+                );
+            }
+
+            #[test]
+            fn enters_safety_margin() {
+                let mut diagnostics = Diagnostics::default();
+                let mut world = plateau(Some(Position::new(5, 5)));
+                world.safety_margin = 1;
+                let rover = Rover::new(1, Pose::new(Position::new(1, 1), Direction::North));
+                let result = rover.execute_instructions(
+                    &[Instruction::Move, Instruction::Left, Instruction::Move],
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut diagnostics,
+                    &mut Trace::default(),
+                );
+                assert!(result.is_ok());
+                assert_eq!(
+                    diagnostics.warnings(),
+                    &[
+                        Warning::NearBoundery {
+                            rover: 1,
+                            position: Position::new(0, 2),
+                            instruction: 2
+                        },
+                        Warning::EndedOnBoundery {
+                            rover: 1,
+                            position: Position::new(0, 2)
+                        }
+                    ]
+                );
+            }
+
+            #[test]
+            fn halts() {
+                let mut diagnostics = Diagnostics::default();
+                let rover = Rover::new(1, Pose::new(Position::new(1, 1), Direction::North));
+                let result = rover
+                    .execute_instructions(
+                        &[
+                            Instruction::Move,
+                            Instruction::Halt,
+                            Instruction::Move,
+                            Instruction::Move,
+                        ],
+                        &mut plateau(Some(Position::new(5, 5))),
+                        &mut Budget::default(),
+                        &mut diagnostics,
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert_eq!((result.pose.position.x, result.pose.position.y), (1, 2));
+                assert!(result.halted);
+                assert_eq!(
+                    diagnostics.warnings(),
+                    &[Warning::Halted {
+                        rover: 1,
+                        instruction: 1,
+                        ignored: 2
+                    }]
+                );
+            }
+
+            #[test]
+            fn lost_rover_leaves_scent() {
+                let mut world = World::new(Vec::new(), true);
+                world.bounderies = Position::new(5, 5);
+                let first = Rover::new(1, Pose::new(Position::new(0, 5), Direction::North))
+                    .execute_instructions(
+                        &[Instruction::Move, Instruction::Right],
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut Diagnostics::default(),
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert!(first.lost);
+                assert_eq!(first.odometer, 0);
+                assert_eq!(
+                    (
+                        first.pose.position.x,
+                        first.pose.position.y,
+                        first.pose.facing
+                    ),
+                    (0, 5, Direction::North)
+                );
+
+                let second = Rover::new(2, Pose::new(Position::new(0, 5), Direction::North))
+                    .execute_instructions(
+                        &[Instruction::Move, Instruction::Right, Instruction::Move],
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut Diagnostics::default(),
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert!(!second.lost);
+                assert_eq!(second.odometer, 1);
+                assert_eq!((second.pose.position.x, second.pose.position.y), (1, 5));
+            }
+
+            #[test]
+            fn slips_onto_each_cell() {
+                let mut world = World::new(vec![Position::new(1, 1)], false);
+                world.bounderies = Position::new(5, 5);
+                world.model = StochasticModel {
+                    slippage: 1.0,
+                    ..StochasticModel::default()
+                };
+                let rover = Rover::new(1, Pose::new(Position::new(1, 0), Direction::North));
+                let result = rover.execute_instructions(
+                    &[Instruction::Move],
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                );
+                match result {
+                    Err(RoverErr::Crater(rover, 0)) => {
+                        assert_eq!(rover.pose.position, Position::new(1, 1))
+                    }
+                    result => panic!("expected the rover to fall in the crater, got {result:?}"),
+                }
+            }
+
+            #[test]
+            fn slips_off_edge() {
+                let mut world = World::new(Vec::new(), true);
+                world.bounderies = Position::new(5, 5);
+                world.model = StochasticModel {
+                    slippage: 1.0,
+                    ..StochasticModel::default()
+                };
+                let rover = Rover::new(1, Pose::new(Position::new(1, 4), Direction::North))
+                    .execute_instructions(
+                        &[Instruction::Move],
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut Diagnostics::default(),
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert!(rover.lost);
+                assert_eq!(rover.pose.position, Position::new(1, 5));
+                assert_eq!(rover.odometer, 1);
+            }
+
+            #[test]
+            fn traces_hazard() {
+                let mut world = World::new(Vec::new(), true);
+                world.bounderies = Position::new(5, 5);
+                world.model = StochasticModel {
+                    hazard: 1.0,
+                    ..StochasticModel::default()
+                };
+                let mut trace = Trace::new(TraceFilter::default());
+                let rover = Rover::new(1, Pose::new(Position::new(1, 1), Direction::North))
+                    .execute_instructions(
+                        &[Instruction::Move, Instruction::Move],
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut Diagnostics::default(),
+                        &mut trace,
+                    )
+                    .unwrap();
+                assert!(rover.lost);
+                assert_eq!(trace.events().len(), 2);
+                assert!(trace.events()[1].lost);
+                assert_eq!(trace.events()[1].to_string(), "1 1 move 1 2 N lost");
+            }
+
+            #[test]
+            fn falls_into_crater() {
+                let mut world = World::new(vec![Position::new(2, 1)], false);
+                world.bounderies = Position::new(5, 5);
+                let rover = Rover::new(1, Pose::new(Position::new(1, 1), Direction::East));
+                let result = rover.execute_instructions(
+                    &[Instruction::Move],
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                );
+                assert!(matches!(result, Err(RoverErr::Crater(_, 0))));
+            }
+
+            #[test]
+            fn enters_tagged_cell() {
+                let mut world = plateau(Some(Position::new(5, 5)));
+                world.add_tags([
+                    (Position::new(1, 2), "slippery".to_string()),
+                    (Position::new(1, 2), "science-target".to_string()),
+                ]);
+                let mut trace = Trace::new(TraceFilter::default());
+                let rover = Rover::new(1, Pose::new(Position::new(1, 1), Direction::North));
+                let result = rover.execute_instructions(
+                    &[Instruction::Move, Instruction::Right, Instruction::Move],
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut trace,
+                );
+                assert!(result.is_ok());
+                assert_eq!(
+                    world
+                        .events
+                        .iter()
+                        .map(|event| (event.instruction, event.tag.as_str()))
+                        .collect::<Vec<_>>(),
+                    vec![(0, "slippery"), (0, "science-target")]
+                );
+                assert_eq!(trace.events()[1].tags, vec!["slippery", "science-target"]);
+                assert!(trace.events()[2].tags.is_empty());
+            }
+
+            #[test]
+            fn degrades_past_maintenance() {
+                let mut diagnostics = Diagnostics::default();
+                let mut rover = Rover::new(1, Pose::new(Position::new(1, 0), Direction::North));
+                rover.maintenance = Some(100);
+                let result = rover
+                    .execute_instructions(
+                        &[Instruction::Move; 150],
+                        &mut plateau(Some(Position::new(999, 999))),
+                        &mut Budget::default(),
+                        &mut diagnostics,
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert!(result.degraded);
+                assert_eq!(result.odometer, 150);
+                assert_eq!(result.status(), "degraded");
+                assert_eq!(
+                    diagnostics.warnings(),
+                    &[Warning::Degraded {
+                        rover: 1,
+                        instruction: 100,
+                        maintenance: 100
+                    }]
+                );
+            }
+
+            #[test]
+            fn serviced_on_base() {
+                let mut world = plateau(Some(Position::new(9, 9)));
+                world.add_tags([(Position::new(1, 2), terrain::BASE.to_string())]);
+                let mut diagnostics = Diagnostics::default();
+                let mut rover = Rover::new(1, Pose::new(Position::new(1, 0), Direction::North));
+                rover.maintenance = Some(3);
+                let result = rover
+                    .execute_instructions(
+                        &[
+                            vec![Instruction::Move; 2],
+                            vec![Instruction::Service],
+                            vec![Instruction::Move; 3],
+                        ]
+                        .concat(),
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut diagnostics,
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert!(!result.degraded);
+                assert_eq!(result.since_service, 3);
+                assert_eq!(result.odometer, 5);
+                assert!(diagnostics.warnings().is_empty());
+            }
+
+            #[test]
+            fn serviced_off_base() {
+                let mut diagnostics = Diagnostics::default();
+                let mut rover = Rover::new(1, Pose::new(Position::new(1, 0), Direction::North));
+                rover.maintenance = Some(3);
+                let result = rover
+                    .execute_instructions(
+                        &[Instruction::Move, Instruction::Service],
+                        &mut plateau(Some(Position::new(5, 5))),
+                        &mut Budget::default(),
+                        &mut diagnostics,
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert_eq!(result.since_service, 1);
+                assert_eq!(
+                    diagnostics.warnings(),
+                    &[Warning::ServiceOffBase {
+                        rover: 1,
+                        position: Position::new(1, 1),
+                        instruction: 1
+                    }]
+                );
+            }
+
+            #[test]
+            fn safe_autonomy_stops() {
+                let mut world = World::new(vec![Position::new(2, 3)], false);
+                world.bounderies = Position::new(5, 5);
+                world.autonomy = Autonomy::Safe;
+                let mut diagnostics = Diagnostics::default();
+                let rover = Rover::new(1, Pose::new(Position::new(2, 1), Direction::North));
                 let result = rover
-                    .execute_instructions(vec![Instruction::Left, Instruction::Move], Some((5, 5)));
+                    .execute_instructions(
+                        &[Instruction::Move; 3],
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut diagnostics,
+                        &mut Trace::default(),
+                    )
+                    .unwrap();
+                assert!(result.halted);
+                assert_eq!((result.pose.position.x, result.pose.position.y), (2, 2));
+                assert_eq!(
+                    diagnostics.warnings(),
+                    &[Warning::Intervention {
+                        rover: 1,
+                        position: Position::new(2, 2),
+                        instruction: 1,
+                        recovery: Recovery::Stop
+                    }]
+                );
+            }
+
+            #[test]
+            fn safe_autonomy_traces_turn() {
+                let mut world = plateau(Some(Position::new(3, 5)));
+                world.autonomy = Autonomy::Safe;
+                world.recovery = Recovery::TurnRight;
+                let mut trace = Trace::new(TraceFilter::default());
+                Rover::new(1, Pose::new(Position::new(1, 4), Direction::North))
+                    .execute_instructions(
+                        &[Instruction::Move; 2],
+                        &mut world,
+                        &mut Budget::default(),
+                        &mut Diagnostics::default(),
+                        &mut trace,
+                    )
+                    .unwrap();
+                let events = trace
+                    .events()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>();
+                assert_eq!(
+                    events,
+                    vec![
+                        "0 1 start 1 4 N",
+                        "1 1 move 1 5 N",
+                        "2 1 right 1 5 E",
+                        "2 1 move 2 5 E"
+                    ]
+                );
+            }
+
+            #[test]
+            fn safe_autonomy_turns_right() {
+                let execute = |position| {
+                    let mut world = plateau(Some(Position::new(3, 5)));
+                    world.autonomy = Autonomy::Safe;
+                    world.recovery = Recovery::TurnRight;
+                    let mut diagnostics = Diagnostics::default();
+                    let rover = Rover::new(1, Pose::new(position, Direction::North))
+                        .execute_instructions(
+                            &[Instruction::Move; 3],
+                            &mut world,
+                            &mut Budget::default(),
+                            &mut diagnostics,
+                            &mut Trace::default(),
+                        )
+                        .unwrap();
+                    let recoveries = diagnostics
+                        .warnings()
+                        .iter()
+                        .filter_map(|warning| match warning {
+                            Warning::Intervention { recovery, .. } => Some(*recovery),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    (rover, recoveries)
+                };
+
+                let (rover, recoveries) = execute(Position::new(1, 4));
+                assert_eq!(
+                    (
+                        rover.pose.position.x,
+                        rover.pose.position.y,
+                        rover.pose.facing
+                    ),
+                    (3, 5, Direction::East)
+                );
+                assert!(!rover.halted);
+                assert_eq!(recoveries, vec![Recovery::TurnRight]);
+
+                // In the corner turning right is fatal too
+                let (rover, recoveries) = execute(Position::new(3, 4));
+                assert_eq!(
+                    (
+                        rover.pose.position.x,
+                        rover.pose.position.y,
+                        rover.pose.facing
+                    ),
+                    (3, 5, Direction::East)
+                );
+                assert!(rover.halted);
+                assert_eq!(recoveries, vec![Recovery::Stop]);
+            }
+
+            #[test]
+            fn folded_moves() {
+                let instructions = [
+                    vec![Instruction::Move; 500],
+                    vec![Instruction::Right],
+                    vec![Instruction::Move; 995],
+                    vec![Instruction::Right],
+                    vec![Instruction::Move; 10],
+                ]
+                .concat();
+                let execute = |trace: &mut Trace| {
+                    let mut diagnostics = Diagnostics::default();
+                    let mut world = plateau(Some(Position::new(999, 999)));
+                    world.safety_margin = 3;
+                    let rover = Rover::new(1, Pose::new(Position::new(0, 0), Direction::North))
+                        .execute_instructions(
+                            &instructions,
+                            &mut world,
+                            &mut Budget::default(),
+                            &mut diagnostics,
+                            trace,
+                        )
+                        .unwrap();
+                    (rover, diagnostics.warnings().len())
+                };
+
+                // Recording every move stops them from being folded together
+                let folded = execute(&mut Trace::default());
+                let stepped = execute(&mut Trace::new(TraceFilter::default()));
+                assert_eq!(folded, stepped);
+                assert_eq!(
+                    (folded.0.pose.position.x, folded.0.pose.position.y),
+                    (995, 490)
+                );
+            }
+
+            #[test]
+            fn folded_moves_cross_boundery() {
+                let rover = Rover::new(1, Pose::new(Position::new(0, 0), Direction::North));
+                let result = rover.execute_instructions(
+                    &[Instruction::Move; 10],
+                    &mut plateau(Some(Position::new(5, 5))),
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                );
+                assert!(matches!(
+                    result,
+                    Err(RoverErr::Boundery(ref rover, 5)) if rover.pose.position == Position::new(0, 6)
+                ));
+            }
+
+            #[test]
+            fn crosses_boundery() {
+                let rover = Rover::new(0, Pose::new(Position::new(0, 0), Direction::North));
+                let result = rover.execute_instructions(
+                    &[Instruction::Left, Instruction::Move],
+                    &mut plateau(Some(Position::new(5, 5))),
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                );
                 assert!(result.is_err());
             }
         }
@@ -194,12 +1467,13 @@ mod rover_module {
         #[cfg(test)]
         mod parse_bounderies {
             use super::RoverControlSatellite;
+            use crate::position::Position;
 
             #[test]
             fn valid_input() {
                 let result = RoverControlSatellite::parse_bounderies(Some((0, "5 5")));
                 assert!(result.is_ok());
-                assert_eq!(result.unwrap(), (5, 5))
+                assert_eq!(result.unwrap(), Position::new(5, 5))
             }
 
             #[test]
@@ -211,61 +1485,357 @@ mod rover_module {
 
         #[cfg(test)]
         mod parse_instructions_and_position {
-            use crate::enums::{Direction, Instruction};
+            use crate::{
+                diagnostics::Diagnostics,
+                enums::{Direction, Instruction, InstructionLocale},
+                position::{Pose, Position},
+            };
 
-            use super::RoverControlSatellite;
+            use super::{Attributes, RoverControlSatellite};
 
             #[test]
             fn valid_input() {
-                let result = RoverControlSatellite::parse_instructions_and_position((
-                    Some((0, "1 1 N")),
-                    Some((0, "M")),
-                ));
+                let result = RoverControlSatellite::parse_instructions_and_position(
+                    (Some((0, "1 1 N")), Some((0, "M"))),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
                 assert!(result.is_ok());
                 assert_eq!(
                     result.unwrap(),
-                    Some((((1, 1), Direction::North), vec![Instruction::Move]))
+                    Some((
+                        Pose::new(Position::new(1, 1), Direction::North),
+                        vec![Instruction::Move],
+                        Attributes::default()
+                    ))
                 )
             }
 
             #[test]
             fn invalid_position() {
-                let result = RoverControlSatellite::parse_instructions_and_position((
-                    Some((0, "1 N")),
-                    Some((0, "M")),
-                ));
+                let result = RoverControlSatellite::parse_instructions_and_position(
+                    (Some((0, "1 N")), Some((0, "M"))),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
                 assert!(result.is_err());
             }
 
             #[test]
             fn invalid_instructions() {
-                let result = RoverControlSatellite::parse_instructions_and_position((
-                    Some((0, "1 1 N")),
-                    Some((0, "d")),
-                ));
+                let result = RoverControlSatellite::parse_instructions_and_position(
+                    (Some((0, "1 1 N")), Some((0, "d"))),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
                 assert!(result.is_err());
             }
 
             #[test]
             fn only_one_input() {
-                let result = RoverControlSatellite::parse_instructions_and_position((
-                    Some((0, "1 1 N")),
-                    None,
-                ));
+                let result = RoverControlSatellite::parse_instructions_and_position(
+                    (Some((0, "1 1 N")), None),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
                 assert!(result.is_err());
             }
 
             #[test]
             fn no_inputs() {
-                let result = RoverControlSatellite::parse_instructions_and_position((None, None));
+                let result = RoverControlSatellite::parse_instructions_and_position(
+                    (None, None),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
                 assert!(result.is_ok());
                 assert!(result.unwrap().is_none())
             }
         }
 
+        #[cfg(test)]
+        mod check_final_positions {
+            use super::{Rover, RoverControlSatellite};
+            use crate::{
+                enums::{Direction, RoverErr},
+                position::{Pose, Position},
+            };
+
+            #[test]
+            fn unique_positions() {
+                let result = RoverControlSatellite::check_final_positions(&[
+                    Rover::new(1, Pose::new(Position::new(1, 1), Direction::North)),
+                    Rover::new(2, Pose::new(Position::new(1, 2), Direction::North)),
+                ]);
+                assert!(result.is_ok());
+            }
+
+            #[test]
+            fn shared_position() {
+                let result = RoverControlSatellite::check_final_positions(&[
+                    Rover::new(1, Pose::new(Position::new(1, 1), Direction::North)),
+                    Rover::new(2, Pose::new(Position::new(3, 3), Direction::North)),
+                    Rover::new(3, Pose::new(Position::new(1, 1), Direction::East)),
+                ]);
+                assert!(matches!(
+                    result,
+                    Err(RoverErr::SharedFinalPosition(1, 3, Position { x: 1, y: 1 }))
+                ));
+            }
+        }
+
+        #[cfg(test)]
+        mod parse_incoming_message {
+            use super::{Mission, RoverControlSatellite};
+            use crate::{
+                diagnostics::Diagnostics,
+                enums::{InstructionLocale, ParsingErr, RoverErr},
+                position::Position,
+            };
+
+            fn message(rovers: usize) -> String {
+                let mut message = String::from("5 5");
+                for _ in 0..rovers {
+                    message.push_str("\n1 2 N\nLRM");
+                }
+                message
+            }
+
+            #[test]
+            fn many_rovers() {
+                let mut diagnostics = Diagnostics::default();
+                let result = RoverControlSatellite::parse_incoming_message(
+                    message(3000),
+                    InstructionLocale::English,
+                    &mut diagnostics,
+                );
+                assert_eq!(result.unwrap().rovers.len(), 3000);
+                assert_eq!(diagnostics.len(), 3000);
+            }
+
+            #[test]
+            fn numbered_rovers() {
+                let result = RoverControlSatellite::parse_incoming_message(
+                    "5 5\n3 3 E id=2\nM\n1 2 N id=1\nL".to_string(),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
+                let positions = result
+                    .unwrap()
+                    .rovers
+                    .iter()
+                    .map(|(pose, _, _)| pose.position)
+                    .collect::<Vec<Position>>();
+                assert_eq!(positions, vec![Position::new(1, 2), Position::new(3, 3)]);
+            }
+
+            #[test]
+            fn invalid_ids() {
+                for message in [
+                    "5 5\n3 3 E id=2\nM\n1 2 N\nL",
+                    "5 5\n3 3 E id=1\nM\n1 2 N id=1\nL",
+                    "5 5\n3 3 E id=3\nM\n1 2 N id=1\nL",
+                ] {
+                    let result = RoverControlSatellite::parse_incoming_message(
+                        message.to_string(),
+                        InstructionLocale::English,
+                        &mut Diagnostics::default(),
+                    );
+                    assert!(matches!(result, Err(RoverErr::RoverIds)));
+                }
+            }
+
+            #[test]
+            fn parallel_blocks_with_error() {
+                let message = format!("{}\n1 N\nM\n{}", message(20), message(10));
+                let pairs = message
+                    .lines()
+                    .enumerate()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+                    .chunks(2)
+                    .map(|pair| (Some(pair[0]), pair.get(1).copied()))
+                    .collect::<Vec<_>>();
+                let mut mission = Mission {
+                    bounderies: Position::new(5, 5),
+                    rovers: Vec::new(),
+                };
+                let mut diagnostics = Diagnostics::default();
+                let result = RoverControlSatellite::parse_pairs_in_parallel(
+                    &mut mission,
+                    &pairs,
+                    4,
+                    InstructionLocale::English,
+                    &mut diagnostics,
+                );
+                assert!(matches!(
+                    result,
+                    Err(RoverErr::Parse(ParsingErr::UnexpectedToken, 41))
+                ));
+                assert_eq!(diagnostics.len(), 20);
+            }
+
+            #[test]
+            fn many_rovers_with_error() {
+                let mut diagnostics = Diagnostics::default();
+                let result = RoverControlSatellite::parse_incoming_message(
+                    format!("{}\n1 N\nM\n{}", message(2000), message(1000)),
+                    InstructionLocale::English,
+                    &mut diagnostics,
+                );
+                // The error is on the line after the first 2000 rovers and the plateau
+                assert!(matches!(
+                    result,
+                    Err(RoverErr::Parse(ParsingErr::UnexpectedToken, 4001))
+                ));
+                assert_eq!(diagnostics.len(), 2000);
+            }
+        }
+
+        #[cfg(test)]
+        mod resume_mission {
+            use super::{Rover, RoverControlSatellite};
+            use crate::{
+                budget::Budget,
+                diagnostics::Diagnostics,
+                enums::{Direction, InstructionLocale, RoverErr},
+                position::{Pose, Position},
+                trace::Trace,
+                world::World,
+            };
+
+            fn world() -> World {
+                let mut world = World::new(Vec::new(), true);
+                world.bounderies = Position::new(5, 5);
+                world.rovers = vec![
+                    Rover::new(1, Pose::new(Position::new(1, 1), Direction::North)),
+                    Rover::new(2, Pose::new(Position::new(3, 3), Direction::East)),
+                ];
+                world
+            }
+
+            #[test]
+            fn valid_input() {
+                let mut world = world();
+                let streams = RoverControlSatellite::parse_resumption_message(
+                    "2 MMLM\n\n2 M".to_string(),
+                    InstructionLocale::English,
+                )
+                .unwrap();
+                let result = RoverControlSatellite::resume_mission(
+                    streams,
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                );
+                assert_eq!(
+                    result.unwrap(),
+                    vec![
+                        Rover::new(1, Pose::new(Position::new(1, 1), Direction::North)),
+                        Rover {
+                            odometer: 4,
+                            turns: 1,
+                            ..Rover::new(2, Pose::new(Position::new(5, 5), Direction::North))
+                        }
+                    ]
+                );
+                assert_eq!(world.rovers[1].odometer, 4);
+            }
+
+            #[test]
+            fn lost_rover() {
+                let mut world = world();
+                world.rovers[0].lost = true;
+                let result = RoverControlSatellite::resume_mission(
+                    vec![(1, Vec::new(), 3)],
+                    &mut world,
+                    &mut Budget::default(),
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                );
+                assert!(matches!(result, Err(RoverErr::LostRover(1, 3))));
+            }
+        }
+
+        #[cfg(test)]
+        mod parse_additional_message {
+            use super::RoverControlSatellite;
+            use crate::{
+                diagnostics::Diagnostics,
+                enums::{Direction, InstructionLocale, RoverErr},
+                position::{Pose, Position},
+                rover::Mission,
+            };
+
+            fn mission() -> Mission {
+                RoverControlSatellite::parse_incoming_message(
+                    "5 5\n1 2 N\nM".to_string(),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                )
+                .unwrap()
+            }
+
+            #[test]
+            fn without_bounderies() {
+                let mut mission = mission();
+                let result = RoverControlSatellite::parse_additional_message(
+                    &mut mission,
+                    "3 3 E\nM".to_string(),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
+                assert!(result.is_ok());
+                assert_eq!(mission.rovers.len(), 2);
+                assert_eq!(
+                    mission.rovers[1].0,
+                    Pose::new(Position::new(3, 3), Direction::East)
+                );
+            }
+
+            #[test]
+            fn matching_bounderies() {
+                let mut mission = mission();
+                let result = RoverControlSatellite::parse_additional_message(
+                    &mut mission,
+                    "5 5\n3 3 E\nM".to_string(),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
+                assert!(result.is_ok());
+                assert_eq!(mission.rovers.len(), 2);
+            }
+
+            #[test]
+            fn conflicting_bounderies() {
+                let mut mission = mission();
+                let result = RoverControlSatellite::parse_additional_message(
+                    &mut mission,
+                    "4 4\n3 3 E\nM".to_string(),
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                );
+                assert!(matches!(
+                    result,
+                    Err(RoverErr::ConflictingBounderies(
+                        Position { x: 5, y: 5 },
+                        Position { x: 4, y: 4 },
+                        0
+                    ))
+                ));
+            }
+        }
+
         #[cfg(test)]
         mod parse_and_execute_incoming_message {
             use super::{Rover, RoverControlSatellite};
+            use crate::{
+                diagnostics::Diagnostics,
+                enums::InstructionLocale,
+                position::{Pose, Position},
+                trace::{Trace, TraceFilter},
+            };
 
             #[test]
             fn valid_input() {
@@ -277,17 +1847,82 @@ mod rover_module {
                     MMRMMRMRRM"#
                         .to_string(),
                     false,
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
                 );
                 assert!(result.is_ok());
                 assert_eq!(
                     result.unwrap(),
                     vec![
-                        Rover::new(1, (1, 3), crate::enums::Direction::North),
-                        Rover::new(2, (5, 1), crate::enums::Direction::East)
+                        Rover {
+                            odometer: 5,
+                            turns: 4,
+                            ..Rover::new(
+                                1,
+                                Pose::new(Position::new(1, 3), crate::enums::Direction::North)
+                            )
+                        },
+                        Rover {
+                            odometer: 6,
+                            turns: 4,
+                            ..Rover::new(
+                                2,
+                                Pose::new(Position::new(5, 1), crate::enums::Direction::East)
+                            )
+                        }
                     ]
                 )
             }
 
+            #[test]
+            fn executes_in_priority_order() {
+                let mut trace = Trace::new(TraceFilter::default());
+                let rovers = RoverControlSatellite::parse_and_execute_incoming_message(
+                    r#"5 5
+                    1 2 N
+                    M
+                    3 3 E priority=2
+                    M"#
+                    .to_string(),
+                    false,
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                    &mut trace,
+                )
+                .unwrap();
+                assert_eq!(
+                    trace
+                        .events()
+                        .iter()
+                        .map(|event| event.rover)
+                        .collect::<Vec<usize>>(),
+                    vec![2, 2, 1, 1]
+                );
+                assert_eq!(rovers[0].id, 1);
+            }
+
+            #[test]
+            fn halted_report() {
+                let rovers = RoverControlSatellite::parse_and_execute_incoming_message(
+                    r#"5 5
+                    1 2 N
+                    MXM
+                    3 3 E
+                    M"#
+                    .to_string(),
+                    false,
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
+                )
+                .unwrap();
+                assert_eq!(
+                    RoverControlSatellite::format_report(&rovers),
+                    "1 3 N halted\n4 3 E"
+                );
+            }
+
             #[test]
             fn crosses_boundery_unbounded() {
                 let result = RoverControlSatellite::parse_and_execute_incoming_message(
@@ -296,11 +1931,21 @@ mod rover_module {
                     LM"#
                     .to_string(),
                     true,
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
                 );
                 assert!(result.is_ok());
                 assert_eq!(
                     result.unwrap(),
-                    vec![Rover::new(1, (-1, 0), crate::enums::Direction::West),]
+                    vec![Rover {
+                        odometer: 1,
+                        turns: 1,
+                        ..Rover::new(
+                            1,
+                            Pose::new(Position::new(-1, 0), crate::enums::Direction::West)
+                        )
+                    }]
                 )
             }
 
@@ -312,6 +1957,9 @@ mod rover_module {
                     LM"#
                     .to_string(),
                     false,
+                    InstructionLocale::English,
+                    &mut Diagnostics::default(),
+                    &mut Trace::default(),
                 );
                 assert!(result.is_err());
             }
@@ -0,0 +1,399 @@
+use std::io::{BufRead, Write};
+
+use crate::{
+    enums::RoverErr,
+    expand::Defines,
+    input::{self, MissionText},
+    json::{self, Json},
+    rover::{Rover, RoverControlSatellite},
+    simulation::Simulation,
+    stepper::{Stepper, SNAPSHOT_INTERVAL},
+};
+
+// The error codes JSON-RPC 2.0 reserves
+const PARSE_ERROR: isize = -32700;
+const INVALID_REQUEST: isize = -32600;
+const METHOD_NOT_FOUND: isize = -32601;
+const INVALID_PARAMS: isize = -32602;
+/// A mission that could not be loaded, or a method that needs one before it is
+const MISSION_ERROR: isize = -32000;
+
+/// A failed request, sent back as the `error` of the response
+#[derive(Debug, PartialEq)]
+pub struct RpcError {
+    pub code: isize,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: isize, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A mission being stepped through by a client, one JSON-RPC request per line
+///
+/// Methods:
+/// - `loadMission {"mission": "..."}` parses a mission and rewinds to its start
+/// - `step {"count": N}` executes the next `N` instructions, one if left out
+/// - `getState` returns the rovers, warnings and any error as of the current tick
+/// - `reset` rewinds the loaded mission to its start
+/// - `jumpToTick {"tick": N}` moves forwards or backwards to the state after `N` instructions
+/// - `jumpToPreviousViolation` moves back to the last tick before this one that raised a warning
+///
+/// Stepping is as deterministic as a whole run, and jumps only execute the instructions since
+/// the closest snapshot before them.
+#[derive(Debug, Default)]
+pub struct RpcSession {
+    pub simulation: Simulation,
+    stepper: Option<Stepper>,
+    /// How many instructions have been executed, across all rovers
+    tick: usize,
+}
+
+impl RpcSession {
+    pub fn new(simulation: Simulation) -> Self {
+        RpcSession {
+            simulation,
+            ..RpcSession::default()
+        }
+    }
+
+    /// Serve requests until the input ends, writing one response per line
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> Result<(), RoverErr> {
+        for line in input.lines() {
+            let line = line.map_err(RoverErr::Reading)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                writeln!(output, "{response}")
+                    .and_then(|_| output.flush())
+                    .map_err(RoverErr::Saving)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer a single request, notifications (valid requests without an ID) get no response
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let Some(request) = json::parse(line) else {
+            return Some(response(
+                &Json::Null,
+                Err(RpcError::new(PARSE_ERROR, "Parse error")),
+            ));
+        };
+        let id = request.get("id").cloned();
+
+        // An invalid request is answered even without an ID, as it cannot be a notification
+        let Some(method) = request.get("method").and_then(Json::as_str) else {
+            return Some(response(
+                &id.unwrap_or(Json::Null),
+                Err(RpcError::new(INVALID_REQUEST, "Invalid Request")),
+            ));
+        };
+        let result = self.call(method, request.get("params"));
+        id.map(|id| response(&id, result))
+    }
+
+    fn call(&mut self, method: &str, params: Option<&Json>) -> Result<String, RpcError> {
+        let param = |name| params.and_then(|params| params.get(name));
+
+        match method {
+            "loadMission" => {
+                let text = param("mission")
+                    .and_then(Json::as_str)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "expected a `mission` string"))?;
+                let texts = vec![MissionText {
+                    path: None,
+                    text: text.to_string(),
+                    origins: None,
+                }];
+                let (texts, plateaus) = input::prepare(texts, None, &Defines::new())
+                    .map_err(|err| RpcError::new(MISSION_ERROR, err.to_string()))?;
+                // Warnings raised whilst parsing are reported from the first tick
+                let mut diagnostics = self.simulation.diagnostics();
+                let mission = RoverControlSatellite::parse_incoming_message(
+                    texts[0].text.clone(),
+                    self.simulation.locale,
+                    &mut diagnostics,
+                )
+                .map_err(|err| RpcError::new(MISSION_ERROR, err.to_string()))?;
+                self.stepper = Some(Stepper::new(
+                    mission,
+                    &self.simulation.with_plateaus(&plateaus),
+                    diagnostics,
+                    SNAPSHOT_INTERVAL,
+                ));
+                self.tick = 0;
+                self.state()
+            }
+            "step" => {
+                let count = match param("count") {
+                    Some(count) => count_param(count, "count")?,
+                    None => 1,
+                };
+                self.stepper()?;
+                self.tick = self.tick.saturating_add(count);
+                self.state()
+            }
+            "getState" => self.state(),
+            "reset" => {
+                self.stepper()?;
+                self.tick = 0;
+                self.state()
+            }
+            "jumpToTick" => {
+                let tick = param("tick")
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "expected a `tick`"))
+                    .and_then(|tick| count_param(tick, "tick"))?;
+                self.stepper()?;
+                self.tick = tick;
+                self.state()
+            }
+            "jumpToPreviousViolation" => {
+                let tick = self.tick;
+                self.tick = self.stepper()?.previous_violation(tick).ok_or_else(|| {
+                    RpcError::new(MISSION_ERROR, format!("no warning before tick {tick}"))
+                })?;
+                self.state()
+            }
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {method}"),
+            )),
+        }
+    }
+
+    fn stepper(&mut self) -> Result<&mut Stepper, RpcError> {
+        self.stepper
+            .as_mut()
+            .ok_or_else(|| RpcError::new(MISSION_ERROR, "no mission is loaded"))
+    }
+
+    /// The state of the mission as of the current tick, which stops at the end of the mission
+    fn state(&mut self) -> Result<String, RpcError> {
+        let tick = self.tick;
+        let stepper = self.stepper()?;
+        let checkpoint = stepper.at(tick);
+        let mission = stepper.mission();
+
+        // Rovers yet to execute are where they started
+        let rovers = (0..mission.rovers.len())
+            .map(|index| {
+                checkpoint
+                    .world
+                    .rovers
+                    .iter()
+                    .chain(checkpoint.running.as_ref().map(|(rover, _)| rover))
+                    .find(|rover| rover.id == index + 1)
+                    .cloned()
+                    .unwrap_or_else(|| mission.launch(index))
+            })
+            .map(|rover| Rover::to_json(&rover))
+            .collect::<Vec<_>>();
+        let warnings = checkpoint
+            .diagnostics
+            .warnings()
+            .iter()
+            .map(|warning| Json::String(warning.to_string()).to_string())
+            .collect::<Vec<_>>();
+        self.tick = checkpoint.tick;
+
+        Ok(format!(
+            r#"{{"tick": {}, "done": {}, "rovers": [{}], "warnings": [{}], "error": {}}}"#,
+            self.tick,
+            checkpoint.done,
+            rovers.join(", "),
+            warnings.join(", "),
+            checkpoint.error.map_or(Json::Null, Json::String)
+        ))
+    }
+}
+
+fn count_param(json: &Json, name: &str) -> Result<usize, RpcError> {
+    json.as_isize()
+        .and_then(|count| usize::try_from(count).ok())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("expected a non-negative `{name}`")))
+}
+
+fn response(id: &Json, result: Result<String, RpcError>) -> String {
+    match result {
+        Ok(result) => format!(r#"{{"jsonrpc": "2.0", "id": {id}, "result": {result}}}"#),
+        Err(err) => format!(
+            r#"{{"jsonrpc": "2.0", "id": {id}, "error": {{"code": {}, "message": {}}}}}"#,
+            err.code,
+            Json::String(err.message)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod rpc_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod handle {
+        use super::RpcSession;
+        use crate::json::{self, Json};
+
+        fn call(session: &mut RpcSession, request: &str) -> Json {
+            json::parse(&session.handle(request).unwrap()).unwrap()
+        }
+
+        fn positions(state: &Json) -> Vec<(isize, isize)> {
+            state
+                .get("result")
+                .and_then(|result| result.get("rovers"))
+                .and_then(Json::as_array)
+                .unwrap()
+                .iter()
+                .map(|rover| {
+                    (
+                        rover.get("x").unwrap().as_isize().unwrap(),
+                        rover.get("y").unwrap().as_isize().unwrap(),
+                    )
+                })
+                .collect()
+        }
+
+        #[test]
+        fn steps_through_mission() {
+            let mut session = RpcSession::default();
+            let loaded = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "loadMission", "params": {"mission": "5 5\n1 2 N\nMM\n3 3 E\nM"}}"#,
+            );
+            assert_eq!(positions(&loaded), vec![(1, 2), (3, 3)]);
+
+            let stepped = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "step"}"#,
+            );
+            assert_eq!(positions(&stepped), vec![(1, 3), (3, 3)]);
+
+            let finished = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": "3", "method": "step", "params": {"count": 10}}"#,
+            );
+            assert_eq!(finished.get("id"), Some(&Json::String("3".to_string())));
+            let result = finished.get("result").unwrap();
+            assert_eq!(result.get("done"), Some(&Json::Bool(true)));
+            assert_eq!(result.get("tick").unwrap().as_isize(), Some(3));
+            assert_eq!(positions(&finished), vec![(1, 4), (4, 3)]);
+
+            let reset = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 4, "method": "reset"}"#,
+            );
+            assert_eq!(positions(&reset), vec![(1, 2), (3, 3)]);
+        }
+
+        #[test]
+        fn time_travel() {
+            let mut session = RpcSession::default();
+            call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "loadMission", "params": {"mission": "5 5\n1 2 N\nMMM\n3 3 E\nM"}}"#,
+            );
+            let jumped = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "jumpToTick", "params": {"tick": 4}}"#,
+            );
+            assert_eq!(positions(&jumped), vec![(1, 5), (4, 3)]);
+
+            // The first rover ending on the top edge raised a warning at tick 3
+            let violation = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 3, "method": "jumpToPreviousViolation"}"#,
+            );
+            let result = violation.get("result").unwrap();
+            assert_eq!(result.get("tick").unwrap().as_isize(), Some(3));
+            assert_eq!(positions(&violation), vec![(1, 5), (3, 3)]);
+
+            let none = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 4, "method": "jumpToPreviousViolation"}"#,
+            );
+            assert!(none.get("error").is_some());
+        }
+
+        #[test]
+        fn parse_warnings() {
+            let mut session = RpcSession::default();
+            let loaded = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "loadMission", "params": {"mission": "5 5\n1 2 N\nLRM"}}"#,
+            );
+            let warnings = loaded
+                .get("result")
+                .and_then(|result| result.get("warnings"))
+                .and_then(Json::as_array)
+                .unwrap();
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].as_str().unwrap().contains("Rover Warning"));
+        }
+
+        #[test]
+        fn mission_error() {
+            let mut session = RpcSession::default();
+            call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "loadMission", "params": {"mission": "5 5\n1 2 N\nMMMMMM"}}"#,
+            );
+            let state = call(
+                &mut session,
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "step", "params": {"count": 6}}"#,
+            );
+            let result = state.get("result").unwrap();
+            assert_eq!(result.get("done"), Some(&Json::Bool(true)));
+            assert!(result
+                .get("error")
+                .and_then(Json::as_str)
+                .unwrap()
+                .contains("boundery"));
+        }
+
+        #[test]
+        fn invalid_requests() {
+            let mut session = RpcSession::default();
+            let code = |response: Json| {
+                response
+                    .get("error")
+                    .and_then(|error| error.get("code"))
+                    .and_then(Json::as_isize)
+            };
+
+            assert_eq!(code(call(&mut session, "{")), Some(-32700));
+            assert_eq!(
+                code(call(&mut session, r#"{"jsonrpc": "2.0", "id": 1}"#)),
+                Some(-32600)
+            );
+            // An invalid request without an ID is still answered, with a null ID
+            let invalid = call(&mut session, r#"{"jsonrpc": "2.0"}"#);
+            assert_eq!(invalid.get("id"), Some(&Json::Null));
+            assert_eq!(code(invalid), Some(-32600));
+            assert_eq!(
+                code(call(
+                    &mut session,
+                    r#"{"jsonrpc": "2.0", "id": 1, "method": "fly"}"#
+                )),
+                Some(-32601)
+            );
+            assert_eq!(
+                code(call(
+                    &mut session,
+                    r#"{"jsonrpc": "2.0", "id": 1, "method": "getState"}"#
+                )),
+                Some(-32000)
+            );
+            assert_eq!(
+                session.handle(r#"{"jsonrpc": "2.0", "method": "reset"}"#),
+                None
+            );
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use ed25519_dalek::{
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    Signer, SigningKey, VerifyingKey,
+};
+use sha2::{Digest, Sha256};
+
+use crate::enums::RoverErr;
+
+/// The first line of every signature file, naming its layout
+const HEADER: &str = "rover-cli-signature 1";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+fn unhex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// The SHA-256 digest of some text, as written in signature files and manifests
+pub fn digest(text: &str) -> String {
+    format!("sha256:{}", hex(&Sha256::digest(text.as_bytes())))
+}
+
+/// Read an Ed25519 private key from a PKCS#8 PEM file, e.g. made by
+/// `openssl genpkey -algorithm ed25519`
+pub fn signing_key(pem: &str) -> Result<SigningKey, RoverErr> {
+    SigningKey::from_pkcs8_pem(pem).map_err(|_| RoverErr::InvalidKey("private"))
+}
+
+/// Read an Ed25519 public key from a PEM file, e.g. made by `openssl pkey -pubout`
+pub fn verifying_key(pem: &str) -> Result<VerifyingKey, RoverErr> {
+    VerifyingKey::from_public_key_pem(pem).map_err(|_| RoverErr::InvalidKey("public"))
+}
+
+/// Where the signature of an output file is written, alongside it with `.sig` appended
+pub fn signature_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// What a run read and wrote, signed with a private key so anyone holding the public key can
+/// check the output against its input
+#[derive(Debug, PartialEq)]
+pub struct Signature {
+    pub crate_version: String,
+    /// Where each text of instructions came from, and the digest of its contents
+    pub inputs: Vec<(String, String)>,
+    pub output: String,
+    /// The Ed25519 signature of everything above it in the signature file
+    pub signature: String,
+}
+
+impl Signature {
+    /// Sign the output of a run of this build of the simulator
+    pub fn new(key: &SigningKey, inputs: Vec<(String, String)>, output: &str) -> Self {
+        let mut signature = Signature {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            inputs,
+            output: digest(output),
+            signature: String::new(),
+        };
+        signature.signature = format!(
+            "ed25519:{}",
+            hex(&key.sign(signature.body().as_bytes()).to_bytes())
+        );
+        signature
+    }
+
+    /// The lines covered by the signature
+    fn body(&self) -> String {
+        let mut body = format!("{HEADER}\ncrate_version {}\n", self.crate_version);
+        for (source, digest) in &self.inputs {
+            // Sources go last on their line, as they may contain spaces
+            body.push_str(&format!("input {digest} {source}\n"));
+        }
+        body.push_str(&format!("output {}\n", self.output));
+        body
+    }
+
+    pub fn to_file(&self) -> String {
+        format!("{}{}\n", self.body(), self.signature)
+    }
+
+    pub fn from_file(file: &str) -> Result<Self, RoverErr> {
+        let mut lines = file.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(RoverErr::SignatureParse("header"));
+        }
+        let crate_version = lines
+            .next()
+            .and_then(|line| line.strip_prefix("crate_version "))
+            .ok_or(RoverErr::SignatureParse("crate_version"))?
+            .to_string();
+
+        let mut inputs = Vec::new();
+        let mut line = lines.next();
+        while let Some(input) = line.and_then(|line| line.strip_prefix("input ")) {
+            let (digest, source) = input
+                .split_once(' ')
+                .ok_or(RoverErr::SignatureParse("input"))?;
+            inputs.push((source.to_string(), digest.to_string()));
+            line = lines.next();
+        }
+
+        let output = line
+            .and_then(|line| line.strip_prefix("output "))
+            .ok_or(RoverErr::SignatureParse("output"))?
+            .to_string();
+        let signature = lines
+            .next()
+            .filter(|line| line.starts_with("ed25519:"))
+            .ok_or(RoverErr::SignatureParse("signature"))?
+            .to_string();
+
+        Ok(Signature {
+            crate_version,
+            inputs,
+            output,
+            signature,
+        })
+    }
+
+    /// Check the signature was made with the private half of the key, and covers the output and
+    /// the inputs, which must be given in the order they were signed
+    pub fn verify(
+        &self,
+        key: &VerifyingKey,
+        output: &str,
+        inputs: &[String],
+    ) -> Result<(), RoverErr> {
+        let signature = self
+            .signature
+            .strip_prefix("ed25519:")
+            .and_then(unhex)
+            .and_then(|bytes| ed25519_dalek::Signature::from_slice(&bytes).ok())
+            .ok_or(RoverErr::SignatureParse("signature"))?;
+        if key
+            .verify_strict(self.body().as_bytes(), &signature)
+            .is_err()
+        {
+            return Err(RoverErr::BadSignature(
+                "the signature was not made with this key",
+            ));
+        }
+        if self.output != digest(output) {
+            return Err(RoverErr::BadSignature("the output has been changed"));
+        }
+        if inputs.len() != self.inputs.len()
+            || inputs
+                .iter()
+                .zip(&self.inputs)
+                .any(|(input, (_, signed))| digest(input) != *signed)
+        {
+            return Err(RoverErr::BadSignature(
+                "the output was not produced from these inputs",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod sign_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod digest {
+        use super::digest;
+
+        #[test]
+        fn known_values() {
+            assert_eq!(
+                digest(""),
+                "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            );
+            assert_eq!(
+                digest("abc"),
+                "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod keys {
+        use ed25519_dalek::{
+            pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey, EncodePublicKey},
+            SigningKey,
+        };
+
+        use super::{signing_key, verifying_key};
+        use crate::enums::RoverErr;
+
+        #[test]
+        fn valid_input() {
+            let key = SigningKey::from_bytes(&[7; 32]);
+            let private = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+            let public = key
+                .verifying_key()
+                .to_public_key_pem(LineEnding::LF)
+                .unwrap();
+            assert_eq!(signing_key(&private).unwrap(), key);
+            assert_eq!(verifying_key(&public).unwrap(), key.verifying_key());
+        }
+
+        #[test]
+        fn invalid_input() {
+            let key = SigningKey::from_bytes(&[7; 32]);
+            let public = key
+                .verifying_key()
+                .to_public_key_pem(LineEnding::LF)
+                .unwrap();
+            assert!(matches!(signing_key(&public), Err(RoverErr::InvalidKey(_))));
+            assert!(matches!(
+                verifying_key("secret"),
+                Err(RoverErr::InvalidKey(_))
+            ));
+        }
+    }
+
+    #[cfg(test)]
+    mod verify {
+        use ed25519_dalek::SigningKey;
+
+        use super::{digest, Signature};
+        use crate::enums::RoverErr;
+
+        #[test]
+        fn round_trip() {
+            let key = SigningKey::from_bytes(&[7; 32]);
+            let input = "5 5\n1 2 N\nLMLMLMLMM".to_string();
+            let signature = Signature::new(
+                &key,
+                vec![("mission one.txt".to_string(), digest(&input))],
+                "1 3 N",
+            );
+            let signature = Signature::from_file(&signature.to_file()).unwrap();
+            assert_eq!(signature.inputs[0].0, "mission one.txt");
+            assert!(signature
+                .verify(&key.verifying_key(), "1 3 N", &[input])
+                .is_ok());
+        }
+
+        #[test]
+        fn tampered() {
+            let key = SigningKey::from_bytes(&[7; 32]);
+            let other = SigningKey::from_bytes(&[8; 32]);
+            let signature = Signature::new(&key, vec![("a".to_string(), digest("5 5"))], "1 3 N");
+            for (key, output, inputs) in [
+                (other.verifying_key(), "1 3 N", vec!["5 5".to_string()]),
+                (key.verifying_key(), "1 4 N", vec!["5 5".to_string()]),
+                (key.verifying_key(), "1 3 N", vec!["4 4".to_string()]),
+                (key.verifying_key(), "1 3 N", Vec::new()),
+            ] {
+                assert!(matches!(
+                    signature.verify(&key, output, &inputs),
+                    Err(RoverErr::BadSignature(_))
+                ));
+            }
+
+            // Changing what the signature covers breaks it, even if the digests are updated
+            let mut forged = Signature::from_file(&signature.to_file()).unwrap();
+            forged.output = digest("1 4 N");
+            assert!(matches!(
+                forged.verify(&key.verifying_key(), "1 4 N", &["5 5".to_string()]),
+                Err(RoverErr::BadSignature(_))
+            ));
+        }
+    }
+}
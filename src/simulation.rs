@@ -0,0 +1,400 @@
+use std::{mem, time::Duration};
+
+use crate::{
+    budget::Budget,
+    diagnostics::{Diagnostics, Policy, Warning},
+    enums::{Autonomy, Generator, InstructionLocale, Recovery, RoverErr},
+    plateau::Plateau,
+    position::Position,
+    rng::Rng,
+    rover::{Rover, RoverControlSatellite, RoverEntry},
+    stochastic::StochasticModel,
+    terrain::CellEvent,
+    trace::Trace,
+    world::World,
+};
+
+/// Runs missions in isolation from each other
+///
+/// Every run gets its own world, diagnostics and trace, so one simulation can be shared between
+/// threads and run many missions at once.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Simulation {
+    pub unbounded: bool,
+    pub locale: InstructionLocale,
+    pub safety_margin: usize,
+    pub craters: Vec<Position>,
+    pub tags: Vec<(Position, String)>,
+    pub lost_rovers: bool,
+    /// The most instructions a single run may execute, across all of its rovers
+    pub instruction_budget: Option<usize>,
+    /// The longest a single run may execute for
+    pub time_budget: Option<Duration>,
+    /// How likely things are to go wrong, drawn from the world's generator
+    pub model: StochasticModel,
+    /// The seed of the world's generator, every run starts from it
+    pub seed: u64,
+    pub generator: Generator,
+    /// Whether rovers refuse fatal moves, and what they do instead
+    pub autonomy: Autonomy,
+    pub recovery: Recovery,
+    /// How seriously each kind of warning is taken, denied warnings fail the run
+    pub policy: Policy,
+}
+
+/// Everything a single run of a mission produced
+#[derive(Debug)]
+pub struct Outcome {
+    pub result: Result<Vec<Rover>, RoverErr>,
+    /// Warnings are collected even if the mission failed
+    pub warnings: Vec<Warning>,
+    pub world: World,
+}
+
+impl Outcome {
+    /// The outcome of a run, which fails once it has executed if the policy denies any warning
+    fn new(result: Result<Vec<Rover>, RoverErr>, diagnostics: Diagnostics, world: World) -> Self {
+        let result = match diagnostics.denied() {
+            0 => result,
+            denied => result.and(Err(RoverErr::DeniedWarnings(denied))),
+        };
+        Outcome {
+            result,
+            warnings: diagnostics.into_warnings(),
+            world,
+        }
+    }
+}
+
+/// The bounderies a run's rovers ran on, and the simulation that ran them
+type Context = (Position, Simulation);
+
+/// A rover block of an earlier run, and everything executing it produced
+#[derive(Debug, Clone)]
+struct CachedRover {
+    entry: RoverEntry,
+    rover: Rover,
+    warnings: Vec<Warning>,
+    events: Vec<CellEvent>,
+}
+
+/// The rovers of the last run of a mission, so a run of an edited mission only executes the
+/// rover blocks that changed
+#[derive(Debug, Default, Clone)]
+pub struct RunCache {
+    /// The plateau and settings the cached rovers ran with, the cache is emptied when they change
+    context: Option<Context>,
+    /// The rovers by their index in the mission, `None` for those that failed
+    rovers: Vec<Option<CachedRover>>,
+    /// How many rovers the last run took from the cache
+    pub reused: usize,
+}
+
+impl Simulation {
+    /// Whether each rover runs the same however the others run, nothing is shared between them
+    /// through scents, the world's generator or a budget
+    pub fn isolates_rovers(&self) -> bool {
+        !self.lost_rovers
+            && self.model.is_deterministic()
+            && self.instruction_budget.is_none()
+            && self.time_budget.is_none()
+    }
+
+    /// The simulation with the craters and tags of world files added to its own
+    pub fn with_plateaus(&self, plateaus: &[Plateau]) -> Simulation {
+        let mut simulation = self.clone();
+        for plateau in plateaus {
+            simulation.craters.extend(plateau.craters());
+            simulation.tags.extend(plateau.tags());
+        }
+        simulation
+    }
+
+    /// A fresh world for a run
+    pub fn world(&self) -> World {
+        let mut world = World::new(self.craters.clone(), self.lost_rovers);
+        world.add_tags(self.tags.iter().cloned());
+        world.unbounded = self.unbounded;
+        world.safety_margin = self.safety_margin;
+        world.model = self.model;
+        world.rng = Rng::with_generator(self.generator, self.seed);
+        world.autonomy = self.autonomy;
+        world.recovery = self.recovery;
+        world
+    }
+
+    /// Fresh diagnostics for a run, taking warnings as seriously as the policy says
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics::default().with_policy(self.policy.clone())
+    }
+
+    /// A fresh budget for a run, its time limit starts now
+    pub fn budget(&self) -> Budget {
+        Budget::new(self.instruction_budget, self.time_budget)
+    }
+
+    /// Parse and execute an instructions message
+    pub fn run(&self, message: &str) -> Outcome {
+        let mut world = self.world();
+        let mut diagnostics = self.diagnostics();
+
+        let result = RoverControlSatellite::parse_incoming_message(
+            message.to_string(),
+            self.locale,
+            &mut diagnostics,
+        )
+        .and_then(|mission| {
+            RoverControlSatellite::execute_mission(
+                &mission,
+                &mut world,
+                &mut self.budget(),
+                &mut diagnostics,
+                &mut Trace::default(),
+            )
+        });
+
+        Outcome::new(result, diagnostics, world)
+    }
+
+    /// Parse and execute an instructions message, reusing the rovers of the cache's last run
+    /// whose blocks are unchanged
+    ///
+    /// The outcome is the same as that of `run`. When the rovers are not isolated from each
+    /// other every rover is executed, and the cache is left empty.
+    pub fn run_incremental(&self, message: &str, cache: &mut RunCache) -> Outcome {
+        let previous = mem::take(&mut cache.rovers);
+        cache.reused = 0;
+        if !self.isolates_rovers() {
+            return self.run(message);
+        }
+
+        let mut world = self.world();
+        let mut diagnostics = self.diagnostics();
+        let mission = match RoverControlSatellite::parse_incoming_message(
+            message.to_string(),
+            self.locale,
+            &mut diagnostics,
+        ) {
+            Ok(mission) => mission,
+            Err(err) => return Outcome::new(Err(err), diagnostics, world),
+        };
+
+        let context = (mission.bounderies, self.clone());
+        let mut previous = match cache.context.as_ref() == Some(&context) {
+            true => previous,
+            false => Vec::new(),
+        };
+        cache.context = Some(context);
+        cache.rovers = vec![None; mission.rovers.len()];
+        world.bounderies = mission.bounderies;
+
+        for index in mission.execution_order() {
+            let entry = &mission.rovers[index];
+            let reused = previous
+                .get_mut(index)
+                .and_then(Option::take)
+                .filter(|cached| cached.entry == *entry);
+
+            let cached = match reused {
+                Some(cached) => {
+                    cache.reused += 1;
+                    cached
+                }
+                None => {
+                    // Each rover runs in a world of its own, so its warnings and events can be kept
+                    let mut own_world = self.world();
+                    own_world.bounderies = mission.bounderies;
+                    let mut own_diagnostics = Diagnostics::default();
+                    // Isolated rovers never have a budget
+                    let result = mission.launch(index).execute_instructions(
+                        &entry.1,
+                        &mut own_world,
+                        &mut Budget::default(),
+                        &mut own_diagnostics,
+                        &mut Trace::default(),
+                    );
+                    match result {
+                        Ok(rover) => CachedRover {
+                            entry: entry.clone(),
+                            rover,
+                            warnings: own_diagnostics.into_warnings(),
+                            events: own_world.events,
+                        },
+                        // A failed rover is not cached, and stops the mission as in `run`
+                        Err(err) => {
+                            diagnostics.merge(own_diagnostics);
+                            world.events.extend(own_world.events);
+                            return Outcome::new(Err(err), diagnostics, world);
+                        }
+                    }
+                }
+            };
+
+            for warning in &cached.warnings {
+                diagnostics.warn(warning.clone());
+            }
+            world.events.extend(cached.events.iter().cloned());
+            world.rovers.push(cached.rover.clone());
+            cache.rovers[index] = Some(cached);
+        }
+        world.rovers.sort_by_key(|rover| rover.id);
+
+        Outcome::new(Ok(world.rovers.clone()), diagnostics, world)
+    }
+}
+
+#[cfg(test)]
+mod simulation_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod run {
+        use std::thread;
+
+        use super::{RunCache, Simulation};
+        use crate::{
+            diagnostics::{Level, Policy, WarningCode},
+            enums::{Autonomy, InstructionLocale, Recovery, RoverErr},
+            position::Position,
+        };
+
+        fn is_send_and_sync<T: Send + Sync>() {}
+
+        #[test]
+        fn send_and_sync() {
+            is_send_and_sync::<Simulation>();
+        }
+
+        /// A different mission for every seed, each rover falls off the plateau on odd seeds
+        fn mission(seed: usize) -> String {
+            format!(
+                "5 5\n{} {} N\nMRML{}\n0 0 E\n{}",
+                seed % 4,
+                seed % 3,
+                "M".repeat(seed % 7),
+                "M".repeat(seed % 5)
+            )
+        }
+
+        #[test]
+        fn incremental_runs() {
+            let simulation = Simulation {
+                craters: vec![Position::new(3, 3)],
+                tags: vec![(Position::new(1, 3), "science".to_string())],
+                safety_margin: 1,
+                ..Simulation::default()
+            };
+            let mut cache = RunCache::default();
+            let original = "5 5\n1 2 N\nLMLMLMLMM\n3 3 E\nMMRMMRMRRM\n0 0 N\nMM";
+            let edited = "5 5\n1 2 N\nLMLMLMLMM\n3 3 E\nMMRMMRMRRM\n0 0 N\nMMRM";
+
+            for (message, reused) in [(original, 0), (original, 3), (edited, 2)] {
+                let outcome = simulation.run_incremental(message, &mut cache);
+                let expected = simulation.run(message);
+                assert_eq!(cache.reused, reused);
+                assert_eq!(
+                    outcome.result.map_err(|err| err.to_string()),
+                    expected.result.map_err(|err| err.to_string())
+                );
+                assert_eq!(outcome.warnings, expected.warnings);
+                assert_eq!(outcome.world, expected.world);
+            }
+
+            // Rovers are run again once the plateau or a setting changes, or something is shared
+            // between them
+            simulation.run_incremental(&edited.replacen("5 5", "6 6", 1), &mut cache);
+            assert_eq!(cache.reused, 0);
+            for changed in [
+                Simulation {
+                    unbounded: true,
+                    safety_margin: 0,
+                    ..simulation.clone()
+                },
+                Simulation {
+                    safety_margin: 2,
+                    ..simulation.clone()
+                },
+                Simulation {
+                    autonomy: Autonomy::Safe,
+                    ..simulation.clone()
+                },
+                Simulation {
+                    recovery: Recovery::TurnRight,
+                    ..simulation.clone()
+                },
+                Simulation {
+                    locale: InstructionLocale::French,
+                    ..simulation.clone()
+                },
+            ] {
+                simulation.run_incremental(edited, &mut cache);
+                let outcome = changed.run_incremental(edited, &mut cache);
+                assert_eq!(cache.reused, 0);
+                assert_eq!(outcome.warnings, changed.run(edited).warnings);
+            }
+            let shared = Simulation {
+                lost_rovers: true,
+                ..simulation
+            };
+            shared.run_incremental(edited, &mut cache);
+            shared.run_incremental(edited, &mut cache);
+            assert_eq!(cache.reused, 0);
+        }
+
+        #[test]
+        fn policy() {
+            let mut policy = Policy::default();
+            policy.set(WarningCode::NoOpTurns, Level::Allow);
+            policy.set(WarningCode::EndedOnBoundery, Level::Deny);
+            let simulation = Simulation {
+                policy,
+                ..Simulation::default()
+            };
+
+            for outcome in [
+                simulation.run("2 2\n1 1 N\nLRM"),
+                simulation.run_incremental("2 2\n1 1 N\nLRM", &mut RunCache::default()),
+            ] {
+                assert!(matches!(outcome.result, Err(RoverErr::DeniedWarnings(1))));
+                assert_eq!(outcome.warnings.len(), 1);
+                assert_eq!(outcome.world.rovers.len(), 1);
+            }
+        }
+
+        #[test]
+        fn concurrent_runs() {
+            let simulation = Simulation {
+                craters: vec![Position::new(3, 3)],
+                lost_rovers: true,
+                safety_margin: 1,
+                ..Simulation::default()
+            };
+            let expected = (0..64)
+                .map(|seed| simulation.run(&mission(seed)))
+                .collect::<Vec<_>>();
+
+            thread::scope(|scope| {
+                let handles = (0..16)
+                    .map(|_| {
+                        scope.spawn(|| {
+                            (0..64)
+                                .map(|seed| simulation.run(&mission(seed)))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                for handle in handles {
+                    for (outcome, expected) in handle.join().unwrap().iter().zip(&expected) {
+                        assert_eq!(
+                            outcome.result.as_ref().map_err(ToString::to_string),
+                            expected.result.as_ref().map_err(ToString::to_string)
+                        );
+                        assert_eq!(outcome.warnings, expected.warnings);
+                        assert_eq!(outcome.world, expected.world);
+                    }
+                }
+            });
+        }
+    }
+}
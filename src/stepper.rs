@@ -0,0 +1,238 @@
+use crate::{
+    budget::{Budget, Limit},
+    diagnostics::Diagnostics,
+    enums::RoverErr,
+    rover::{Mission, Rover},
+    simulation::Simulation,
+    trace::Trace,
+    world::World,
+};
+
+/// How many instructions apart the stepper keeps snapshots of the mission
+pub const SNAPSHOT_INTERVAL: usize = 64;
+
+/// The state of a mission part way through, from which it can carry on executing
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    /// How many instructions have been executed, across all rovers
+    pub tick: usize,
+    /// The world, holding every rover that has finished executing
+    pub world: World,
+    pub diagnostics: Diagnostics,
+    /// How many rovers of the execution order have finished
+    finished: usize,
+    /// The rover part way through its instructions, and the index of its next instruction
+    pub running: Option<(Rover, usize)>,
+    /// Whether every rover has finished, or the mission failed
+    pub done: bool,
+    /// Why the mission failed, if it did
+    pub error: Option<String>,
+}
+
+/// Steps through a mission to any tick, backwards as well as forwards
+///
+/// A snapshot of the mission is kept every `interval` instructions as they are reached, so a
+/// jump only executes the instructions since the closest snapshot before it.
+#[derive(Debug)]
+pub struct Stepper {
+    mission: Mission,
+    interval: usize,
+    /// The states at every multiple of the interval reached so far, starting with tick zero
+    snapshots: Vec<Checkpoint>,
+}
+
+impl Stepper {
+    /// Step through a mission, starting from the diagnostics it was parsed with
+    pub fn new(
+        mission: Mission,
+        simulation: &Simulation,
+        diagnostics: Diagnostics,
+        interval: usize,
+    ) -> Self {
+        let start = Checkpoint {
+            tick: 0,
+            world: simulation.world(),
+            diagnostics,
+            finished: 0,
+            running: None,
+            done: false,
+            error: None,
+        };
+        let mut stepper = Stepper {
+            mission,
+            interval: interval.max(1),
+            snapshots: Vec::new(),
+        };
+        // Rovers without instructions finish without a tick passing
+        stepper.snapshots.push(stepper.advance(start, 0));
+        stepper
+    }
+
+    pub fn mission(&self) -> &Mission {
+        &self.mission
+    }
+
+    /// The state after `tick` instructions, or at the end of the mission if it finishes first
+    pub fn at(&mut self, tick: usize) -> Checkpoint {
+        let index = tick / self.interval;
+        while self.snapshots.len() <= index {
+            let last = self.snapshots[self.snapshots.len() - 1].clone();
+            if last.done {
+                break;
+            }
+            let next = self.advance(last, self.interval);
+            self.snapshots.push(next);
+        }
+
+        let snapshot = self.snapshots[index.min(self.snapshots.len() - 1)].clone();
+        match snapshot.done {
+            true => snapshot,
+            false => {
+                let ticks = tick - snapshot.tick;
+                self.advance(snapshot, ticks)
+            }
+        }
+    }
+
+    /// The last tick before `tick` whose instruction raised a warning
+    pub fn previous_violation(&mut self, tick: usize) -> Option<usize> {
+        let mut warnings = |tick| self.at(tick).diagnostics.warnings().len();
+        let target = warnings(tick.checked_sub(1)?);
+        if target == warnings(0) {
+            return None;
+        }
+
+        // The warnings only ever grow, so search for the first tick with as many
+        let (mut low, mut high) = (1, tick - 1);
+        while low < high {
+            let middle = (low + high) / 2;
+            match warnings(middle) < target {
+                true => low = middle + 1,
+                false => high = middle,
+            }
+        }
+        Some(low)
+    }
+
+    /// Execute up to `ticks` more instructions of the mission
+    fn advance(&self, mut checkpoint: Checkpoint, ticks: usize) -> Checkpoint {
+        let order = self.mission.execution_order();
+        checkpoint.world.bounderies = self.mission.bounderies;
+        let mut budget = Budget::new(Some(ticks), None);
+
+        while let Some(&index) = order.get(checkpoint.finished) {
+            let (rover, start) = checkpoint
+                .running
+                .take()
+                .unwrap_or_else(|| (self.mission.launch(index), 0));
+
+            let result = rover.execute_instructions_from(
+                &self.mission.rovers[index].1,
+                start,
+                &mut checkpoint.world,
+                &mut budget,
+                &mut checkpoint.diagnostics,
+                &mut Trace::default(),
+            );
+            match result {
+                Ok(rover) => {
+                    checkpoint.world.rovers.push(rover);
+                    checkpoint.finished += 1;
+                }
+                Err(RoverErr::BudgetExceeded(rover, next, Limit::Instructions)) => {
+                    checkpoint.running = Some((rover, next));
+                    break;
+                }
+                Err(err) => {
+                    checkpoint.error = Some(err.to_string());
+                    break;
+                }
+            }
+        }
+
+        checkpoint.tick += ticks - budget.remaining_instructions();
+        checkpoint.done = checkpoint.running.is_none();
+        if checkpoint.done {
+            checkpoint.world.rovers.sort_by_key(|rover| rover.id);
+        }
+        checkpoint
+    }
+}
+
+#[cfg(test)]
+mod stepper_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod at {
+        use super::Stepper;
+        use crate::{
+            rover::RoverControlSatellite, simulation::Simulation, stochastic::StochasticModel,
+        };
+
+        const MISSION: &str = "5 5\n1 2 N\nLMLMLMLMM\n3 3 E priority=1\nMMRMMRMRRM";
+
+        fn stepper(simulation: &Simulation, interval: usize) -> Stepper {
+            let mut diagnostics = simulation.diagnostics();
+            let mission = RoverControlSatellite::parse_incoming_message(
+                MISSION.to_string(),
+                simulation.locale,
+                &mut diagnostics,
+            )
+            .unwrap();
+            Stepper::new(mission, simulation, diagnostics, interval)
+        }
+
+        #[test]
+        fn matches_whole_run() {
+            let simulation = Simulation {
+                model: StochasticModel {
+                    slippage: 0.3,
+                    ..StochasticModel::default()
+                },
+                lost_rovers: true,
+                seed: 7,
+                ..Simulation::default()
+            };
+            let outcome = simulation.run(MISSION);
+            // Jumping backwards and forwards gives the same states as stepping one at a time
+            let mut reference = stepper(&simulation, 1);
+            let states = (0..=19).map(|tick| reference.at(tick)).collect::<Vec<_>>();
+            let mut stepper = stepper(&simulation, 4);
+            for tick in [19, 3, 12, 0, 8, 15] {
+                let state = stepper.at(tick);
+                assert_eq!(state.tick, states[tick].tick);
+                assert_eq!(state.world, states[tick].world);
+                assert_eq!(state.running, states[tick].running);
+            }
+
+            let end = stepper.at(100);
+            assert!(end.done);
+            // A lost rover skips the rest of its instructions, so the mission can end early
+            assert_eq!(end.tick, states[19].tick);
+            assert_eq!(end.world.rovers, outcome.result.unwrap());
+            assert_eq!(end.diagnostics.warnings(), outcome.warnings);
+        }
+
+        #[test]
+        fn part_way() {
+            let mut stepper = stepper(&Simulation::default(), 4);
+            let state = stepper.at(12);
+            assert!(!state.done);
+            // The second rover has priority, so executes first
+            assert_eq!(state.world.rovers.len(), 1);
+            assert_eq!(state.world.rovers[0].id, 2);
+            let (rover, next) = state.running.unwrap();
+            assert_eq!((rover.id, next), (1, 2));
+        }
+
+        #[test]
+        fn previous_violation() {
+            // Rover 2 ends on the boundery after its last instruction, tick 10
+            let mut stepper = stepper(&Simulation::default(), 4);
+            assert_eq!(stepper.previous_violation(19), Some(10));
+            assert_eq!(stepper.previous_violation(10), None);
+            assert_eq!(stepper.previous_violation(0), None);
+        }
+    }
+}
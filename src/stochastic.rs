@@ -0,0 +1,116 @@
+use clap::Args;
+
+use crate::rng::Rng;
+
+/// How likely things are to go wrong for a rover, each drawn from the world's generator
+///
+/// Every probability defaults to zero, which leaves the mission deterministic.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Args)]
+pub struct StochasticModel {
+    /// The chance a move slides on by an extra cell.
+    #[arg(long, value_name = "PROBABILITY", default_value_t = 0.0, value_parser = parse_probability)]
+    pub slippage: f64,
+
+    /// The chance a move ends with the rover struck by a hazard, failing the mission, or losing
+    /// the rover with `--lost-rovers`.
+    #[arg(long, value_name = "PROBABILITY", default_value_t = 0.0, value_parser = parse_probability)]
+    pub hazard: f64,
+
+    /// The chance an instruction is lost on its way to the rover, which never executes it.
+    #[arg(long, value_name = "PROBABILITY", default_value_t = 0.0, value_parser = parse_probability)]
+    pub link_loss: f64,
+}
+
+impl StochasticModel {
+    /// Whether nothing random can happen, so every run of a mission is the same
+    pub fn is_deterministic(&self) -> bool {
+        self.slippage == 0.0 && self.hazard == 0.0 && self.link_loss == 0.0
+    }
+
+    pub fn link_lost(&self, rng: &mut Rng) -> bool {
+        rng.chance(self.link_loss)
+    }
+
+    pub fn slips(&self, rng: &mut Rng) -> bool {
+        rng.chance(self.slippage)
+    }
+
+    pub fn strikes(&self, rng: &mut Rng) -> bool {
+        rng.chance(self.hazard)
+    }
+}
+
+/// Parse a probability between zero and one
+pub fn parse_probability(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(probability) if (0.0..=1.0).contains(&probability) => Ok(probability),
+        _ => Err(format!(
+            "expected a probability from 0 to 1, found `{input}`"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod stochastic_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod parse_probability {
+        use super::parse_probability;
+
+        #[test]
+        fn valid_input() {
+            assert_eq!(parse_probability("0.25"), Ok(0.25));
+        }
+
+        #[test]
+        fn out_of_range() {
+            assert!(parse_probability("1.5").is_err());
+            assert!(parse_probability("-0.1").is_err());
+        }
+    }
+
+    #[cfg(test)]
+    mod slips {
+        use super::StochasticModel;
+        use crate::rng::{Rng, RngSource};
+
+        /// Replays a fixed sequence of numbers
+        #[derive(Debug, Clone)]
+        struct Sequence(Vec<u64>);
+
+        impl RngSource for Sequence {
+            fn next_u64(&mut self) -> u64 {
+                self.0.remove(0)
+            }
+
+            fn seed(&self) -> u64 {
+                0
+            }
+
+            fn name(&self) -> &'static str {
+                "sequence"
+            }
+
+            fn state(&self) -> Vec<u64> {
+                self.0.clone()
+            }
+
+            fn boxed_clone(&self) -> Box<dyn RngSource> {
+                Box::new(self.clone())
+            }
+        }
+
+        #[test]
+        fn injected_source() {
+            let model = StochasticModel {
+                slippage: 0.5,
+                ..StochasticModel::default()
+            };
+            let mut rng = Rng::from_source(Sequence(vec![0, u64::MAX, 1]));
+            assert!(model.slips(&mut rng));
+            assert!(!model.slips(&mut rng));
+            assert!(model.slips(&mut rng));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt::{self, Display};
+
+use crate::{position::Position, rover::Rover};
+
+/// Figures for a whole fleet of rovers, a quick health check before looking at each rover
+///
+/// A rover is counted once for each of its flags, and as active if it has none.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub rovers: usize,
+    pub active: usize,
+    pub halted: usize,
+    pub lost: usize,
+    pub degraded: usize,
+    /// The faults that ended the mission early, the fleet is then the rovers that finished
+    pub faults: usize,
+    /// The fewest, most and mean cells moved by a rover, counting only the moves it made
+    pub path_length: (usize, usize, f64),
+    /// The corners of the smallest box holding every final position, `None` without rovers
+    pub bounds: Option<(Position, Position)>,
+}
+
+impl Summary {
+    pub fn new(rovers: &[Rover], faults: usize) -> Self {
+        let count = |flag: fn(&Rover) -> bool| rovers.iter().filter(|rover| flag(rover)).count();
+        let odometers = rovers.iter().map(|rover| rover.odometer);
+        let bounds = rovers.iter().map(|rover| rover.pose.position).fold(
+            None,
+            |bounds: Option<(Position, Position)>, position| {
+                Some(match bounds {
+                    None => (position, position),
+                    Some((low, high)) => (
+                        Position::new(low.x.min(position.x), low.y.min(position.y)),
+                        Position::new(high.x.max(position.x), high.y.max(position.y)),
+                    ),
+                })
+            },
+        );
+
+        Summary {
+            rovers: rovers.len(),
+            active: count(|rover| !rover.halted && !rover.lost && !rover.degraded),
+            halted: count(|rover| rover.halted),
+            lost: count(|rover| rover.lost),
+            degraded: count(|rover| rover.degraded),
+            faults,
+            path_length: (
+                odometers.clone().min().unwrap_or_default(),
+                odometers.clone().max().unwrap_or_default(),
+                odometers.sum::<usize>() as f64 / rovers.len().max(1) as f64,
+            ),
+            bounds,
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Rovers: {} - Active: {}, Halted: {}, Lost: {}, Degraded: {}, Faults: {}",
+            self.rovers, self.active, self.halted, self.lost, self.degraded, self.faults
+        )?;
+        let (min, max, mean) = self.path_length;
+        write!(f, "Path Length - Min: {min}, Max: {max}, Mean: {mean:.1}")?;
+        if let Some((low, high)) = self.bounds {
+            write!(
+                f,
+                "\nFinal Positions - From: ({}, {}), To: ({}, {})",
+                low.x, low.y, high.x, high.y
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod summary_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod new {
+        use super::Summary;
+        use crate::{
+            enums::Direction,
+            position::{Pose, Position},
+            rover::Rover,
+        };
+
+        #[test]
+        fn valid_input() {
+            let rovers = [
+                Rover {
+                    odometer: 3,
+                    ..Rover::new(1, Pose::new(Position::new(1, 4), Direction::North))
+                },
+                Rover {
+                    odometer: 8,
+                    lost: true,
+                    ..Rover::new(2, Pose::new(Position::new(5, 1), Direction::East))
+                },
+                Rover {
+                    odometer: 4,
+                    halted: true,
+                    ..Rover::new(3, Pose::new(Position::new(2, 2), Direction::South))
+                },
+            ];
+            let summary = Summary::new(&rovers, 0);
+            assert_eq!(
+                summary.to_string(),
+                "Rovers: 3 - Active: 1, Halted: 1, Lost: 1, Degraded: 0, Faults: 0\n\
+                 Path Length - Min: 3, Max: 8, Mean: 5.0\n\
+                 Final Positions - From: (1, 1), To: (5, 4)"
+            );
+        }
+
+        #[test]
+        fn counts_each_flag() {
+            let rovers = [Rover {
+                halted: true,
+                lost: true,
+                degraded: true,
+                ..Rover::new(1, Pose::new(Position::new(0, 0), Direction::North))
+            }];
+            let summary = Summary::new(&rovers, 1);
+            assert_eq!(
+                (
+                    summary.active,
+                    summary.halted,
+                    summary.lost,
+                    summary.degraded
+                ),
+                (0, 1, 1, 1)
+            );
+            assert_eq!(summary.faults, 1);
+        }
+
+        #[test]
+        fn no_rovers() {
+            assert_eq!(
+                Summary::new(&[], 0).to_string(),
+                "Rovers: 0 - Active: 0, Halted: 0, Lost: 0, Degraded: 0, Faults: 0\n\
+                 Path Length - Min: 0, Max: 0, Mean: 0.0"
+            );
+        }
+    }
+}
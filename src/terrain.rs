@@ -0,0 +1,79 @@
+use std::fmt::{self, Display};
+
+use crate::position::Position;
+
+/// A rover moving onto a tagged cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellEvent {
+    pub rover: usize,
+    /// The index of the instruction that moved the rover onto the cell
+    pub instruction: usize,
+    pub position: Position,
+    pub tag: String,
+}
+
+impl Display for CellEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Rover Event 🏷️ - Rover {} entered ({}, {}), tagged {}: Instruction {}, At Line: {}",
+            self.rover,
+            self.position.x,
+            self.position.y,
+            self.tag,
+            self.instruction + 1,
+            (self.rover * 2) + 1
+        )
+    }
+}
+
+/// The tag of the cells rovers can be serviced on
+pub const BASE: &str = "base";
+
+/// Whether a tag can be written in traces and on the command line, e.g. `science-target`
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parse a tagged cell given as `X,Y=TAG`
+pub fn parse_tagged_cell(input: &str) -> Result<(Position, String), String> {
+    let (cell, tag) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected `X,Y=TAG`, found `{input}`"))?;
+    if !is_valid_tag(tag) {
+        return Err(format!(
+            "invalid tag `{tag}`, tags are letters, digits, `-` and `_`"
+        ));
+    }
+
+    Ok((crate::world::parse_cell(cell)?, tag.to_string()))
+}
+
+#[cfg(test)]
+mod terrain_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod parse_tagged_cell {
+        use super::parse_tagged_cell;
+        use crate::position::Position;
+
+        #[test]
+        fn valid_input() {
+            assert_eq!(
+                parse_tagged_cell("2,-3=science-target"),
+                Ok((Position::new(2, -3), "science-target".to_string()))
+            );
+        }
+
+        #[test]
+        fn invalid_tag() {
+            assert!(parse_tagged_cell("2,3=very slippery").is_err());
+            assert!(parse_tagged_cell("2,3=").is_err());
+            assert!(parse_tagged_cell("2,3").is_err());
+        }
+    }
+}
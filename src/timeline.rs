@@ -0,0 +1,219 @@
+use crate::{
+    enums::SchemaVersion,
+    position::Pose,
+    rover::{Mission, Rover},
+    trace::Trace,
+};
+
+/// Every rover's position at every tick of a global clock
+///
+/// All rovers advance one instruction per tick, a rover that has finished its instructions stays
+/// where it is. Tick zero holds the starting positions.
+#[derive(Debug, PartialEq)]
+pub struct Timeline {
+    pub ticks: Vec<Vec<Rover>>,
+}
+
+impl Timeline {
+    /// Lay out where the rovers of a mission went, from an unfiltered trace of its execution
+    ///
+    /// A rover stays where it was on ticks without an event, such as after it halted, and rovers
+    /// that never executed stay where they started.
+    pub fn new(mission: &Mission, trace: &Trace) -> Self {
+        let length = mission
+            .rovers
+            .iter()
+            .map(|(_, instructions, _)| instructions.len())
+            .max()
+            .unwrap_or_default();
+
+        // The pose each rover took on at each tick, if it was traced
+        let mut paths = mission
+            .rovers
+            .iter()
+            .map(|(pose, _, _)| {
+                let mut path = vec![None; length + 1];
+                path[0] = Some(*pose);
+                path
+            })
+            .collect::<Vec<Vec<Option<Pose>>>>();
+        for event in trace.events() {
+            // The ID of the rover starts from one
+            if let Some(pose) = event
+                .rover
+                .checked_sub(1)
+                .and_then(|index| paths.get_mut(index))
+                .and_then(|path| path.get_mut(event.tick))
+            {
+                *pose = Some(event.pose);
+            }
+        }
+
+        let mut rovers = mission
+            .rovers
+            .iter()
+            .enumerate()
+            .map(|(index, (pose, _, _))| Rover::new(index + 1, *pose))
+            .collect::<Vec<Rover>>();
+        let ticks = (0..=length)
+            .map(|tick| {
+                for (rover, path) in rovers.iter_mut().zip(&paths) {
+                    if let Some(pose) = path[tick] {
+                        rover.pose = pose;
+                    }
+                }
+                rovers.clone()
+            })
+            .collect();
+
+        Timeline { ticks }
+    }
+
+    /// Format the timeline as a long format CSV, one row per rover per tick
+    ///
+    /// From version 2 every row starts with the schema version.
+    pub fn to_csv(&self, version: SchemaVersion) -> String {
+        let prefix = match version {
+            SchemaVersion::V1 => String::new(),
+            SchemaVersion::V2 => format!("{},", version.number()),
+        };
+
+        let mut csv = match version {
+            SchemaVersion::V1 => String::from("tick,rover,x,y,facing"),
+            SchemaVersion::V2 => String::from("schema_version,tick,rover,x,y,facing"),
+        };
+        for (tick, rovers) in self.ticks.iter().enumerate() {
+            for rover in rovers {
+                csv.push_str(&format!(
+                    "\n{}{},{},{},{},{}",
+                    prefix,
+                    tick,
+                    rover.id,
+                    rover.pose.position.x,
+                    rover.pose.position.y,
+                    rover.pose.facing
+                ));
+            }
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod timeline_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod timeline {
+        use super::Timeline;
+        use crate::{
+            budget::Budget,
+            diagnostics::Diagnostics,
+            enums::{Direction, Instruction, SchemaVersion},
+            position::{Pose, Position},
+            rover::{Attributes, Mission, RoverControlSatellite},
+            trace::{Trace, TraceFilter},
+            world::World,
+        };
+
+        /// Trace every event of a mission, up to where it failed if it did
+        fn traced(mission: &Mission) -> Trace {
+            let mut trace = Trace::new(TraceFilter::default());
+            let _ = RoverControlSatellite::execute_mission(
+                mission,
+                &mut World::default(),
+                &mut Budget::default(),
+                &mut Diagnostics::default(),
+                &mut trace,
+            );
+            trace
+        }
+
+        #[test]
+        fn rovers_of_different_lengths() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(0, 0), Direction::North),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(2, 2), Direction::East),
+                        vec![Instruction::Move, Instruction::Left],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            assert_eq!(
+                Timeline::new(&mission, &traced(&mission)).to_csv(SchemaVersion::V1),
+                "tick,rover,x,y,facing\n\
+                 0,1,0,0,N\n\
+                 0,2,2,2,E\n\
+                 1,1,0,1,N\n\
+                 1,2,3,2,E\n\
+                 2,1,0,1,N\n\
+                 2,2,3,2,N"
+            );
+        }
+
+        #[test]
+        fn follows_execution() {
+            let mission = Mission {
+                bounderies: Position::new(1, 1),
+                rovers: vec![
+                    (
+                        Pose::new(Position::new(0, 1), Direction::North),
+                        vec![Instruction::Move],
+                        Attributes::default(),
+                    ),
+                    (
+                        Pose::new(Position::new(0, 1), Direction::North),
+                        vec![Instruction::Move, Instruction::Left],
+                        Attributes::default(),
+                    ),
+                ],
+            };
+            let mut trace = Trace::new(TraceFilter::default());
+            RoverControlSatellite::execute_mission(
+                &mission,
+                &mut World::new(Vec::new(), true),
+                &mut Budget::default(),
+                &mut Diagnostics::default(),
+                &mut trace,
+            )
+            .unwrap();
+
+            // The first rover is lost where it was, and its scent stops the second moving
+            assert_eq!(
+                Timeline::new(&mission, &trace).to_csv(SchemaVersion::V1),
+                "tick,rover,x,y,facing\n\
+                 0,1,0,1,N\n\
+                 0,2,0,1,N\n\
+                 1,1,0,1,N\n\
+                 1,2,0,1,N\n\
+                 2,1,0,1,N\n\
+                 2,2,0,1,W"
+            );
+        }
+
+        #[test]
+        fn schema_version() {
+            let mission = Mission {
+                bounderies: Position::new(5, 5),
+                rovers: vec![(
+                    Pose::new(Position::new(0, 0), Direction::North),
+                    vec![Instruction::Move],
+                    Attributes::default(),
+                )],
+            };
+            assert_eq!(
+                Timeline::new(&mission, &traced(&mission)).to_csv(SchemaVersion::V2),
+                "schema_version,tick,rover,x,y,facing\n\
+                 2,0,1,0,0,N\n\
+                 2,1,1,0,1,N"
+            );
+        }
+    }
+}
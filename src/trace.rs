@@ -0,0 +1,327 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::{
+    enums::{Instruction, SchemaVersion},
+    expand::Provenance,
+    position::Pose,
+    rover::Rover,
+};
+
+/// A single executed instruction and the rover's position after it
+#[derive(Debug, PartialEq)]
+pub struct TraceEvent {
+    pub tick: usize,
+    pub rover: usize,
+    /// The executed instruction, or `None` for where the rover started
+    pub instruction: Option<Instruction>,
+    pub pose: Pose,
+    /// Whether the rover was lost by the instruction
+    pub lost: bool,
+    /// The tags of the cell the rover moved onto
+    pub tags: Vec<String>,
+    /// Where the instruction was written, if it is known
+    pub source: Option<Provenance>,
+}
+
+impl TraceEvent {
+    pub fn new(
+        tick: usize,
+        rover: &Rover,
+        instruction: Option<Instruction>,
+        tags: &[&str],
+    ) -> Self {
+        TraceEvent {
+            tick,
+            rover: rover.id,
+            instruction,
+            pose: rover.pose,
+            lost: rover.lost,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            source: None,
+        }
+    }
+}
+
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.tick,
+            self.rover,
+            kind_name(self.instruction.as_ref()),
+            self.pose
+        )?;
+
+        if self.lost {
+            write!(f, " lost")?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, " tags={}", self.tags.join(","))?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, " source={source}")?;
+            if let Some((name, index)) = &source.expansion {
+                write!(f, " macro={name}:{}", index + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The name of an instruction's kind as used in traces and filters, a rover's starting pose is
+/// recorded as `start`
+pub fn kind_name(instruction: Option<&Instruction>) -> &'static str {
+    match instruction {
+        None => "start",
+        Some(Instruction::Move) => "move",
+        Some(Instruction::Left) => "left",
+        Some(Instruction::Right) => "right",
+        Some(Instruction::Halt) => "halt",
+        Some(Instruction::Service) => "service",
+    }
+}
+
+/// Restricts which events are recorded, e.g. `rover=3,kind=move`
+///
+/// Values given for the same key are alternatives, different keys must all match.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TraceFilter {
+    rovers: Vec<usize>,
+    kinds: Vec<Option<Instruction>>,
+}
+
+impl TraceFilter {
+    pub fn matches(&self, rover: usize, instruction: Option<&Instruction>) -> bool {
+        (self.rovers.is_empty() || self.rovers.contains(&rover))
+            && (self.kinds.is_empty() || self.kinds.contains(&instruction.copied()))
+    }
+}
+
+impl FromStr for TraceFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = TraceFilter::default();
+
+        for pair in s.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some(("rover", value)) => filter.rovers.push(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid rover id `{value}`"))?,
+                ),
+                Some(("kind", value)) => filter.kinds.push(
+                    [
+                        None,
+                        Some(Instruction::Move),
+                        Some(Instruction::Left),
+                        Some(Instruction::Right),
+                        Some(Instruction::Halt),
+                        Some(Instruction::Service),
+                    ]
+                    .into_iter()
+                    .find(|instruction| kind_name(instruction.as_ref()) == value)
+                    .ok_or_else(|| format!("unknown instruction kind `{value}`"))?,
+                ),
+                _ => {
+                    return Err(format!(
+                        "expected `rover=<id>` or `kind=<kind>`, found `{pair}`"
+                    ))
+                }
+            }
+        }
+
+        Ok(filter)
+    }
+}
+
+/// Records the events of an execution which pass its filter
+#[derive(Debug, Default)]
+pub struct Trace {
+    filter: Option<TraceFilter>,
+    events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    /// Create a trace that records every event matching the filter
+    pub fn new(filter: TraceFilter) -> Self {
+        Trace {
+            filter: Some(filter),
+            events: Vec::new(),
+        }
+    }
+
+    /// Record the event of the instruction at `index` of the rover's stream, the event is only
+    /// built if it passes the filter
+    ///
+    /// The start is tick zero, so an instruction's event is on the tick after its index, the
+    /// tick the timeline shows its pose on.
+    pub fn record(
+        &mut self,
+        index: usize,
+        rover: &Rover,
+        instruction: &Instruction,
+        tags: &[&str],
+    ) {
+        if self.is_recording(rover.id, Some(instruction)) {
+            self.events
+                .push(TraceEvent::new(index + 1, rover, Some(*instruction), tags))
+        }
+    }
+
+    /// Record where a rover started, before its first instruction
+    pub fn start(&mut self, rover: &Rover) {
+        if self.is_recording(rover.id, None) {
+            self.events.push(TraceEvent::new(0, rover, None, &[]))
+        }
+    }
+
+    /// Whether an event for this rover and instruction would be recorded
+    pub fn is_recording(&self, rover: usize, instruction: Option<&Instruction>) -> bool {
+        self.filter
+            .as_ref()
+            .is_some_and(|filter| filter.matches(rover, instruction))
+    }
+
+    /// Note where each recorded instruction was written, indexed by rover and then instruction
+    pub fn attribute(&mut self, provenance: &[Vec<Provenance>]) {
+        for event in self
+            .events
+            .iter_mut()
+            .filter(|event| event.instruction.is_some())
+        {
+            // The ID of the rover starts from one
+            event.source = event
+                .rover
+                .checked_sub(1)
+                .and_then(|index| provenance.get(index))
+                .zip(event.tick.checked_sub(1))
+                .and_then(|(rover, index)| rover.get(index))
+                .cloned();
+        }
+    }
+
+    /// Keep only the events which pass the filter
+    pub fn retain(&mut self, filter: &TraceFilter) {
+        self.events
+            .retain(|event| filter.matches(event.rover, event.instruction.as_ref()));
+    }
+
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Format the events as a trace file, one per line
+    ///
+    /// From version 2 the events are preceded by a `schema_version <version>` line.
+    pub fn to_file(&self, version: SchemaVersion) -> String {
+        let events = self
+            .events
+            .iter()
+            .map(|event| event.to_string())
+            .collect::<Vec<String>>();
+
+        match version {
+            SchemaVersion::V1 => events.join("\n"),
+            SchemaVersion::V2 => {
+                format!("schema_version {}\n{}", version.number(), events.join("\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod trace_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod trace_filter {
+        use super::TraceFilter;
+        use crate::enums::Instruction;
+
+        #[test]
+        fn valid_input() {
+            let filter: TraceFilter = "rover=3,kind=move".parse().unwrap();
+            assert!(filter.matches(3, Some(&Instruction::Move)));
+            assert!(!filter.matches(3, Some(&Instruction::Left)));
+            assert!(!filter.matches(3, None));
+            assert!(!filter.matches(2, Some(&Instruction::Move)));
+        }
+
+        #[test]
+        fn repeated_key() {
+            let filter: TraceFilter = "rover=1,rover=2".parse().unwrap();
+            assert!(filter.matches(1, Some(&Instruction::Left)));
+            assert!(filter.matches(2, None));
+            assert!(!filter.matches(3, Some(&Instruction::Move)));
+        }
+
+        #[test]
+        fn unknown_key() {
+            let result = "speed=3".parse::<TraceFilter>();
+            assert!(result.is_err());
+        }
+    }
+
+    #[cfg(test)]
+    mod trace {
+        use super::{Trace, TraceFilter};
+        use crate::{
+            enums::{Direction, Instruction},
+            position::{Pose, Position},
+            rover::Rover,
+        };
+
+        #[test]
+        fn disabled_by_default() {
+            let mut trace = Trace::default();
+            trace.record(
+                0,
+                &Rover::new(1, Pose::new(Position::new(0, 0), Direction::North)),
+                &Instruction::Move,
+                &[],
+            );
+            assert!(trace.events().is_empty());
+        }
+
+        #[test]
+        fn filtered_events() {
+            let mut trace = Trace::new("kind=left".parse().unwrap());
+            let rover = Rover::new(1, Pose::new(Position::new(0, 0), Direction::North));
+            trace.record(0, &rover, &Instruction::Move, &[]);
+            trace.record(1, &rover, &Instruction::Left, &[]);
+            assert_eq!(trace.events().len(), 1);
+            assert_eq!(trace.events()[0].to_string(), "2 1 left 0 0 N");
+        }
+
+        #[test]
+        fn starting_pose() {
+            let mut trace = Trace::new(TraceFilter::default());
+            trace.start(&Rover::new(
+                2,
+                Pose::new(Position::new(1, 2), Direction::North),
+            ));
+            assert_eq!(trace.events()[0].to_string(), "0 2 start 1 2 N");
+        }
+
+        #[test]
+        fn tagged_cell() {
+            let mut trace = Trace::new(TraceFilter::default());
+            let rover = Rover::new(1, Pose::new(Position::new(0, 1), Direction::North));
+            trace.record(
+                0,
+                &rover,
+                &Instruction::Move,
+                &["slippery", "science-target"],
+            );
+            assert_eq!(
+                trace.events()[0].to_string(),
+                "1 1 move 0 1 N tags=slippery,science-target"
+            );
+        }
+    }
+}
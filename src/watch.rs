@@ -0,0 +1,328 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    enums::RoverErr,
+    expand::Defines,
+    input::{self, MissionText},
+    render::{render_frame, CLEAR_SCREEN},
+    simulation::{Outcome, RunCache, Simulation},
+};
+
+/// The gap between panels in the merged view
+const GUTTER: &str = "    ";
+
+/// The latest run of one of the watched files
+#[derive(Debug, Default)]
+struct Panel {
+    /// The files the run read, the instructions file followed by the world file it names if any
+    sources: Vec<PathBuf>,
+    /// When each of the sources was last modified as of the run, `None` if it has not been run
+    ///
+    /// A source that cannot be found has no time, so it is only run again once it can be.
+    modified: Option<Vec<Option<SystemTime>>>,
+    /// The file, the plateau as the run left it and how the run went, one item per line
+    lines: Vec<String>,
+    /// The warnings and error of the run, which are too long to sit in a panel
+    messages: Vec<String>,
+    /// The rovers of the run, so an edit only re-runs the rover blocks it touched
+    cache: RunCache,
+}
+
+/// Watches several instructions files, re-running each one when it or its world file changes
+///
+/// Every mission is drawn on a plateau panel of its own, and the panels are shown side by side.
+/// When nothing is shared between rovers, only the rover blocks that changed are executed again.
+#[derive(Debug)]
+pub struct Watch {
+    pub paths: Vec<PathBuf>,
+    pub simulation: Simulation,
+    pub defines: Defines,
+    panels: Vec<Panel>,
+}
+
+impl Watch {
+    pub fn new(paths: Vec<PathBuf>, simulation: Simulation, defines: Defines) -> Self {
+        let panels = paths
+            .iter()
+            .map(|path| Panel {
+                sources: vec![path.clone()],
+                ..Panel::default()
+            })
+            .collect();
+        Watch {
+            paths,
+            simulation,
+            defines,
+            panels,
+        }
+    }
+
+    /// Redraw the view whenever a file changes, forever or only once if `interval` is `None`
+    pub fn run(&mut self, interval: Option<Duration>) -> Result<(), RoverErr> {
+        loop {
+            if self.refresh() > 0 {
+                print!("{CLEAR_SCREEN}{}", self.view());
+            }
+
+            match interval {
+                Some(interval) => thread::sleep(interval),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Re-run the files modified since their last run, returning how many were
+    pub fn refresh(&mut self) -> usize {
+        let mut refreshed = 0;
+        for (path, panel) in self.paths.iter().zip(&mut self.panels) {
+            let sources = panel.sources.clone();
+            let modified = modified_times(&sources);
+            if panel.modified.as_ref() == Some(&modified) {
+                continue;
+            }
+            refreshed += 1;
+
+            let name = path.display().to_string();
+            panel.lines = vec![name.clone()];
+            panel.messages = Vec::new();
+            let result = panel.run(&self.simulation, &self.defines, path);
+            // The times are taken before the run, so an edit made while it runs is not missed
+            panel.modified = Some(match panel.sources == sources {
+                true => modified,
+                false => modified_times(&panel.sources),
+            });
+            let outcome = match result {
+                Ok(outcome) => outcome,
+                Err(err) => {
+                    panel.lines.push("error".to_string());
+                    panel.messages.push(format!("{name}: {err}"));
+                    continue;
+                }
+            };
+            match render_frame(outcome.world.bounderies, &outcome.world.rovers) {
+                Ok(frame) => panel.lines.extend(frame.lines().map(String::from)),
+                Err(err) => panel.messages.push(format!("{name}: {err}")),
+            }
+            panel
+                .lines
+                .push(match (&outcome.result, outcome.warnings.len()) {
+                    (Err(_), _) => "error".to_string(),
+                    (Ok(_), 0) => "ok".to_string(),
+                    (Ok(_), count) => format!("{count} warning(s)"),
+                });
+            for warning in &outcome.warnings {
+                panel.messages.push(format!("{name}: {warning}"));
+            }
+            if let Err(err) = outcome.result {
+                panel.messages.push(format!("{name}: {err}"));
+            }
+        }
+        refreshed
+    }
+
+    /// Every panel side by side, followed by the messages of every run
+    pub fn view(&self) -> String {
+        let height = self
+            .panels
+            .iter()
+            .map(|panel| panel.lines.len())
+            .max()
+            .unwrap_or_default();
+        let widths = self
+            .panels
+            .iter()
+            .map(|panel| {
+                panel
+                    .lines
+                    .iter()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut view = (0..height)
+            .map(|row| {
+                self.panels
+                    .iter()
+                    .zip(&widths)
+                    .map(|(panel, width)| {
+                        let line = panel.lines.get(row).map_or("", String::as_str);
+                        format!("{line:<width$}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(GUTTER)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        view.extend(
+            self.panels
+                .iter()
+                .flat_map(|panel| panel.messages.iter().cloned()),
+        );
+
+        let mut view = view.join("\n");
+        view.push('\n');
+        view
+    }
+}
+
+impl Panel {
+    /// Run a file, with the craters and tags of the world file it names if it names one
+    fn run(
+        &mut self,
+        simulation: &Simulation,
+        defines: &Defines,
+        path: &Path,
+    ) -> Result<Outcome, RoverErr> {
+        self.sources.truncate(1);
+        let texts = vec![MissionText {
+            path: Some(path.to_path_buf()),
+            text: fs::read_to_string(path).map_err(RoverErr::Opening)?,
+            origins: None,
+        }];
+        let mut texts = input::to_classic(texts, None)?;
+        if let Some(Ok(world_path)) = input::world_file(&texts[0], defines) {
+            self.sources.push(world_path);
+        }
+        let plateaus = input::resolve_worlds(&mut texts, defines)?;
+
+        let MissionText {
+            path,
+            text,
+            origins,
+        } = &texts[0];
+        let expansion = input::expand(text, path.as_deref(), origins.as_deref(), defines)?;
+        let mut outcome = simulation
+            .with_plateaus(&plateaus)
+            .run_incremental(&expansion.text, &mut self.cache);
+        outcome.result = outcome.result.map_err(|err| expansion.locate(err));
+        outcome.warnings = outcome
+            .warnings
+            .into_iter()
+            .map(|warning| expansion.locate_warning(warning))
+            .collect();
+        Ok(outcome)
+    }
+}
+
+/// When each file was last modified, `None` for a file that cannot be found
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod watch_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod refresh {
+        use std::{
+            env,
+            fs::{self, File},
+            path::Path,
+            time::Duration,
+        };
+
+        use super::Watch;
+        use crate::{expand::Defines, simulation::Simulation};
+
+        /// Rewrite a file and move its modified time on, as the file system may not tick between
+        /// writes
+        fn edit(path: &Path, text: &str) {
+            let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+            fs::write(path, text).unwrap();
+            File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(modified.unwrap() + Duration::from_secs(1)))
+                .unwrap();
+        }
+
+        #[test]
+        fn reruns_changed_files() {
+            let dir = env::temp_dir().join(format!("rover-watch-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let (first, second) = (dir.join("first.txt"), dir.join("second.txt"));
+            fs::write(&first, "2 2\n1 0 N\nM").unwrap();
+            fs::write(&second, "1 1\n1 1 N\nMM").unwrap();
+
+            let mut watch = Watch::new(
+                vec![first.clone(), second.clone()],
+                Simulation::default(),
+                Defines::new(),
+            );
+            assert_eq!(watch.refresh(), 2);
+            assert_eq!(watch.refresh(), 0);
+
+            let view = watch.view();
+            let lines = view.lines().collect::<Vec<_>>();
+            let width = first.display().to_string().len();
+            assert_eq!(
+                lines[0],
+                format!("{}    {}", first.display(), second.display())
+            );
+            assert_eq!(lines[2], format!("{:<width$}    ..", ".^."));
+            assert_eq!(lines[3], format!("{:<width$}    error", "..."));
+            assert_eq!(lines[4], "ok");
+            assert!(lines[5].starts_with(&format!("{}: Rover Error", second.display())));
+
+            // Only the file that changed is run again
+            edit(&second, "1 1\n1 1 N\nL");
+            assert_eq!(watch.refresh(), 1);
+            assert_eq!(
+                watch.view().lines().nth(1),
+                Some(format!("{:<width$}    .<", "...").as_str())
+            );
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn reruns_changed_world() {
+            let dir = env::temp_dir().join(format!("rover-watch-world-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let (mission, world) = (dir.join("mission.txt"), dir.join("mars.world"));
+            fs::write(&mission, "WORLD ${PLANET}.world\n1 0 N\nM").unwrap();
+            fs::write(&world, "2 2").unwrap();
+
+            let defines = [("PLANET".to_string(), "mars".to_string())]
+                .into_iter()
+                .collect();
+            let mut watch = Watch::new(vec![mission.clone()], Simulation::default(), defines);
+            assert_eq!(watch.refresh(), 1);
+            assert_eq!(watch.refresh(), 0);
+            assert_eq!(watch.view().lines().nth(2), Some(".^."));
+            assert_eq!(watch.view().lines().nth(4), Some("ok"));
+
+            // Landing on the new crater fails the mission
+            edit(&world, "2 2\ncrater 1,1");
+            assert_eq!(watch.refresh(), 1);
+            assert_eq!(watch.view().lines().nth(4), Some("error"));
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn missing_file_is_not_rerun() {
+            let path = env::temp_dir().join(format!("rover-watch-missing-{}", std::process::id()));
+
+            let mut watch = Watch::new(vec![path], Simulation::default(), Defines::new());
+            assert_eq!(watch.refresh(), 1);
+            assert_eq!(watch.refresh(), 0);
+        }
+    }
+}
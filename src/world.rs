@@ -0,0 +1,424 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    enums::{Autonomy, Direction, Recovery, RoverErr, SchemaVersion},
+    json::{self, Json},
+    position::{Pose, Position},
+    rng::Rng,
+    rover::Rover,
+    stochastic::StochasticModel,
+    terrain::{self, CellEvent},
+};
+
+/// State shared by every rover of a mission, which lasts between their executions
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct World {
+    pub bounderies: Position,
+    /// Every rover that has finished executing, in order of their IDs
+    pub rovers: Vec<Rover>,
+    /// Cells a rover can fall into
+    pub craters: HashSet<Position>,
+    /// Cells that report an event when a rover moves onto them, a cell may have several tags
+    pub tags: HashMap<Position, Vec<String>>,
+    /// Whether rovers that fall off the plateau or into a crater are lost, rather than failing
+    /// the mission
+    pub lost_rovers: bool,
+    /// Whether rovers may leave the plateau, as if it had no edges, this is not saved
+    pub unbounded: bool,
+    /// How many of the outermost rows and columns of the plateau rovers are warned about
+    /// entering, this is not saved
+    pub safety_margin: usize,
+    /// Where lost rovers were, and which way they were facing, when they moved to their doom
+    scents: HashSet<Pose>,
+    /// Every time a rover moved onto a tagged cell, this is not saved
+    pub events: Vec<CellEvent>,
+    /// Where anything random in the mission comes from, this is not saved
+    pub rng: Rng,
+    /// How likely things are to go wrong for the rovers, this is not saved
+    pub model: StochasticModel,
+    /// Whether rovers refuse fatal moves, and what they do instead, this is not saved
+    pub autonomy: Autonomy,
+    pub recovery: Recovery,
+}
+
+impl World {
+    pub fn new(craters: Vec<Position>, lost_rovers: bool) -> Self {
+        World {
+            craters: craters.into_iter().collect(),
+            lost_rovers,
+            ..World::default()
+        }
+    }
+
+    /// The edges of the plateau rovers must stay within, if it has any
+    pub fn boundery(&self) -> Option<Position> {
+        (!self.unbounded).then_some(self.bounderies)
+    }
+
+    pub fn is_crater(&self, position: Position) -> bool {
+        self.craters.contains(&position)
+    }
+
+    /// The tags of a cell, in the order they were given
+    pub fn tags_at(&self, position: Position) -> Vec<&str> {
+        self.tags
+            .get(&position)
+            .map_or(Vec::new(), |tags| tags.iter().map(String::as_str).collect())
+    }
+
+    /// Tag cells, after any tags they already have
+    pub fn add_tags(&mut self, tags: impl IntoIterator<Item = (Position, String)>) {
+        for (position, tag) in tags {
+            self.tags.entry(position).or_default().push(tag);
+        }
+    }
+
+    /// How many of the cells in a line ahead of the pose, up to `limit`, are neither craters nor
+    /// tagged, so a rover can cross them without anything happening
+    pub fn plain_cells_ahead(&self, pose: Pose, limit: usize) -> usize {
+        if self.craters.is_empty() && self.tags.is_empty() {
+            return limit;
+        }
+        (1..=limit)
+            .map(|step| pose.forwards(step as isize).position)
+            .take_while(|position| !self.is_crater(*position) && !self.tags.contains_key(position))
+            .count()
+    }
+
+    /// Whether a rover was lost moving forwards from this cell in this direction
+    pub fn has_scent(&self, pose: Pose) -> bool {
+        self.scents.contains(&pose)
+    }
+
+    /// Mark the cell a rover was lost from, so later rovers ignore the move that doomed it
+    pub fn leave_scent(&mut self, pose: Pose) {
+        self.scents.insert(pose);
+    }
+
+    /// Serialise the whole world as JSON, so it can be saved and picked up again later
+    ///
+    /// Cells are written in order of their position, so the same world is always saved the same.
+    pub fn to_json(&self, version: SchemaVersion) -> String {
+        let mut craters = self.craters.iter().collect::<Vec<&Position>>();
+        craters.sort();
+        let craters = craters
+            .into_iter()
+            .map(|Position { x, y }| format!("[{x}, {y}]"))
+            .collect::<Vec<String>>();
+        let mut tags = self.tags.iter().collect::<Vec<(&Position, &Vec<String>)>>();
+        tags.sort_by_key(|(position, _)| **position);
+        let tags = tags
+            .into_iter()
+            .flat_map(|(Position { x, y }, tags)| {
+                tags.iter()
+                    .map(move |tag| format!(r#"{{"x": {x}, "y": {y}, "tag": "{tag}"}}"#))
+            })
+            .collect::<Vec<String>>();
+        let mut scents = self.scents.iter().collect::<Vec<&Pose>>();
+        scents.sort_by_key(|pose| (pose.position, pose.facing.to_string()));
+        let scents = scents
+            .into_iter()
+            .map(|Pose { position, facing }| {
+                let Position { x, y } = position;
+                format!(r#"{{"x": {x}, "y": {y}, "facing": "{facing}"}}"#)
+            })
+            .collect::<Vec<String>>();
+        let rovers = self
+            .rovers
+            .iter()
+            .map(Rover::to_json)
+            .collect::<Vec<String>>();
+
+        format!(
+            "{{\n  \"schema_version\": {},\n  \"plateau\": [{}, {}],\n  \"lost_rovers\": {},\n  \"craters\": [{}],\n  \"tags\": [{}],\n  \"scents\": [{}],\n  \"rovers\": [{}]\n}}\n",
+            version.number(),
+            self.bounderies.x,
+            self.bounderies.y,
+            self.lost_rovers,
+            craters.join(", "),
+            tags.join(", "),
+            scents.join(", "),
+            list(&rovers),
+        )
+    }
+}
+
+impl World {
+    /// Read back a world saved with `to_json`, of any schema version that has one
+    pub fn from_json(input: &str) -> Result<Self, RoverErr> {
+        let json = json::parse(input).ok_or(RoverErr::WorldParse("invalid JSON"))?;
+        json.get("schema_version")
+            .and_then(Json::as_isize)
+            .and_then(|version| SchemaVersion::from_number(version as usize))
+            .ok_or(RoverErr::WorldParse("unknown schema version"))?;
+
+        let bounderies = json
+            .get("plateau")
+            .and_then(cell)
+            .ok_or(RoverErr::WorldParse("missing plateau"))?;
+        let lost_rovers = json
+            .get("lost_rovers")
+            .and_then(Json::as_bool)
+            .unwrap_or_default();
+        let craters = list_of(&json, "craters", cell)?.into_iter().collect();
+        let tags = list_of(&json, "tags", |tag| {
+            let name = tag.get("tag")?.as_str()?;
+            Some((
+                Position::new(tag.get("x")?.as_isize()?, tag.get("y")?.as_isize()?),
+                terrain::is_valid_tag(name).then(|| name.to_string())?,
+            ))
+        })?;
+        let scents = list_of(&json, "scents", pose)?.into_iter().collect();
+        let rovers = list_of(&json, "rovers", |rover| {
+            let mut parsed = Rover::new(
+                usize::try_from(rover.get("id")?.as_isize()?).ok()?,
+                pose(rover)?,
+            );
+            match rover.get("status")?.as_str()? {
+                "active" => (),
+                "halted" => parsed.halted = true,
+                "lost" => parsed.lost = true,
+                "degraded" => parsed.degraded = true,
+                _ => return None,
+            }
+            // Worlds saved before each flag was written only have the most serious one
+            let flag = |key, status| match rover.get(key) {
+                Some(flag) => flag.as_bool(),
+                None => Some(status),
+            };
+            parsed.halted = flag("halted", parsed.halted)?;
+            parsed.lost = flag("lost", parsed.lost)?;
+            parsed.degraded = flag("degraded", parsed.degraded)?;
+            // Worlds saved before rovers kept counters start them at zero
+            let count = |key| match rover.get(key) {
+                Some(count) => usize::try_from(count.as_isize()?).ok(),
+                None => Some(0),
+            };
+            parsed.odometer = count("odometer")?;
+            parsed.turns = count("turns")?;
+            parsed.since_service = count("since_service")?;
+            parsed.maintenance = match rover.get("maintenance") {
+                Some(maintenance) => Some(usize::try_from(maintenance.as_isize()?).ok()?),
+                None => None,
+            };
+            parsed.name = match rover.get("name") {
+                Some(name) => Some(name.as_str()?.to_string()),
+                None => None,
+            };
+            Some(parsed)
+        })?;
+
+        // The rovers are identified by their position in the mission
+        if rovers
+            .iter()
+            .enumerate()
+            .any(|(index, rover)| rover.id != index + 1)
+        {
+            return Err(RoverErr::WorldParse("rover IDs are not in order"));
+        }
+
+        let mut world = World {
+            bounderies,
+            rovers,
+            craters,
+            tags: HashMap::new(),
+            lost_rovers,
+            unbounded: false,
+            safety_margin: 0,
+            scents,
+            events: Vec::new(),
+            rng: Rng::default(),
+            model: StochasticModel::default(),
+            autonomy: Autonomy::default(),
+            recovery: Recovery::default(),
+        };
+        world.add_tags(tags);
+        Ok(world)
+    }
+}
+
+/// Read every item of a list that may be left out, failing if any item is malformed
+fn list_of<T>(
+    json: &Json,
+    key: &'static str,
+    item: impl Fn(&Json) -> Option<T>,
+) -> Result<Vec<T>, RoverErr> {
+    match json.get(key) {
+        Some(list) => list
+            .as_array()
+            .and_then(|items| items.iter().map(item).collect())
+            .ok_or(RoverErr::WorldParse(key)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn cell(json: &Json) -> Option<Position> {
+    match json.as_array()? {
+        [x, y] => Some(Position::new(x.as_isize()?, y.as_isize()?)),
+        _ => None,
+    }
+}
+
+/// A pose given as `"x"`, `"y"` and `"facing"` keys of an object
+fn pose(json: &Json) -> Option<Pose> {
+    Some(Pose::new(
+        Position::new(json.get("x")?.as_isize()?, json.get("y")?.as_isize()?),
+        direction(json.get("facing")?)?,
+    ))
+}
+
+fn direction(json: &Json) -> Option<Direction> {
+    match json.as_str()? {
+        "N" => Some(Direction::North),
+        "E" => Some(Direction::East),
+        "S" => Some(Direction::South),
+        "W" => Some(Direction::West),
+        _ => None,
+    }
+}
+
+/// Lay out a JSON list with one item per line
+fn list(items: &[String]) -> String {
+    match items.is_empty() {
+        true => String::new(),
+        false => format!("\n    {}\n  ", items.join(",\n    ")),
+    }
+}
+
+/// Parse a cell given as `X,Y`
+pub fn parse_cell(input: &str) -> Result<Position, String> {
+    input
+        .split_once(',')
+        .and_then(|(x, y)| {
+            Some(Position::new(
+                x.trim().parse().ok()?,
+                y.trim().parse().ok()?,
+            ))
+        })
+        .ok_or_else(|| format!("expected `X,Y`, found `{input}`"))
+}
+
+#[cfg(test)]
+mod world_module {
+    use super::*;
+
+    #[cfg(test)]
+    mod to_json {
+        use super::World;
+        use crate::{
+            enums::{Direction, SchemaVersion},
+            position::{Pose, Position},
+            rover::Rover,
+        };
+
+        #[test]
+        fn valid_input() {
+            let mut world = World::new(vec![Position::new(2, 2)], true);
+            world.bounderies = Position::new(5, 5);
+            world.leave_scent(Pose::new(Position::new(0, 5), Direction::North));
+            world.rovers = vec![Rover::new(
+                1,
+                Pose::new(Position::new(0, 5), Direction::North),
+            )];
+            world.rovers[0].lost = true;
+
+            assert_eq!(
+                world.to_json(SchemaVersion::V2),
+                r#"{
+  "schema_version": 2,
+  "plateau": [5, 5],
+  "lost_rovers": true,
+  "craters": [[2, 2]],
+  "tags": [],
+  "scents": [{"x": 0, "y": 5, "facing": "N"}],
+  "rovers": [
+    {"id": 1, "x": 0, "y": 5, "facing": "N", "status": "lost", "halted": false, "lost": true, "degraded": false, "odometer": 0, "turns": 0}
+  ]
+}
+"#
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod plain_cells_ahead {
+        use super::World;
+        use crate::{
+            enums::Direction,
+            position::{Pose, Position},
+        };
+
+        #[test]
+        fn stops_before_features() {
+            let mut world = World::new(vec![Position::new(0, 6)], false);
+            let pose = Pose::new(Position::new(0, 0), Direction::North);
+            assert_eq!(world.plain_cells_ahead(pose, 10), 5);
+
+            world.add_tags([(Position::new(0, 3), "slippery".to_string())]);
+            assert_eq!(world.plain_cells_ahead(pose, 10), 2);
+            assert_eq!(world.plain_cells_ahead(pose, 1), 1);
+        }
+    }
+
+    #[cfg(test)]
+    mod from_json {
+        use super::World;
+        use crate::{
+            enums::{Direction, RoverErr, SchemaVersion},
+            position::{Pose, Position},
+            rover::Rover,
+        };
+
+        #[test]
+        fn round_trip() {
+            let mut world = World::new(vec![Position::new(2, 2)], true);
+            world.bounderies = Position::new(5, 5);
+            world.leave_scent(Pose::new(Position::new(0, 5), Direction::North));
+            world.rovers = vec![
+                Rover::new(1, Pose::new(Position::new(0, 5), Direction::North)),
+                Rover::new(2, Pose::new(Position::new(3, -1), Direction::West)),
+            ];
+            world.rovers[1].halted = true;
+            world.rovers[1].degraded = true;
+            world.rovers[1].odometer = 7;
+            world.rovers[1].turns = 3;
+            world.rovers[1].maintenance = Some(5);
+            world.rovers[1].since_service = 2;
+            world.rovers[1].name = Some("spirit".to_string());
+            world.add_tags([
+                (Position::new(4, 4), "base".to_string()),
+                (Position::new(1, 1), "slippery".to_string()),
+                (Position::new(1, 1), "science-target".to_string()),
+            ]);
+
+            let result = World::from_json(&world.to_json(SchemaVersion::V2));
+            assert_eq!(result.unwrap(), world);
+        }
+
+        #[test]
+        fn rovers_out_of_order() {
+            let result = World::from_json(
+                r#"{"schema_version": 2, "plateau": [5, 5], "rovers": [
+                    {"id": 2, "x": 0, "y": 0, "facing": "N", "status": "active"}
+                ]}"#,
+            );
+            assert!(matches!(result, Err(RoverErr::WorldParse(_))));
+        }
+    }
+
+    #[cfg(test)]
+    mod parse_cell {
+        use super::parse_cell;
+        use crate::position::Position;
+
+        #[test]
+        fn valid_input() {
+            assert_eq!(parse_cell("2,-3"), Ok(Position::new(2, -3)));
+        }
+
+        #[test]
+        fn missing_comma() {
+            assert!(parse_cell("2 3").is_err());
+        }
+    }
+}